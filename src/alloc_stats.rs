@@ -0,0 +1,138 @@
+// Heap usage per part, counted by a global allocator that only exists with the `alloc-stats` feature. Counts are kept
+// per thread so parts running side by side don't see each other's allocations.
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AllocStats {
+    // Most bytes held at once above what was already held when measuring started
+    pub peak_bytes: usize,
+    pub allocations: u64,
+}
+
+#[cfg(feature = "alloc-stats")]
+mod counting {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    thread_local! {
+        // Signed, since memory allocated on one thread can be freed on another
+        static CURRENT_BYTES: Cell<isize> = const { Cell::new(0) };
+        static PEAK_BYTES: Cell<isize> = const { Cell::new(0) };
+        static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+    }
+
+    pub struct CountingAllocator;
+
+    // Thread locals can be gone while a thread shuts down, and those last allocations don't matter
+    fn record(bytes_change: isize, allocations: u64) {
+        let _ = CURRENT_BYTES.try_with(|current| {
+            let bytes = current.get() + bytes_change;
+            current.set(bytes);
+            let _ = PEAK_BYTES.try_with(|peak| peak.set(peak.get().max(bytes)));
+        });
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + allocations));
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let pointer = System.alloc(layout);
+            if !pointer.is_null() {
+                record(layout.size() as isize, 1);
+            }
+            pointer
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let pointer = System.alloc_zeroed(layout);
+            if !pointer.is_null() {
+                record(layout.size() as isize, 1);
+            }
+            pointer
+        }
+
+        unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+            System.dealloc(pointer, layout);
+            record(-(layout.size() as isize), 0);
+        }
+
+        unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_pointer = System.realloc(pointer, layout, new_size);
+            if !new_pointer.is_null() {
+                record(new_size as isize - layout.size() as isize, 1);
+            }
+            new_pointer
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    // (current bytes, allocations so far), after restarting the peak from the current usage
+    pub fn start() -> (isize, u64) {
+        let current = CURRENT_BYTES.with(|current| current.get());
+        PEAK_BYTES.with(|peak| peak.set(current));
+        (current, ALLOCATIONS.with(|count| count.get()))
+    }
+
+    pub fn since((start_bytes, start_allocations): (isize, u64)) -> super::AllocStats {
+        super::AllocStats {
+            peak_bytes: (PEAK_BYTES.with(|peak| peak.get()) - start_bytes).max(0) as usize,
+            allocations: ALLOCATIONS.with(|count| count.get()) - start_allocations,
+        }
+    }
+}
+
+// Runs `f` on this thread, along with what it allocated if allocations are being counted
+#[cfg(feature = "alloc-stats")]
+pub fn measure<R, F: FnOnce() -> R>(f: F) -> (R, Option<AllocStats>) {
+    let start = counting::start();
+    let result = f();
+    (result, Some(counting::since(start)))
+}
+
+#[cfg(not(feature = "alloc-stats"))]
+pub fn measure<R, F: FnOnce() -> R>(f: F) -> (R, Option<AllocStats>) {
+    (f(), None)
+}
+
+// Like `1.5 MiB`, for showing peak usage
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn measures_allocations() {
+        let (length, stats) = measure(|| {
+            let values: Vec<u64> = (0..1000).collect();
+            drop(vec![0u8; 64]);
+            values.len()
+        });
+        assert_eq!(1000, length);
+        assert_eq!(cfg!(feature = "alloc-stats"), stats.is_some());
+        if let Some(stats) = stats {
+            assert!(stats.peak_bytes >= 8000, "peak of {} bytes", stats.peak_bytes);
+            assert!(stats.allocations >= 2);
+        }
+    }
+
+    #[test]
+    pub fn formats_bytes() {
+        assert_eq!("512 B", format_bytes(512));
+        assert_eq!("1.5 KiB", format_bytes(1536));
+        assert_eq!("3.0 MiB", format_bytes(3 * 1024 * 1024));
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use crate::cli::BenchOptions;
+use crate::days::PartOutcome;
+use crate::runner::{describe_error, load_input, select_days};
+
+const REPORT_HEADER: &str = "day,stage,iterations,min_ns,median_ns,max_ns";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StageTiming {
+    pub day: u32,
+    pub stage: String,
+    pub iterations: u32,
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl StageTiming {
+    fn from_samples(day: u32, stage: &str, samples: &mut [Duration]) -> StageTiming {
+        samples.sort();
+        StageTiming {
+            day,
+            stage: stage.to_string(),
+            iterations: samples.len() as u32,
+            min: samples[0],
+            median: samples[samples.len() / 2],
+            max: samples[samples.len() - 1],
+        }
+    }
+
+    fn to_csv_row(&self) -> String {
+        format!("{},{},{},{},{},{}", self.day, self.stage, self.iterations, self.min.as_nanos(), self.median.as_nanos(), self.max.as_nanos())
+    }
+
+    fn from_csv_row(line: &str) -> Result<StageTiming, String> {
+        let columns: Vec<&str> = line.split(',').collect();
+        if columns.len() != 6 {
+            return Err(format!("Expected 6 columns in report row '{}'", line));
+        }
+
+        let parse_nanos = |value: &str| value.parse::<u64>()
+            .map(Duration::from_nanos)
+            .map_err(|_| format!("Could not parse duration '{}' in report row '{}'", value, line));
+
+        Ok(StageTiming {
+            day: columns[0].parse().map_err(|_| format!("Could not parse day in report row '{}'", line))?,
+            stage: columns[1].to_string(),
+            iterations: columns[2].parse().map_err(|_| format!("Could not parse iterations in report row '{}'", line))?,
+            min: parse_nanos(columns[3])?,
+            median: parse_nanos(columns[4])?,
+            max: parse_nanos(columns[5])?,
+        })
+    }
+}
+
+pub fn write_report(path: &Path, timings: &[StageTiming]) -> Result<(), String> {
+    let mut lines = vec![REPORT_HEADER.to_string()];
+    lines.extend(timings.iter().map(|timing| timing.to_csv_row()));
+    fs::write(path, lines.join("\n") + "\n").map_err(|err| format!("Could not write report to {}: {}", path.display(), err))
+}
+
+pub fn read_report(path: &Path) -> Result<Vec<StageTiming>, String> {
+    let contents = fs::read_to_string(path).map_err(|err| format!("Could not read report {}: {}", path.display(), err))?;
+    contents.lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(StageTiming::from_csv_row)
+        .collect()
+}
+
+fn is_regression(current: &StageTiming, baseline: &StageTiming, threshold_percent: f64) -> bool {
+    current.median.as_secs_f64() > baseline.median.as_secs_f64() * (1.0 + threshold_percent / 100.0)
+}
+
+pub fn find_regressions<'a>(current: &'a [StageTiming], baseline: &[StageTiming], threshold_percent: f64) -> Vec<(&'a StageTiming, StageTiming)> {
+    let baseline_by_stage: HashMap<(u32, &str), &StageTiming> = baseline.iter()
+        .map(|timing| ((timing.day, timing.stage.as_str()), timing))
+        .collect();
+
+    current.iter().filter_map(|timing| {
+        let baseline_timing = baseline_by_stage.get(&(timing.day, timing.stage.as_str()))?;
+        if is_regression(timing, baseline_timing, threshold_percent) {
+            Some((timing, (*baseline_timing).clone()))
+        } else {
+            None
+        }
+    }).collect()
+}
+
+pub fn bench(options: &BenchOptions) -> Result<(), String> {
+    let mut timings: Vec<StageTiming> = vec![];
+
+    for day in select_days(&options.run.days)? {
+        println!("Day {}", day.number);
+        let input = load_input(&day, &options.run).map_err(|error| describe_error(&day, &error))?;
+        let parts = options.run.parts();
+
+        let mut parse_samples: Vec<Duration> = vec![];
+        let mut part_samples: Vec<Vec<Duration>> = vec![vec![]; parts.len()];
+        for _ in 0..options.iterations {
+            let result = day.run(input.as_str(), &parts, &options.run.settings()).map_err(|error| describe_error(&day, &error))?;
+            parse_samples.push(result.parse_elapsed);
+            for (i, part_result) in result.parts.iter().enumerate() {
+                // How long it took to fail isn't a timing worth comparing
+                if let PartOutcome::Failed(error) = &part_result.outcome {
+                    return Err(format!("Day {}, part {}: {}", day.number, part_result.part.number(), error));
+                }
+                part_samples[i].push(part_result.elapsed);
+            }
+        }
+
+        let mut day_timings = vec![StageTiming::from_samples(day.number, "parse", &mut parse_samples)];
+        for (part, samples) in parts.iter().zip(part_samples.iter_mut()) {
+            day_timings.push(StageTiming::from_samples(day.number, format!("part{}", part.number()).as_str(), samples));
+        }
+
+        for timing in &day_timings {
+            println!("  {:<6} min {:>12?}  median {:>12?}  max {:>12?}", timing.stage, timing.min, timing.median, timing.max);
+        }
+
+        timings.extend(day_timings);
+    }
+
+    if let Some(report_path) = &options.report_path {
+        write_report(report_path, &timings)?;
+        println!("Wrote report to {}", report_path.display());
+    }
+
+    if let Some(baseline_path) = &options.baseline_path {
+        let baseline = read_report(baseline_path)?;
+        let regressions = find_regressions(&timings, &baseline, options.threshold_percent);
+        for (current, baseline_timing) in &regressions {
+            println!("Regression: day {} {} median went from {:?} to {:?}", current.day, current.stage, baseline_timing.median, current.median);
+        }
+
+        if !regressions.is_empty() {
+            return Err(format!("{} stage(s) are more than {}% slower than the baseline", regressions.len(), options.threshold_percent));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn timing(day: u32, stage: &str, median_ms: u64) -> StageTiming {
+        StageTiming {
+            day,
+            stage: stage.to_string(),
+            iterations: 3,
+            min: Duration::from_millis(median_ms - 1),
+            median: Duration::from_millis(median_ms),
+            max: Duration::from_millis(median_ms + 1),
+        }
+    }
+
+    #[test]
+    pub fn csv_round_trip() {
+        let original = timing(15, "part2", 40);
+        assert_eq!(Ok(original.clone()), StageTiming::from_csv_row(original.to_csv_row().as_str()));
+    }
+
+    #[test]
+    pub fn regressions() {
+        let baseline = vec![timing(1, "part1", 100), timing(2, "part1", 100)];
+        let current = vec![timing(1, "part1", 105), timing(2, "part1", 120), timing(3, "part1", 500)];
+        let regressions = find_regressions(&current, &baseline, 10.0);
+        assert_eq!(1, regressions.len());
+        assert_eq!(2, regressions[0].0.day);
+    }
+}
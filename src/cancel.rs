@@ -0,0 +1,41 @@
+use std::time::{Duration, Instant};
+
+// Handed to solvers so long searches can notice the runner has run out of patience and return early.
+// Whatever they return after that is thrown away, so any answer will do.
+#[derive(Copy, Clone, Debug)]
+pub struct CancellationToken {
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    pub fn never() -> CancellationToken {
+        CancellationToken {
+            deadline: None,
+        }
+    }
+
+    pub fn after(budget: Duration) -> CancellationToken {
+        CancellationToken {
+            deadline: Some(Instant::now() + budget),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        match self.deadline {
+            Some(deadline) => Instant::now() >= deadline,
+            None => false
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn cancels_after_budget() {
+        assert!(!CancellationToken::never().is_cancelled());
+        assert!(CancellationToken::after(Duration::ZERO).is_cancelled());
+        assert!(!CancellationToken::after(Duration::from_secs(60)).is_cancelled());
+    }
+}
@@ -0,0 +1,537 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::common::numeric::Arithmetic;
+use crate::days::RunSettings;
+use crate::solution::Part;
+
+pub const USAGE: &str = "Usage: advent-2022 [run|bench|verify] [options]
+       advent-2022 new-day <N>    Create src/days/dayN.rs from a template and register it
+
+Options:
+    --day <N>        Run only day N (can be repeated)
+    --part <1|2>     Run only the given part
+    --input <PATH>   Read the puzzle input from PATH instead of input/dayN.txt (requires a single --day)
+    --all            Run every registered day, including the slow ones
+    --jobs <N>       Run up to N days at once, and both parts of a day side by side (default 1)
+    --format <FMT>   Print results as `text` (default) or `json`, one object per day and part
+    --timeout <SECS> Give up on a part once it has run for SECS seconds and report it as timed out
+    --arithmetic <MODE>
+                     Run the numeric solvers (days 11, 20 and 21) with `native` 128-bit integers (default),
+                     `checked` ones that report overflow as an error, or `big` arbitrary-precision ones
+    --render <DIR>   Save pictures of the simulations in days 9, 10, 14, 17, 22 and 23 into DIR, as dayN.png or an
+                     animated dayN.gif. With `-` the last picture of each day is printed instead.
+    --trace <PATH>   Write the VM's state during every cycle of day 10's program to PATH as CSV (requires a single --day)
+    --break <BREAKPOINT>
+                     Stop the traced program once at `cycle=N`, `pc=N` or a register test like `x>=5`, and print
+                     the registers there (can be repeated, requires --trace)
+    -h, --help       Show this message
+
+Bench options:
+    --iterations <N>     Number of times to run each day (default 10)
+    --report <PATH>      Write the timings as CSV to PATH
+    --baseline <PATH>    Compare median timings against a report written by an earlier run
+    --threshold <PCT>    Allowed slowdown against the baseline in percent (default 10)
+
+Verify options (every day is checked unless --day is given):
+    --answers <PATH>     Read the expected answers from PATH (default answers.toml)
+
+Build with `--features alloc-stats` to also report each part's peak heap usage and allocation count.
+Missing inputs are downloaded into input/ when AOC_SESSION holds the session cookie.
+Set AOC_BASE_URL to download from somewhere other than https://adventofcode.com.";
+
+const DEFAULT_BENCH_ITERATIONS: u32 = 10;
+const DEFAULT_REGRESSION_THRESHOLD_PERCENT: f64 = 10.0;
+const DEFAULT_ANSWERS_PATH: &str = "answers.toml";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DaySelection {
+    // Every day except the ones that are too slow to run every time
+    Default,
+    All,
+    Only(Vec<u32>),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RenderTarget {
+    Terminal,
+    Directory(PathBuf),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RunOptions {
+    pub days: DaySelection,
+    pub part: Option<Part>,
+    pub input_path: Option<PathBuf>,
+    pub jobs: usize,
+    pub format: OutputFormat,
+    pub part_timeout: Option<Duration>,
+    pub arithmetic: Arithmetic,
+    pub render: Option<RenderTarget>,
+    pub trace_path: Option<PathBuf>,
+    pub breakpoints: Vec<String>,
+}
+
+impl RunOptions {
+    pub fn is_parallel(&self) -> bool {
+        self.jobs > 1
+    }
+
+    pub fn settings(&self) -> RunSettings {
+        RunSettings {
+            parallel_parts: self.is_parallel(),
+            part_timeout: self.part_timeout,
+            arithmetic: self.arithmetic,
+        }
+    }
+
+    pub fn parts(&self) -> Vec<Part> {
+        match self.part {
+            Some(part) => vec![part],
+            None => Part::all()
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchOptions {
+    pub run: RunOptions,
+    pub iterations: u32,
+    pub report_path: Option<PathBuf>,
+    pub baseline_path: Option<PathBuf>,
+    pub threshold_percent: f64,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerifyOptions {
+    pub run: RunOptions,
+    pub answers_path: PathBuf,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Run(RunOptions),
+    Bench(BenchOptions),
+    Verify(VerifyOptions),
+    NewDay(u32),
+    Help,
+}
+
+// Flags that only make sense for one command, collected by name so that command can interpret them
+type ExtraFlags = HashMap<&'static str, String>;
+
+fn split_flag(arg: &str) -> (&str, Option<&str>) {
+    match arg.split_once('=') {
+        Some((flag, value)) => (flag, Some(value)),
+        None => (arg, None)
+    }
+}
+
+fn flag_value<I>(flag: &str, inline_value: Option<&str>, args: &mut I) -> Result<String, String>
+    where I: Iterator<Item=String> {
+    match inline_value {
+        Some(value) => Ok(value.to_string()),
+        None => args.next().ok_or_else(|| format!("Missing value for {}", flag))
+    }
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<u32, String> {
+    value.parse().map_err(|_| format!("Expected a number for {}, got '{}'", flag, value))
+}
+
+fn parse_options<I>(mut args: I, extra_flag_names: &[&'static str]) -> Result<Option<(RunOptions, ExtraFlags)>, String>
+    where I: Iterator<Item=String> {
+    let mut days: Vec<u32> = vec![];
+    let mut is_all = false;
+    let mut part: Option<Part> = None;
+    let mut input_path: Option<PathBuf> = None;
+    let mut jobs = 1;
+    let mut format = OutputFormat::Text;
+    let mut part_timeout: Option<Duration> = None;
+    let mut arithmetic = Arithmetic::Native;
+    let mut render: Option<RenderTarget> = None;
+    let mut trace_path: Option<PathBuf> = None;
+    let mut breakpoints: Vec<String> = vec![];
+    let mut extra_flags = ExtraFlags::new();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = split_flag(arg.as_str());
+        match flag {
+            "--day" => {
+                let value = flag_value(flag, inline_value, &mut args)?;
+                days.push(parse_number(flag, value.as_str())?);
+            }
+            "--part" => {
+                let value = flag_value(flag, inline_value, &mut args)?;
+                let part_number = parse_number(flag, value.as_str())?;
+                part = Some(Part::from_number(part_number).ok_or_else(|| format!("There is no part {}", part_number))?);
+            }
+            "--input" => {
+                input_path = Some(PathBuf::from(flag_value(flag, inline_value, &mut args)?));
+            }
+            "--jobs" => {
+                let value = flag_value(flag, inline_value, &mut args)?;
+                jobs = parse_number(flag, value.as_str())? as usize;
+            }
+            "--format" => {
+                format = match flag_value(flag, inline_value, &mut args)?.as_str() {
+                    "text" => OutputFormat::Text,
+                    "json" => OutputFormat::Json,
+                    other => return Err(format!("Unknown format '{}', expected text or json", other))
+                };
+            }
+            "--timeout" => {
+                let value = flag_value(flag, inline_value, &mut args)?;
+                let seconds: f64 = value.parse().map_err(|_| format!("Expected a number of seconds for --timeout, got '{}'", value))?;
+                part_timeout = Some(Duration::try_from_secs_f64(seconds).ok().filter(|timeout| !timeout.is_zero())
+                    .ok_or_else(|| "--timeout must be more than 0 seconds".to_string())?);
+            }
+            "--arithmetic" => {
+                let value = flag_value(flag, inline_value, &mut args)?;
+                arithmetic = Arithmetic::from_name(value.as_str())
+                    .ok_or_else(|| format!("Unknown arithmetic '{}', expected native, checked or big", value))?;
+            }
+            "--render" => {
+                render = Some(match flag_value(flag, inline_value, &mut args)?.as_str() {
+                    "-" => RenderTarget::Terminal,
+                    path => RenderTarget::Directory(PathBuf::from(path))
+                });
+            }
+            "--trace" => {
+                trace_path = Some(PathBuf::from(flag_value(flag, inline_value, &mut args)?));
+            }
+            "--break" => {
+                breakpoints.push(flag_value(flag, inline_value, &mut args)?);
+            }
+            "--all" => is_all = true,
+            "-h" | "--help" => return Ok(None),
+            _ => {
+                match extra_flag_names.iter().find(|&&name| name == flag) {
+                    Some(&name) => {
+                        extra_flags.insert(name, flag_value(flag, inline_value, &mut args)?);
+                    }
+                    None => return Err(format!("Unknown argument '{}'", arg))
+                }
+            }
+        }
+    }
+
+    if is_all && !days.is_empty() {
+        return Err("--all cannot be combined with --day".to_string());
+    }
+
+    if input_path.is_some() && days.len() != 1 {
+        return Err("--input requires exactly one --day".to_string());
+    }
+
+    if trace_path.is_some() && days.len() != 1 {
+        return Err("--trace requires exactly one --day".to_string());
+    }
+
+    if !breakpoints.is_empty() && trace_path.is_none() {
+        return Err("--break requires --trace".to_string());
+    }
+
+    if jobs == 0 {
+        return Err("--jobs must be at least 1".to_string());
+    }
+
+    // Pictures in the terminal would get in the way of reading one JSON object per line
+    if render == Some(RenderTarget::Terminal) && format == OutputFormat::Json {
+        return Err("--render - cannot be combined with --format json".to_string());
+    }
+
+    let days = if is_all {
+        DaySelection::All
+    } else if days.is_empty() {
+        DaySelection::Default
+    } else {
+        DaySelection::Only(days)
+    };
+
+    Ok(Some((RunOptions {
+        days,
+        part,
+        input_path,
+        jobs,
+        format,
+        part_timeout,
+        arithmetic,
+        render,
+        trace_path,
+        breakpoints,
+    }, extra_flags)))
+}
+
+fn parse_run<I>(args: I) -> Result<Command, String>
+    where I: Iterator<Item=String> {
+    match parse_options(args, &[])? {
+        Some((options, _)) => Ok(Command::Run(options)),
+        None => Ok(Command::Help)
+    }
+}
+
+fn parse_bench<I>(args: I) -> Result<Command, String>
+    where I: Iterator<Item=String> {
+    let (run, extra_flags) = match parse_options(args, &["--iterations", "--report", "--baseline", "--threshold"])? {
+        Some(parsed) => parsed,
+        None => return Ok(Command::Help)
+    };
+
+    let iterations = match extra_flags.get("--iterations") {
+        Some(value) => parse_number("--iterations", value)?,
+        None => DEFAULT_BENCH_ITERATIONS
+    };
+
+    if iterations == 0 {
+        return Err("--iterations must be at least 1".to_string());
+    }
+
+    if run.is_parallel() {
+        return Err("--jobs cannot be used with bench, the days would slow each other down".to_string());
+    }
+
+    if run.format != OutputFormat::Text {
+        return Err("--format is only supported by run".to_string());
+    }
+
+    if run.part_timeout.is_some() {
+        return Err("--timeout cannot be used with bench".to_string());
+    }
+
+    if run.render.is_some() {
+        return Err("--render is only supported by run".to_string());
+    }
+
+    if run.trace_path.is_some() {
+        return Err("--trace is only supported by run".to_string());
+    }
+
+    let threshold_percent = match extra_flags.get("--threshold") {
+        Some(value) => value.parse().map_err(|_| format!("Expected a number for --threshold, got '{}'", value))?,
+        None => DEFAULT_REGRESSION_THRESHOLD_PERCENT
+    };
+
+    Ok(Command::Bench(BenchOptions {
+        run,
+        iterations,
+        report_path: extra_flags.get("--report").map(PathBuf::from),
+        baseline_path: extra_flags.get("--baseline").map(PathBuf::from),
+        threshold_percent,
+    }))
+}
+
+fn parse_verify<I>(args: I) -> Result<Command, String>
+    where I: Iterator<Item=String> {
+    let (run, extra_flags) = match parse_options(args, &["--answers"])? {
+        Some(parsed) => parsed,
+        None => return Ok(Command::Help)
+    };
+
+    // Inputs to check are listed in the answers file, next to the answers they should produce
+    if run.input_path.is_some() {
+        return Err("--input cannot be used with verify, add the input to the answers file instead".to_string());
+    }
+
+    if run.format != OutputFormat::Text {
+        return Err("--format is only supported by run".to_string());
+    }
+
+    if run.render.is_some() {
+        return Err("--render is only supported by run".to_string());
+    }
+
+    if run.trace_path.is_some() {
+        return Err("--trace is only supported by run".to_string());
+    }
+
+    Ok(Command::Verify(VerifyOptions {
+        run,
+        answers_path: PathBuf::from(extra_flags.get("--answers").map(|path| path.as_str()).unwrap_or(DEFAULT_ANSWERS_PATH)),
+    }))
+}
+
+fn parse_new_day<I>(mut args: I) -> Result<Command, String>
+    where I: Iterator<Item=String> {
+    let day = match (args.next(), args.next()) {
+        (Some(day), None) => parse_number("new-day", day.as_str())?,
+        _ => return Err("new-day expects exactly one day number".to_string())
+    };
+
+    if !(1..=25).contains(&day) {
+        return Err(format!("There is no day {} in the calendar", day));
+    }
+
+    Ok(Command::NewDay(day))
+}
+
+pub fn parse_args<I>(args: I) -> Result<Command, String>
+    where I: IntoIterator<Item=String> {
+    let mut args = args.into_iter().peekable();
+
+    match args.peek().map(|arg| arg.as_str()) {
+        Some("run") => {
+            args.next();
+            parse_run(args)
+        }
+        Some("bench") => {
+            args.next();
+            parse_bench(args)
+        }
+        Some("verify") => {
+            args.next();
+            parse_verify(args)
+        }
+        Some("new-day") => {
+            args.next();
+            parse_new_day(args)
+        }
+        Some("help") => Ok(Command::Help),
+        // Running is the default, so `advent-2022 --day 15` works too
+        Some(arg) if arg.starts_with('-') => parse_run(args),
+        None => parse_run(args),
+        Some(arg) => Err(format!("Unknown command '{}'", arg))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Command, String> {
+        parse_args(args.split_ascii_whitespace().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    pub fn run_single_day() {
+        assert_eq!(Ok(Command::Run(RunOptions {
+            days: DaySelection::Only(vec![15]),
+            part: Some(Part::Two),
+            input_path: Some(PathBuf::from("path/to/file")),
+            jobs: 1,
+            format: OutputFormat::Json,
+            part_timeout: None,
+            arithmetic: Arithmetic::Native,
+            render: None,
+            trace_path: None,
+            breakpoints: vec![],
+        })), parse("run --day 15 --part 2 --input path/to/file --format json"));
+    }
+
+    #[test]
+    pub fn run_defaults() {
+        let expected = Ok(Command::Run(RunOptions {
+            days: DaySelection::Default,
+            part: None,
+            input_path: None,
+            jobs: 1,
+            format: OutputFormat::Text,
+            part_timeout: None,
+            arithmetic: Arithmetic::Native,
+            render: None,
+            trace_path: None,
+            breakpoints: vec![],
+        }));
+        assert_eq!(expected, parse(""));
+        assert_eq!(expected, parse("run"));
+    }
+
+    #[test]
+    pub fn invalid_combinations() {
+        assert!(parse("run --all --day 1").is_err());
+        assert!(parse("run --input file.txt").is_err());
+        assert!(parse("run --part 3").is_err());
+        assert!(parse("jump").is_err());
+        assert!(parse("run --iterations 5").is_err());
+        assert!(parse("bench --iterations 0").is_err());
+        assert!(parse("verify --day 1 --input file.txt").is_err());
+        assert!(parse("run --jobs 0").is_err());
+        assert!(parse("bench --jobs 4").is_err());
+        assert!(parse("run --format yaml").is_err());
+        assert!(parse("run --timeout 0").is_err());
+        assert!(parse("run --timeout soon").is_err());
+        assert!(parse("run --arithmetic float").is_err());
+        assert!(parse("bench --timeout 5").is_err());
+        assert!(parse("verify --format json").is_err());
+        assert!(parse("run --render - --format json").is_err());
+        assert!(parse("bench --render frames").is_err());
+        assert!(parse("run --trace trace.csv").is_err());
+        assert!(parse("run --day 10 --break cycle=20").is_err());
+        assert!(parse("verify --day 10 --trace trace.csv").is_err());
+        assert!(parse("new-day").is_err());
+        assert!(parse("new-day 26").is_err());
+        assert!(parse("new-day 24 25").is_err());
+    }
+
+    #[test]
+    pub fn render_targets() {
+        let render = |args: &str| match parse(args) {
+            Ok(Command::Run(options)) => options.render,
+            _ => None
+        };
+        assert_eq!(Some(RenderTarget::Directory(PathBuf::from("frames"))), render("run --day 14 --render frames"));
+        assert_eq!(Some(RenderTarget::Terminal), render("run --render=-"));
+    }
+
+    #[test]
+    pub fn trace_options() {
+        let trace = |args: &str| match parse(args) {
+            Ok(Command::Run(options)) => Some((options.trace_path, options.breakpoints)),
+            _ => None
+        };
+        assert_eq!(Some((Some(PathBuf::from("trace.csv")), vec!["cycle=20".to_string(), "x>=5".to_string()])),
+                   trace("run --day 10 --trace trace.csv --break cycle=20 --break=x>=5"));
+    }
+
+    #[test]
+    pub fn bench_options() {
+        assert_eq!(Ok(Command::Bench(BenchOptions {
+            run: RunOptions {
+                days: DaySelection::All,
+                part: None,
+                input_path: None,
+                jobs: 1,
+                format: OutputFormat::Text,
+                part_timeout: None,
+                arithmetic: Arithmetic::Native,
+                render: None,
+                trace_path: None,
+                breakpoints: vec![],
+            },
+            iterations: 3,
+            report_path: Some(PathBuf::from("bench.csv")),
+            baseline_path: None,
+            threshold_percent: 25.0,
+        })), parse("bench --all --iterations 3 --report bench.csv --threshold=25"));
+    }
+
+    #[test]
+    pub fn new_day() {
+        assert_eq!(Ok(Command::NewDay(24)), parse("new-day 24"));
+    }
+
+    #[test]
+    pub fn verify_options() {
+        assert_eq!(Ok(Command::Verify(VerifyOptions {
+            run: RunOptions {
+                days: DaySelection::Only(vec![10]),
+                part: None,
+                input_path: None,
+                jobs: 4,
+                format: OutputFormat::Text,
+                part_timeout: Some(Duration::from_millis(2500)),
+                arithmetic: Arithmetic::Big,
+                render: None,
+                trace_path: None,
+                breakpoints: vec![],
+            },
+            answers_path: PathBuf::from("answers.toml"),
+        })), parse("verify --day 10 --jobs 4 --timeout 2.5 --arithmetic big"));
+    }
+}
@@ -3,27 +3,36 @@ use std::fmt::Debug;
 use std::fs;
 use std::hash::Hash;
 use std::ops::{Add, Div, Mul, Rem, Sub};
+use std::path::Path;
+use std::str::FromStr;
 use crate::common::movement::Bounds;
+use crate::error::{AocError, AocResult};
+use crate::input;
 
+pub mod cycle;
+pub mod graph;
+pub mod interval;
 pub mod movement;
+pub mod numeric;
+pub mod ocr;
+pub mod parse;
+pub mod render;
 
-fn read_input_trim_opt(day: i32, should_trim: bool) -> String {
-    let input = fs::read_to_string(format!("input/day{}.txt", day))
-        .expect("Could not read input");
-    if should_trim {
-        input.trim()
-            .to_string()
-    } else {
-        input
-    }
+pub fn read_input_file(path: &Path) -> AocResult<String> {
+    fs::read_to_string(path).map_err(|err| AocError::io(path.display().to_string(), err.to_string()))
+}
+
+pub fn read_input(day: u32) -> AocResult<String> {
+    input::default_provider().load(day)
 }
 
-pub fn read_input(day: i32) -> String {
-    read_input_trim_opt(day, true)
+pub fn parse_number<T: FromStr>(value: &str) -> AocResult<T> {
+    value.parse().map_err(|_| AocError::parse(format!("expected a number, got `{}`", value)))
 }
 
-pub fn read_input_no_trim(day: i32) -> String {
-    read_input_trim_opt(day, false)
+// `value` must be a slice of `source`, which lets the error point at the exact line and column
+pub fn parse_number_in<T: FromStr>(source: &str, value: &str) -> AocResult<T> {
+    value.parse().map_err(|_| AocError::parse_in(source, value, format!("expected a number, got `{}`", value)))
 }
 
 pub fn intersect_to_set<T: Eq + Hash + Clone>(a: &HashSet<T>, b: &HashSet<T>) -> HashSet<T> {
@@ -69,13 +78,13 @@ pub enum BinaryOperation {
 }
 
 impl BinaryOperation {
-    pub fn from_str(value: &str) -> BinaryOperation {
+    pub fn from_str(value: &str) -> AocResult<BinaryOperation> {
         match value {
-            "*" => BinaryOperation::Multiply,
-            "/" => BinaryOperation::Divide,
-            "+" => BinaryOperation::Add,
-            "-" => BinaryOperation::Subtract,
-            _ => panic!()
+            "*" => Ok(BinaryOperation::Multiply),
+            "/" => Ok(BinaryOperation::Divide),
+            "+" => Ok(BinaryOperation::Add),
+            "-" => Ok(BinaryOperation::Subtract),
+            _ => Err(AocError::parse(format!("unknown operation `{}`", value)))
         }
     }
 }
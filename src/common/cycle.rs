@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// A simulation that repeats itself: after `start` steps, every `length` steps bring it back to the same state
+// while the measured quantity changes by `gain`, which is negative if it shrinks
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Cycle {
+    pub start: u128,
+    pub length: u128,
+    pub gain: i128,
+}
+
+// Steps `state` until the fingerprint of a state is seen again, or `limit` steps have run. `fingerprint` can return
+// None while the state isn't settled enough to compare yet. On success `state` is left at step `start + length`.
+pub fn find_cycle<S, K, F, P, M>(state: &mut S, limit: u128, mut step: F, mut fingerprint: P, mut measure: M) -> Option<Cycle>
+    where K: Eq + Hash, F: FnMut(&mut S), P: FnMut(&S) -> Option<K>, M: FnMut(&S) -> i128 {
+    let mut seen: HashMap<K, (u128, i128)> = HashMap::new();
+    for iteration in 0..limit {
+        if let Some(key) = fingerprint(state) {
+            let value = measure(state);
+            if let Some(&(start, start_value)) = seen.get(&key) {
+                return Some(Cycle {
+                    start,
+                    length: iteration - start,
+                    gain: value - start_value,
+                });
+            }
+            seen.insert(key, (iteration, value));
+        }
+        step(state);
+    }
+    None
+}
+
+// What `measure` would read after `target` steps, without running all of them once a cycle shows up
+pub fn extrapolate<S, K, F, P, M>(state: &mut S, target: u128, mut step: F, fingerprint: P, mut measure: M) -> i128
+    where K: Eq + Hash, F: FnMut(&mut S), P: FnMut(&S) -> Option<K>, M: FnMut(&S) -> i128 {
+    let cycle = match find_cycle(state, target, &mut step, fingerprint, &mut measure) {
+        Some(cycle) => cycle,
+        // Reached the target before anything repeated
+        None => return measure(state)
+    };
+
+    let remaining = target - (cycle.start + cycle.length);
+    for _ in 0..remaining % cycle.length {
+        step(state);
+    }
+    measure(state) + (remaining / cycle.length) as i128 * cycle.gain
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    // Counts up by the value of a counter that runs 0, 1, 2, 3, 1, 2, 3, 1, ...
+    fn step(state: &mut (u128, i128)) {
+        state.0 = if state.0 == 3 { 1 } else { state.0 + 1 };
+        state.1 += state.0 as i128;
+    }
+
+    #[test]
+    pub fn finds_cycle() {
+        let cycle = find_cycle(&mut (0, 0), 100, step, |state| Some(state.0), |state| state.1);
+        assert_eq!(Some(Cycle { start: 1, length: 3, gain: 6 }), cycle);
+        assert_eq!(None, find_cycle(&mut (0, 0), 3, step, |state| Some(state.0), |state| state.1));
+    }
+
+    #[test]
+    pub fn extrapolates_to_target() {
+        for target in [0, 2, 10, 11, 12, 1000] {
+            let mut simulated = (0, 0);
+            for _ in 0..target {
+                step(&mut simulated);
+            }
+            assert_eq!(simulated.1, extrapolate(&mut (0, 0), target, step, |state| Some(state.0), |state| state.1), "after {} steps", target);
+        }
+    }
+
+    #[test]
+    pub fn follows_shrinking_measure() {
+        // Counts down by 2 every step, from a counter that cycles 0, 1, 0, 1, ...
+        let shrink = |state: &mut (u128, i128)| {
+            state.0 = 1 - state.0;
+            state.1 -= 2;
+        };
+        let cycle = find_cycle(&mut (0, 0), 100, shrink, |state| Some(state.0), |state| state.1);
+        assert_eq!(Some(Cycle { start: 0, length: 2, gain: -4 }), cycle);
+        assert_eq!(-2003, extrapolate(&mut (0, 0), 1001, shrink, |state| Some(state.0), |state| state.1 - 1));
+    }
+}
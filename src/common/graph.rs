@@ -0,0 +1,177 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use priority_queue::PriorityQueue;
+
+// What a search found: the distance to every node it reached, the node each one was reached from, and the goal
+// it stopped at if there was one
+pub struct SearchResult<N> {
+    pub distances: HashMap<N, u128>,
+    pub previous: HashMap<N, N>,
+    pub goal: Option<N>,
+}
+
+impl<N: Clone + Eq + Hash> SearchResult<N> {
+    fn new() -> SearchResult<N> {
+        SearchResult {
+            distances: HashMap::new(),
+            previous: HashMap::new(),
+            goal: None,
+        }
+    }
+
+    pub fn distance_to(&self, node: &N) -> Option<u128> {
+        self.distances.get(node).copied()
+    }
+
+    pub fn goal_distance(&self) -> Option<u128> {
+        self.goal.as_ref().and_then(|goal| self.distance_to(goal))
+    }
+
+    // From the source the node was reached from up to and including `node`
+    #[allow(dead_code, reason = "no day needs the path itself yet, only its length")]
+    pub fn path_to(&self, node: &N) -> Option<Vec<N>> {
+        if !self.distances.contains_key(node) {
+            return None;
+        }
+
+        let mut path = vec![node.clone()];
+        while let Some(previous) = self.previous.get(path.last().unwrap()) {
+            path.push(previous.clone());
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+// Every step costs 1. Stops as soon as a node passing `is_goal` is reached; pass `|_| false` to explore everything.
+pub fn bfs<N, I, F, G>(sources: impl IntoIterator<Item = N>, mut neighbors: F, mut is_goal: G) -> SearchResult<N>
+    where N: Clone + Eq + Hash, I: IntoIterator<Item = N>, F: FnMut(&N) -> I, G: FnMut(&N) -> bool {
+    let mut result = SearchResult::new();
+    let mut queue: VecDeque<N> = VecDeque::new();
+    for source in sources {
+        if !result.distances.contains_key(&source) {
+            result.distances.insert(source.clone(), 0);
+            queue.push_back(source);
+        }
+    }
+
+    while let Some(current) = queue.pop_front() {
+        if is_goal(&current) {
+            result.goal = Some(current);
+            break;
+        }
+
+        let next_distance = result.distances[&current] + 1;
+        for neighbor in neighbors(&current) {
+            if !result.distances.contains_key(&neighbor) {
+                result.distances.insert(neighbor.clone(), next_distance);
+                result.previous.insert(neighbor.clone(), current.clone());
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    result
+}
+
+// `neighbors` gives each reachable node with the cost of the step to it. `heuristic` must never overestimate the
+// remaining cost to the goal, or the distances found may not be the shortest.
+#[allow(dead_code, reason = "every day's steps cost the same so far, which bfs handles")]
+pub fn astar<N, I, F, H, G>(sources: impl IntoIterator<Item = N>, mut neighbors: F, mut heuristic: H, mut is_goal: G) -> SearchResult<N>
+    where N: Clone + Eq + Hash, I: IntoIterator<Item = (N, u128)>, F: FnMut(&N) -> I, H: FnMut(&N) -> u128, G: FnMut(&N) -> bool {
+    let mut result = SearchResult::new();
+    let mut queue: PriorityQueue<N, Reverse<u128>> = PriorityQueue::new();
+    for source in sources {
+        result.distances.insert(source.clone(), 0);
+        let estimate = heuristic(&source);
+        queue.push(source, Reverse(estimate));
+    }
+
+    while let Some((current, _)) = queue.pop() {
+        if is_goal(&current) {
+            result.goal = Some(current);
+            break;
+        }
+
+        let distance = result.distances[&current];
+        for (neighbor, cost) in neighbors(&current) {
+            let neighbor_distance = distance + cost;
+            if result.distance_to(&neighbor).is_none_or(|existing| neighbor_distance < existing) {
+                result.distances.insert(neighbor.clone(), neighbor_distance);
+                result.previous.insert(neighbor.clone(), current.clone());
+                let estimate = neighbor_distance + heuristic(&neighbor);
+                // Replaces the priority if the neighbor is already queued
+                queue.push(neighbor, Reverse(estimate));
+            }
+        }
+    }
+
+    result
+}
+
+#[allow(dead_code, reason = "every day's steps cost the same so far, which bfs handles")]
+pub fn dijkstra<N, I, F, G>(sources: impl IntoIterator<Item = N>, neighbors: F, is_goal: G) -> SearchResult<N>
+    where N: Clone + Eq + Hash, I: IntoIterator<Item = (N, u128)>, F: FnMut(&N) -> I, G: FnMut(&N) -> bool {
+    astar(sources, neighbors, |_| 0, is_goal)
+}
+
+// Step counts between every pair of nodes, leaving out pairs that can't reach each other
+pub fn all_pairs_steps<N, I, F>(nodes: impl IntoIterator<Item = N>, mut neighbors: F) -> HashMap<N, HashMap<N, u128>>
+    where N: Clone + Eq + Hash, I: IntoIterator<Item = N>, F: FnMut(&N) -> I {
+    nodes.into_iter()
+        .map(|node| {
+            let distances = bfs([node.clone()], &mut neighbors, |_| false).distances;
+            (node, distances)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    // 0 - 1 - 2 - 3, with a costly shortcut from 0 straight to 3
+    fn line_neighbors(node: &u32) -> Vec<(u32, u128)> {
+        let mut neighbors = vec![];
+        if *node > 0 {
+            neighbors.push((node - 1, 1));
+        }
+        if *node < 3 {
+            neighbors.push((node + 1, 1));
+        }
+        if *node == 0 {
+            neighbors.push((3, 5));
+        }
+        neighbors
+    }
+
+    #[test]
+    pub fn finds_shortest_paths() {
+        let unweighted = |node: &u32| line_neighbors(node).into_iter().map(|(neighbor, _)| neighbor);
+        let result = bfs([0], unweighted, |&node| node == 3);
+        assert_eq!(Some(1), result.goal_distance());
+        assert_eq!(Some(vec![0, 3]), result.path_to(&3));
+
+        let result = dijkstra([0], line_neighbors, |&node| node == 3);
+        assert_eq!(Some(3), result.goal_distance());
+        assert_eq!(Some(vec![0, 1, 2, 3]), result.path_to(&3));
+
+        let result = astar([0], line_neighbors, |&node| 3 - node as u128, |&node| node == 3);
+        assert_eq!(Some(vec![0, 1, 2, 3]), result.path_to(&3));
+
+        assert_eq!(None, dijkstra([0], line_neighbors, |&node| node == 7).goal_distance());
+    }
+
+    #[test]
+    pub fn searches_from_many_sources() {
+        let result = dijkstra([0, 3], line_neighbors, |_| false);
+        assert_eq!(Some(1), result.distance_to(&1));
+        assert_eq!(Some(1), result.distance_to(&2));
+        assert_eq!(Some(vec![3, 2]), result.path_to(&2));
+
+        let all_pairs = all_pairs_steps(0..4, |node: &u32| line_neighbors(node).into_iter().map(|(neighbor, _)| neighbor));
+        assert_eq!(Some(&2), all_pairs[&1].get(&3));
+        assert_eq!(Some(&3), all_pairs[&3].get(&0));
+    }
+}
@@ -0,0 +1,129 @@
+use std::fmt::{Display, Formatter};
+use crate::common::movement::Coordinate;
+
+// An inclusive run of values, `start..=end`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Interval<T = i128> {
+    pub start: T,
+    pub end: T,
+}
+
+impl<T: Coordinate> Interval<T> {
+    // None when `end` comes before `start`
+    pub fn new(start: T, end: T) -> Option<Interval<T>> {
+        (start <= end).then_some(Interval { start, end })
+    }
+
+    // None when there are more values than a u128 can count, which only the full range of a 128-bit type has
+    pub fn len(&self) -> Option<u128> {
+        let span = match self.end.checked_sub(&self.start) {
+            Some(span) => span.to_u128()?,
+            // A signed interval too wide for its own type runs from below zero to above it, so count each side of zero
+            None => self.end.to_u128()? + (T::zero() - (self.start + T::one())).to_u128()? + 1
+        };
+        span.checked_add(1)
+    }
+
+    pub fn contains_value(&self, value: T) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    // True when all of `other` is inside this interval
+    pub fn contains(&self, other: &Interval<T>) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Interval<T>) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    // Overlapping or directly next to each other, so the two can merge into one interval
+    fn touches(&self, other: &Interval<T>) -> bool {
+        let reaches_other = |a: &Interval<T>, b: &Interval<T>| a.end.checked_add(&T::one()).is_none_or(|after_end| after_end >= b.start);
+        self.overlaps(other) || (self.end < other.start && reaches_other(self, other)) || (other.end < self.start && reaches_other(other, self))
+    }
+}
+
+impl<T: Display> Display for Interval<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+// A set of values kept as sorted intervals that neither overlap nor touch, so each value is in at most one of them
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct IntervalSet<T = i128> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T: Coordinate> IntervalSet<T> {
+    fn normalized(mut intervals: Vec<Interval<T>>) -> IntervalSet<T> {
+        intervals.sort_by_key(|interval| interval.start);
+        let mut merged: Vec<Interval<T>> = Vec::with_capacity(intervals.len());
+        for interval in intervals {
+            match merged.last_mut() {
+                Some(last) if last.touches(&interval) => last.end = last.end.max(interval.end),
+                _ => merged.push(interval)
+            }
+        }
+        IntervalSet {
+            intervals: merged,
+        }
+    }
+
+    // How many values are in the set, or None when that's more than a u128 can count
+    pub fn len(&self) -> Option<u128> {
+        self.intervals.iter().try_fold(0u128, |total, interval| total.checked_add(interval.len()?))
+    }
+
+    pub fn contains_value(&self, value: T) -> bool {
+        let after = self.intervals.partition_point(|interval| interval.end < value);
+        self.intervals.get(after).is_some_and(|interval| interval.contains_value(value))
+    }
+}
+
+impl<T: Coordinate> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(intervals: I) -> IntervalSet<T> {
+        IntervalSet::normalized(intervals.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn interval<T: Coordinate>(start: T, end: T) -> Interval<T> {
+        Interval::new(start, end).unwrap()
+    }
+
+    fn set(intervals: &[(i32, i32)]) -> IntervalSet<i32> {
+        intervals.iter().map(|&(start, end)| interval(start, end)).collect()
+    }
+
+    #[test]
+    pub fn interval_queries() {
+        let two_to_six = interval(2, 6);
+        assert_eq!(None, Interval::new(6, 2));
+        assert_eq!(Some(5), two_to_six.len());
+        assert_eq!(Some(u128::MAX), interval(i128::MIN + 1, i128::MAX).len());
+        assert_eq!(Some((1 << 127) + 1), interval(-1, i128::MAX).len());
+        assert_eq!(None, interval(i128::MIN, i128::MAX).len());
+        assert_eq!(None, interval(0, u128::MAX).len());
+        assert!(two_to_six.contains(&interval(3, 6)));
+        assert!(!two_to_six.contains(&interval(3, 7)));
+        assert!(two_to_six.overlaps(&interval(6, 9)));
+        assert!(!two_to_six.overlaps(&interval(7, 9)));
+        assert_eq!("2-6", two_to_six.to_string());
+    }
+
+    #[test]
+    pub fn normalizes_sets() {
+        assert_eq!(set(&[(-3, 4), (8, 10)]), set(&[(0, 4), (8, 9), (-3, 1), (10, 10)]));
+        assert_eq!(set(&[(1, 5)]), set(&[(1, 2), (3, 5)]));
+        assert_eq!(Some(11), set(&[(-3, 4), (8, 10)]).len());
+        assert_eq!(Some(u128::MAX), IntervalSet::from_iter([interval(i128::MIN, -1), interval(1, i128::MAX)]).len());
+        assert!(set(&[(-3, 4), (8, 10)]).contains_value(9));
+        assert!(!set(&[(-3, 4), (8, 10)]).contains_value(5));
+        assert_eq!(set(&[(i32::MIN, i32::MAX)]), set(&[(i32::MIN, 0), (1, i32::MAX)]));
+    }
+}
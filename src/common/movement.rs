@@ -1,6 +1,10 @@
 use std::cmp::{max, min};
+#[cfg(test)]
 use std::collections::HashMap;
-use std::ops::{RangeInclusive};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::{Add, AddAssign, Mul, Neg, RangeInclusive, Sub, SubAssign};
+use num_traits::PrimInt;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct Bounds<T = i128> where T: Ord + Copy {
@@ -38,69 +42,303 @@ impl<T> Bounds<T> where T: Ord + Copy {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
-pub struct Point {
-    pub x: i128,
-    pub y: i128,
+// Any primitive integer works as a coordinate; unsigned ones simply have no neighbors below zero
+pub trait Coordinate: PrimInt + Hash + Debug {}
+
+impl<T: PrimInt + Hash + Debug> Coordinate for T {}
+
+// Steps `value` by -1, 0 or 1, or gives None if that leaves the coordinate type
+fn step<T: Coordinate>(value: T, delta: i8) -> Option<T> {
+    match delta {
+        -1 => value.checked_sub(&T::one()),
+        1 => value.checked_add(&T::one()),
+        _ => Some(value)
+    }
 }
 
-impl Point {
-    pub fn zero() -> Point {
-        Point {
-            x: 0,
-            y: 0,
-        }
+fn abs_diff<T: Coordinate>(a: T, b: T) -> u128 {
+    (if a > b { a - b } else { b - a }).to_u128().unwrap()
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Point2<T = i128> {
+    pub x: T,
+    pub y: T,
+}
+
+// Most days work on the plane with signed coordinates
+pub type Point = Point2<i128>;
+
+const NEIGHBOR_OFFSETS_4: [(i8, i8); 4] = [(-1, 0), (1, 0), (0, 1), (0, -1)];
+const NEIGHBOR_OFFSETS_8: [(i8, i8); 8] = [(-1, 0), (-1, -1), (-1, 1), (1, 0), (1, -1), (1, 1), (0, 1), (0, -1)];
+
+impl<T: Coordinate> Point2<T> {
+    pub fn new(x: T, y: T) -> Point2<T> {
+        Point2 { x, y }
+    }
+
+    pub fn zero() -> Point2<T> {
+        Point2::new(T::zero(), T::zero())
     }
 
     pub fn move_in_dir(&mut self, direction: GridDirection) {
         match direction {
-            GridDirection::Right => self.x += 1,
-            GridDirection::Left => self.x -= 1,
-            GridDirection::Up => self.y += 1,
-            GridDirection::Down => self.y -= 1
+            GridDirection::Right => self.x = self.x + T::one(),
+            GridDirection::Left => self.x = self.x - T::one(),
+            GridDirection::Up => self.y = self.y + T::one(),
+            GridDirection::Down => self.y = self.y - T::one()
         }
     }
 
-    pub fn get_moved_in_dir(&self, direction: GridDirection) -> Point {
-        let mut other = self.clone();
+    pub fn get_moved_in_dir(&self, direction: GridDirection) -> Point2<T> {
+        let mut other = *self;
         other.move_in_dir(direction);
         other
     }
 
-    pub fn move_along_axis(&mut self, axis: Axis, count: i128) {
+    pub fn move_along_axis(&mut self, axis: Axis, count: T) {
         match axis {
-            Axis::Horizontal => self.x += count,
-            Axis::Vertical => self.y += count
+            Axis::Horizontal => self.x = self.x + count,
+            Axis::Vertical => self.y = self.y + count
         }
     }
 
-    pub fn get_coord_by_axis(&self, axis: Axis) -> i128 {
+    pub fn get_coord_by_axis(&self, axis: Axis) -> T {
         match axis {
             Axis::Horizontal => self.x,
             Axis::Vertical => self.y
         }
     }
 
-    pub fn subtract(&self, other: &Point) -> Point {
-        Point {
-            x: self.x - other.x,
-            y: self.y - other.y,
+    pub fn magnitude(&self) -> f64 {
+        let (x, y) = (self.x.to_f64().unwrap(), self.y.to_f64().unwrap());
+        (x * x + y * y).sqrt()
+    }
+
+    pub fn manhattan_dist(&self, other: &Point2<T>) -> u128 {
+        abs_diff(self.x, other.x) + abs_diff(self.y, other.y)
+    }
+
+    pub fn chebyshev_dist(&self, other: &Point2<T>) -> u128 {
+        max(abs_diff(self.x, other.x), abs_diff(self.y, other.y))
+    }
+
+    fn offset_by(&self, (dx, dy): (i8, i8)) -> Option<Point2<T>> {
+        Some(Point2::new(step(self.x, dx)?, step(self.y, dy)?))
+    }
+
+    // Left, right, up, down
+    pub fn neighbors4(&self) -> impl Iterator<Item = Point2<T>> + '_ {
+        NEIGHBOR_OFFSETS_4.iter().filter_map(|&offset| self.offset_by(offset))
+    }
+
+    // The orthogonal neighbors plus the diagonals
+    pub fn neighbors8(&self) -> impl Iterator<Item = Point2<T>> + '_ {
+        NEIGHBOR_OFFSETS_8.iter().filter_map(|&offset| self.offset_by(offset))
+    }
+
+    pub fn with_z(&self, z: T) -> Point3<T> {
+        Point3::new(self.x, self.y, z)
+    }
+
+    pub fn try_cast<U: Coordinate>(&self) -> Option<Point2<U>> {
+        Some(Point2::new(U::from(self.x)?, U::from(self.y)?))
+    }
+}
+
+impl<T: Coordinate> Add for Point2<T> {
+    type Output = Point2<T>;
+
+    fn add(self, other: Point2<T>) -> Point2<T> {
+        Point2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Coordinate> Sub for Point2<T> {
+    type Output = Point2<T>;
+
+    fn sub(self, other: Point2<T>) -> Point2<T> {
+        Point2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Coordinate + Neg<Output = T>> Neg for Point2<T> {
+    type Output = Point2<T>;
+
+    fn neg(self) -> Point2<T> {
+        Point2::new(-self.x, -self.y)
+    }
+}
+
+impl<T: Coordinate> Mul<T> for Point2<T> {
+    type Output = Point2<T>;
+
+    fn mul(self, scale: T) -> Point2<T> {
+        Point2::new(self.x * scale, self.y * scale)
+    }
+}
+
+impl<T: Coordinate> AddAssign for Point2<T> {
+    fn add_assign(&mut self, other: Point2<T>) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Coordinate> SubAssign for Point2<T> {
+    fn sub_assign(&mut self, other: Point2<T>) {
+        *self = *self - other;
+    }
+}
+
+impl<T> From<(T, T)> for Point2<T> {
+    fn from((x, y): (T, T)) -> Point2<T> {
+        Point2 { x, y }
+    }
+}
+
+impl<T> From<Point2<T>> for (T, T) {
+    fn from(point: Point2<T>) -> (T, T) {
+        (point.x, point.y)
+    }
+}
+
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
+pub enum Axis3D {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis3D {
+    pub fn all() -> Vec<Axis3D> {
+        vec![Axis3D::X, Axis3D::Y, Axis3D::Z]
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Point3<T = i128> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T: Coordinate> Point3<T> {
+    pub fn new(x: T, y: T, z: T) -> Point3<T> {
+        Point3 { x, y, z }
+    }
+
+    pub fn zero() -> Point3<T> {
+        Point3::new(T::zero(), T::zero(), T::zero())
+    }
+
+    pub fn get_axis_value(&self, axis: Axis3D) -> T {
+        match axis {
+            Axis3D::X => self.x,
+            Axis3D::Y => self.y,
+            Axis3D::Z => self.z
         }
     }
 
-    pub fn add(&self, other: &Point) -> Point {
-        Point {
-            x: self.x + other.x,
-            y: self.y + other.y,
+    pub fn set_axis_value(&mut self, axis: Axis3D, value: T) {
+        match axis {
+            Axis3D::X => self.x = value,
+            Axis3D::Y => self.y = value,
+            Axis3D::Z => self.z = value
         }
     }
 
-    pub fn magnitude(&self) -> f64 {
-        ((self.x.pow(2) + self.y.pow(2)) as f64).sqrt()
+    pub fn manhattan_dist(&self, other: &Point3<T>) -> u128 {
+        abs_diff(self.x, other.x) + abs_diff(self.y, other.y) + abs_diff(self.z, other.z)
+    }
+
+    pub fn chebyshev_dist(&self, other: &Point3<T>) -> u128 {
+        max(max(abs_diff(self.x, other.x), abs_diff(self.y, other.y)), abs_diff(self.z, other.z))
+    }
+
+    fn offset_by(&self, (dx, dy, dz): (i8, i8, i8)) -> Option<Point3<T>> {
+        Some(Point3::new(step(self.x, dx)?, step(self.y, dy)?, step(self.z, dz)?))
+    }
+
+    // One step along each axis in both directions
+    pub fn neighbors6(&self) -> impl Iterator<Item = Point3<T>> + '_ {
+        Axis3D::all().into_iter()
+            .flat_map(|axis| [-1, 1].map(|delta| match axis {
+                Axis3D::X => (delta, 0, 0),
+                Axis3D::Y => (0, delta, 0),
+                Axis3D::Z => (0, 0, delta)
+            }))
+            .filter_map(|offset| self.offset_by(offset))
+    }
+
+    // Every point of the surrounding 3x3x3 cube
+    pub fn neighbors26(&self) -> impl Iterator<Item = Point3<T>> + '_ {
+        (-1..=1).flat_map(|dx| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| (dx, dy, dz))))
+            .filter(|&offset| offset != (0, 0, 0))
+            .filter_map(|offset| self.offset_by(offset))
+    }
+
+    pub fn xy(&self) -> Point2<T> {
+        Point2::new(self.x, self.y)
+    }
+
+    pub fn try_cast<U: Coordinate>(&self) -> Option<Point3<U>> {
+        Some(Point3::new(U::from(self.x)?, U::from(self.y)?, U::from(self.z)?))
+    }
+}
+
+impl<T: Coordinate> Add for Point3<T> {
+    type Output = Point3<T>;
+
+    fn add(self, other: Point3<T>) -> Point3<T> {
+        Point3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl<T: Coordinate> Sub for Point3<T> {
+    type Output = Point3<T>;
+
+    fn sub(self, other: Point3<T>) -> Point3<T> {
+        Point3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl<T: Coordinate + Neg<Output = T>> Neg for Point3<T> {
+    type Output = Point3<T>;
+
+    fn neg(self) -> Point3<T> {
+        Point3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl<T: Coordinate> Mul<T> for Point3<T> {
+    type Output = Point3<T>;
+
+    fn mul(self, scale: T) -> Point3<T> {
+        Point3::new(self.x * scale, self.y * scale, self.z * scale)
+    }
+}
+
+impl<T: Coordinate> AddAssign for Point3<T> {
+    fn add_assign(&mut self, other: Point3<T>) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Coordinate> SubAssign for Point3<T> {
+    fn sub_assign(&mut self, other: Point3<T>) {
+        *self = *self - other;
+    }
+}
+
+impl<T> From<(T, T, T)> for Point3<T> {
+    fn from((x, y, z): (T, T, T)) -> Point3<T> {
+        Point3 { x, y, z }
     }
+}
 
-    pub fn manhattan_dist(&self, other: &Point) -> u128 {
-        return ((self.x - other.x).abs() + (self.y - other.y).abs()) as u128
+impl<T> From<Point3<T>> for (T, T, T) {
+    fn from(point: Point3<T>) -> (T, T, T) {
+        (point.x, point.y, point.z)
     }
 }
 
@@ -140,12 +378,33 @@ impl Axis {
     }
 }
 
+// Which way y grows going down the rows of a picture or an input. Most days count y upwards, so the first row
+// has the largest y.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Orientation {
+    YUp,
+    YDown,
+}
+
+// The operations solvers need from a grid, so the dense storage can be checked against the sparse one in tests
+pub trait GridStorage<T> {
+    fn visit(&mut self, point: Point, value: T);
+    fn has_visited(&self, point: &Point) -> bool;
+    fn get_value(&self, point: &Point) -> Option<&T>;
+    fn get_x_bounds(&self) -> Bounds;
+    fn get_y_bounds(&self) -> Bounds;
+}
+
+// Sparse storage, only kept as the reference the dense grid is tested against
+#[cfg(test)]
+#[derive(Clone)]
 pub struct Grid<T> {
     _x_bounds: Bounds<i128>,
     _y_bounds: Bounds<i128>,
     _grid: HashMap<Point, T>,
 }
 
+#[cfg(test)]
 impl<T: 'static> Grid<T> {
     pub fn new() -> Grid<T> {
         Grid {
@@ -154,30 +413,203 @@ impl<T: 'static> Grid<T> {
             _grid: HashMap::new(),
         }
     }
+}
 
-    pub fn visit(&mut self, point: Point, value: T) {
+#[cfg(test)]
+impl<T: 'static> Default for Grid<T> {
+    fn default() -> Grid<T> {
+        Grid::new()
+    }
+}
+
+#[cfg(test)]
+impl<T: 'static> GridStorage<T> for Grid<T> {
+    fn visit(&mut self, point: Point, value: T) {
         self._grid.insert(point, value);
         self._x_bounds.update(point.x);
         self._y_bounds.update(point.y);
     }
 
-    pub fn remove(&mut self, point: Point) {
-        self._grid.remove(&point);
-    }
-
-    pub fn has_visited(&self, point: &Point) -> bool {
+    fn has_visited(&self, point: &Point) -> bool {
         self._grid.contains_key(point)
     }
 
-    pub fn get_value(&self, point: &Point) -> Option<&T> {
+    fn get_value(&self, point: &Point) -> Option<&T> {
         self._grid.get(point)
     }
 
-    pub fn get_x_bounds(&self) -> Bounds {
+    fn get_x_bounds(&self) -> Bounds {
         self._x_bounds.clone()
     }
 
-    pub fn get_y_bounds(&self) -> Bounds {
+    fn get_y_bounds(&self) -> Bounds {
         self._y_bounds.clone()
     }
-}
\ No newline at end of file
+}
+
+// Dense storage in a flat row-major Vec, for grids that are mostly filled in and read in tight loops.
+// `_origin` is the point stored at index 0; the storage grows in any direction when a point outside it is visited.
+#[derive(Clone)]
+pub struct DenseGrid<T> {
+    _x_bounds: Bounds<i128>,
+    _y_bounds: Bounds<i128>,
+    _origin: Point,
+    _width: usize,
+    _height: usize,
+    _cells: Vec<Option<T>>,
+}
+
+impl<T: 'static> DenseGrid<T> {
+    pub fn new() -> DenseGrid<T> {
+        DenseGrid {
+            _x_bounds: Bounds::new(0, 0),
+            _y_bounds: Bounds::new(0, 0),
+            _origin: Point::zero(),
+            _width: 0,
+            _height: 0,
+            _cells: vec![],
+        }
+    }
+
+    fn index_of(&self, point: &Point) -> Option<usize> {
+        let x = point.x - self._origin.x;
+        let y = point.y - self._origin.y;
+        if x < 0 || y < 0 || x >= self._width as i128 || y >= self._height as i128 {
+            return None;
+        }
+        Some(y as usize * self._width + x as usize)
+    }
+
+    // Grows by at least the current size on whichever sides are short, so repeated growth stays amortized
+    fn grow_to_include(&mut self, point: &Point) {
+        if self._cells.is_empty() {
+            self._origin = *point;
+            self._width = 1;
+            self._height = 1;
+            self._cells = vec![None];
+            return;
+        }
+
+        let (old_width, old_height) = (self._width as i128, self._height as i128);
+        let min_x = if point.x < self._origin.x { min(point.x, self._origin.x - old_width) } else { self._origin.x };
+        let min_y = if point.y < self._origin.y { min(point.y, self._origin.y - old_height) } else { self._origin.y };
+        let old_max_x = self._origin.x + old_width - 1;
+        let old_max_y = self._origin.y + old_height - 1;
+        let max_x = if point.x > old_max_x { max(point.x, old_max_x + old_width) } else { old_max_x };
+        let max_y = if point.y > old_max_y { max(point.y, old_max_y + old_height) } else { old_max_y };
+
+        let width = (max_x - min_x + 1) as usize;
+        let height = (max_y - min_y + 1) as usize;
+        let mut cells: Vec<Option<T>> = Vec::with_capacity(width * height);
+        cells.resize_with(width * height, || None);
+
+        let x_offset = (self._origin.x - min_x) as usize;
+        let y_offset = (self._origin.y - min_y) as usize;
+        for (i, cell) in self._cells.drain(..).enumerate() {
+            let (x, y) = (i % self._width, i / self._width);
+            cells[(y + y_offset) * width + x + x_offset] = cell;
+        }
+
+        self._origin = Point { x: min_x, y: min_y };
+        self._width = width;
+        self._height = height;
+        self._cells = cells;
+    }
+}
+
+impl<T: 'static> Default for DenseGrid<T> {
+    fn default() -> DenseGrid<T> {
+        DenseGrid::new()
+    }
+}
+
+impl<T: 'static> GridStorage<T> for DenseGrid<T> {
+    fn visit(&mut self, point: Point, value: T) {
+        let index = match self.index_of(&point) {
+            Some(index) => index,
+            None => {
+                self.grow_to_include(&point);
+                self.index_of(&point).unwrap()
+            }
+        };
+        self._cells[index] = Some(value);
+        self._x_bounds.update(point.x);
+        self._y_bounds.update(point.y);
+    }
+
+    fn has_visited(&self, point: &Point) -> bool {
+        self.get_value(point).is_some()
+    }
+
+    fn get_value(&self, point: &Point) -> Option<&T> {
+        self.index_of(point).and_then(|index| self._cells[index].as_ref())
+    }
+
+    fn get_x_bounds(&self) -> Bounds {
+        self._x_bounds.clone()
+    }
+
+    fn get_y_bounds(&self) -> Bounds {
+        self._y_bounds.clone()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn fill<G: GridStorage<char>>(grid: &mut G) {
+        grid.visit(Point { x: 2, y: 3 }, 'a');
+        grid.visit(Point { x: -4, y: 3 }, 'b');
+        grid.visit(Point { x: 0, y: -7 }, 'c');
+        grid.visit(Point { x: 10, y: 12 }, 'd');
+        grid.visit(Point { x: 2, y: 3 }, 'e');
+    }
+
+    #[test]
+    pub fn point_arithmetic() {
+        let a = Point::new(3, -4);
+        let b = Point::new(-1, 2);
+        assert_eq!(Point::new(2, -2), a + b);
+        assert_eq!(Point::new(4, -6), a - b);
+        assert_eq!(Point::new(-3, 4), -a);
+        assert_eq!(Point::new(6, -8), a * 2);
+        assert_eq!(10, a.manhattan_dist(&b));
+        assert_eq!(6, a.chebyshev_dist(&b));
+        assert_eq!(5.0, a.magnitude());
+        assert_eq!((3, -4), a.into());
+        assert_eq!(Some(Point2::<u8>::new(3, 0)), Point::new(3, 0).try_cast());
+        assert_eq!(None, a.try_cast::<u8>());
+        assert_eq!(Point3::new(3, -4, 7), a.with_z(7));
+    }
+
+    #[test]
+    pub fn neighbors_stay_in_range() {
+        assert_eq!(4, Point::zero().neighbors4().count());
+        assert_eq!(8, Point::zero().neighbors8().count());
+        assert_eq!(6, Point3::<i128>::zero().neighbors6().count());
+        assert_eq!(26, Point3::<i128>::zero().neighbors26().count());
+        assert_eq!(vec![Point2::<usize>::new(1, 0), Point2::new(0, 1)], Point2::<usize>::zero().neighbors4().collect::<Vec<_>>());
+        assert_eq!(7, Point3::<usize>::new(0, 0, 0).neighbors26().count());
+    }
+
+    #[test]
+    pub fn dense_grid_matches_sparse_grid() {
+        let mut sparse = Grid::new();
+        let mut dense = DenseGrid::new();
+        fill(&mut sparse);
+        fill(&mut dense);
+
+        assert_eq!(sparse.get_x_bounds(), dense.get_x_bounds());
+        assert_eq!(sparse.get_y_bounds(), dense.get_y_bounds());
+        for y in -10..=15 {
+            for x in -10..=15 {
+                let point = Point { x, y };
+                assert_eq!(sparse.get_value(&point), dense.get_value(&point), "at {:?}", point);
+                assert_eq!(sparse.has_visited(&point), dense.has_visited(&point), "at {:?}", point);
+            }
+        }
+        assert_eq!(Some(&'e'), dense.get_value(&Point { x: 2, y: 3 }));
+        assert_eq!(Some(&'d'), dense.get_value(&Point { x: 10, y: 12 }));
+    }
+}
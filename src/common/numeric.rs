@@ -0,0 +1,246 @@
+use std::fmt::{Debug, Display, Formatter};
+use num_bigint::BigInt;
+use num_traits::{ToPrimitive, Zero};
+use crate::error::{AocError, AocResult};
+use crate::solution::Answer;
+
+// Which number type solvers that are generic over `Number` run with
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Arithmetic {
+    // Plain i128, wrapping around on overflow
+    #[default]
+    Native,
+    // i128 that reports overflow as an error
+    Checked,
+    // Arbitrary precision, so only division by zero can go wrong
+    Big,
+}
+
+impl Arithmetic {
+    pub fn from_name(name: &str) -> Option<Arithmetic> {
+        match name {
+            "native" => Some(Arithmetic::Native),
+            "checked" => Some(Arithmetic::Checked),
+            "big" => Some(Arithmetic::Big),
+            _ => None
+        }
+    }
+}
+
+// Integer arithmetic where anything that can't give the right answer is an error rather than a panic or a wrong value
+pub trait Number: Clone + Ord + Debug + Display + Sized {
+    fn from_i128(value: i128) -> Self;
+    fn to_i128(&self) -> Option<i128>;
+    fn try_add(&self, other: &Self) -> AocResult<Self>;
+    fn try_sub(&self, other: &Self) -> AocResult<Self>;
+    fn try_mul(&self, other: &Self) -> AocResult<Self>;
+    // Rounds towards zero
+    fn try_div(&self, other: &Self) -> AocResult<Self>;
+    // Takes the sign of `self`, like `%`
+    fn try_rem(&self, other: &Self) -> AocResult<Self>;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::from_i128(0)
+    }
+
+    fn try_div_exact(&self, other: &Self) -> AocResult<Self> {
+        if !self.try_rem(other)?.is_zero() {
+            return Err(AocError::arithmetic(format!("{} is not divisible by {}", self, other)));
+        }
+        self.try_div(other)
+    }
+
+    // Always between 0 and `other`, for wrapping positions around a list
+    fn try_rem_euclid(&self, other: &Self) -> AocResult<Self> {
+        let remainder = self.try_rem(other)?;
+        if remainder < Self::from_i128(0) {
+            let magnitude = if *other < Self::from_i128(0) { Self::from_i128(0).try_sub(other)? } else { other.clone() };
+            remainder.try_add(&magnitude)
+        } else {
+            Ok(remainder)
+        }
+    }
+
+    // Falls back to the decimal digits when the value doesn't fit in a numeric answer
+    fn to_answer(&self) -> Answer {
+        match self.to_i128() {
+            Some(value) => Answer::Number(value),
+            None => Answer::Text(self.to_string())
+        }
+    }
+}
+
+fn division_by_zero<N: Display>(value: &N) -> AocError {
+    AocError::arithmetic(format!("{} divided by zero", value))
+}
+
+impl Number for i128 {
+    fn from_i128(value: i128) -> i128 {
+        value
+    }
+
+    fn to_i128(&self) -> Option<i128> {
+        Some(*self)
+    }
+
+    fn try_add(&self, other: &i128) -> AocResult<i128> {
+        Ok(self.wrapping_add(*other))
+    }
+
+    fn try_sub(&self, other: &i128) -> AocResult<i128> {
+        Ok(self.wrapping_sub(*other))
+    }
+
+    fn try_mul(&self, other: &i128) -> AocResult<i128> {
+        Ok(self.wrapping_mul(*other))
+    }
+
+    fn try_div(&self, other: &i128) -> AocResult<i128> {
+        if *other == 0 {
+            return Err(division_by_zero(self));
+        }
+        Ok(self.wrapping_div(*other))
+    }
+
+    fn try_rem(&self, other: &i128) -> AocResult<i128> {
+        if *other == 0 {
+            return Err(division_by_zero(self));
+        }
+        Ok(self.wrapping_rem(*other))
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Checked(pub i128);
+
+impl Display for Checked {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Checked {
+    fn apply(&self, other: &Checked, symbol: &str, operation: fn(i128, i128) -> Option<i128>) -> AocResult<Checked> {
+        if other.0 == 0 && (symbol == "/" || symbol == "%") {
+            return Err(division_by_zero(self));
+        }
+        operation(self.0, other.0)
+            .map(Checked)
+            .ok_or_else(|| AocError::arithmetic(format!("{} {} {} overflows a 128-bit integer", self, symbol, other)))
+    }
+}
+
+impl Number for Checked {
+    fn from_i128(value: i128) -> Checked {
+        Checked(value)
+    }
+
+    fn to_i128(&self) -> Option<i128> {
+        Some(self.0)
+    }
+
+    fn try_add(&self, other: &Checked) -> AocResult<Checked> {
+        self.apply(other, "+", i128::checked_add)
+    }
+
+    fn try_sub(&self, other: &Checked) -> AocResult<Checked> {
+        self.apply(other, "-", i128::checked_sub)
+    }
+
+    fn try_mul(&self, other: &Checked) -> AocResult<Checked> {
+        self.apply(other, "*", i128::checked_mul)
+    }
+
+    fn try_div(&self, other: &Checked) -> AocResult<Checked> {
+        self.apply(other, "/", i128::checked_div)
+    }
+
+    fn try_rem(&self, other: &Checked) -> AocResult<Checked> {
+        self.apply(other, "%", i128::checked_rem)
+    }
+}
+
+impl Number for BigInt {
+    fn from_i128(value: i128) -> BigInt {
+        BigInt::from(value)
+    }
+
+    fn to_i128(&self) -> Option<i128> {
+        ToPrimitive::to_i128(self)
+    }
+
+    fn try_add(&self, other: &BigInt) -> AocResult<BigInt> {
+        Ok(self + other)
+    }
+
+    fn try_sub(&self, other: &BigInt) -> AocResult<BigInt> {
+        Ok(self - other)
+    }
+
+    fn try_mul(&self, other: &BigInt) -> AocResult<BigInt> {
+        Ok(self * other)
+    }
+
+    fn try_div(&self, other: &BigInt) -> AocResult<BigInt> {
+        if Zero::is_zero(other) {
+            return Err(division_by_zero(self));
+        }
+        Ok(self / other)
+    }
+
+    fn try_rem(&self, other: &BigInt) -> AocResult<BigInt> {
+        if Zero::is_zero(other) {
+            return Err(division_by_zero(self));
+        }
+        Ok(self % other)
+    }
+}
+
+// Evaluates `$body` with `$number` standing for the number type `$arithmetic` picks
+macro_rules! with_number_type {
+    ($arithmetic:expr, $number:ident => $body:expr) => {
+        match $arithmetic {
+            $crate::common::numeric::Arithmetic::Native => {
+                type $number = i128;
+                $body
+            }
+            $crate::common::numeric::Arithmetic::Checked => {
+                type $number = $crate::common::numeric::Checked;
+                $body
+            }
+            $crate::common::numeric::Arithmetic::Big => {
+                type $number = num_bigint::BigInt;
+                $body
+            }
+        }
+    };
+}
+
+pub(crate) use with_number_type;
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn checks_overflow() {
+        let big = i128::MAX / 2 + 1;
+        assert_eq!(i128::MIN, big.try_mul(&2).unwrap());
+        assert_eq!(
+            Err(AocError::arithmetic(format!("{} * 2 overflows a 128-bit integer", big))),
+            Checked(big).try_mul(&Checked(2))
+        );
+        let product = BigInt::from(big).try_mul(&BigInt::from(2)).unwrap();
+        assert_eq!(Answer::Text("170141183460469231731687303715884105728".to_string()), product.to_answer());
+        assert_eq!(Answer::Number(big), product.try_div(&BigInt::from(2)).unwrap().to_answer());
+    }
+
+    #[test]
+    pub fn divides() {
+        assert_eq!(Ok(Checked(-3)), Checked(-7).try_div(&Checked(2)));
+        assert_eq!(Ok(Checked(1)), Checked(-7).try_rem_euclid(&Checked(2)));
+        assert_eq!(Ok(BigInt::from(1)), BigInt::from(-7).try_rem_euclid(&BigInt::from(-2)));
+        assert_eq!(Err(AocError::arithmetic("7 is not divisible by 2")), 7.try_div_exact(&2));
+        assert_eq!(Err(AocError::arithmetic("7 divided by zero")), BigInt::from(7).try_rem(&BigInt::from(0)));
+    }
+}
@@ -0,0 +1,118 @@
+// Reads the block letters some puzzles draw as their answer, in either the 4×6 or the 6×10 font. Lit pixels are `#`
+// and anything else counts as unlit.
+
+struct Font {
+    width: usize,
+    height: usize,
+    // Columns from the start of one letter to the start of the next
+    pitch: usize,
+    glyphs: &'static [(char, &'static [&'static str])],
+}
+
+const SMALL: Font = Font {
+    width: 4,
+    height: 6,
+    pitch: 5,
+    glyphs: &[
+        ('A', &[".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+        ('B', &["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+        ('C', &[".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+        ('E', &["####", "#...", "###.", "#...", "#...", "####"]),
+        ('F', &["####", "#...", "###.", "#...", "#...", "#..."]),
+        ('G', &[".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+        ('H', &["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+        ('I', &[".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+        ('J', &["..##", "...#", "...#", "...#", "#..#", ".##."]),
+        ('K', &["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+        ('L', &["#...", "#...", "#...", "#...", "#...", "####"]),
+        ('O', &[".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('P', &["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+        ('R', &["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+        ('S', &[".###", "#...", "#...", ".##.", "...#", "###."]),
+        ('U', &["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('Z', &["####", "...#", "..#.", ".#..", "#...", "####"]),
+    ],
+};
+
+const LARGE: Font = Font {
+    width: 6,
+    height: 10,
+    pitch: 8,
+    glyphs: &[
+        ('A', &["..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#"]),
+        ('B', &["#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#", "#....#", "#####."]),
+        ('C', &[".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#....#", ".####."]),
+        ('E', &["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "######"]),
+        ('F', &["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+        ('G', &[".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+        ('H', &["#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#", "#....#"]),
+        ('J', &["...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.", "#...#.", ".###.."]),
+        ('K', &["#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+        ('L', &["#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "######"]),
+        ('N', &["#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+        ('P', &["#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+        ('R', &["#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+        ('X', &["#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+        ('Z', &["######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....", "#.....", "######"]),
+    ],
+};
+
+// The letters drawn in `picture`, one row of pixels per line. The font is picked from the number of rows, and blank
+// columns before the first letter are skipped. None if any letter isn't one the font knows.
+pub fn recognize(picture: &str) -> Option<String> {
+    let rows: Vec<Vec<bool>> = picture.trim_matches('\n').lines()
+        .map(|row| row.chars().map(|pixel| pixel == '#').collect())
+        .collect();
+    let font = [SMALL, LARGE].into_iter().find(|font| font.height == rows.len())?;
+
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let is_lit = |x: usize, y: usize| rows[y].get(x).copied().unwrap_or(false);
+    let is_blank_column = |x: usize| (0..font.height).all(|y| !is_lit(x, y));
+
+    let mut text = String::new();
+    let mut x = (0..width).find(|&x| !is_blank_column(x))?;
+    while (x..width).any(|x| !is_blank_column(x)) {
+        // Letters are padded with blank columns up to the next one
+        if !(x + font.width..x + font.pitch).all(is_blank_column) {
+            return None;
+        }
+        let (letter, _) = font.glyphs.iter().find(|(_, glyph)| {
+            glyph.iter().enumerate().all(|(y, glyph_row)| {
+                glyph_row.chars().enumerate().all(|(dx, pixel)| (pixel == '#') == is_lit(x + dx, y))
+            })
+        })?;
+        text.push(*letter);
+        x += font.pitch;
+    }
+    Some(text)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn reads_small_letters() {
+        let picture = "####.####.###..###..###..####.####.####.
+#.......#.#..#.#..#.#..#.#.......#.#....
+###....#..###..#..#.###..###....#..###..
+#.....#...#..#.###..#..#.#.....#...#....
+#....#....#..#.#....#..#.#....#....#....
+#....####.###..#....###..#....####.#....";
+        assert_eq!(Some("FZBPBFZF".to_string()), recognize(picture));
+
+        let unknown = picture.replacen("####.####", "####.#####", 1);
+        assert_eq!(None, recognize(&unknown));
+        assert_eq!(None, recognize("##..##..\n##..##.."));
+    }
+
+    #[test]
+    pub fn reads_large_letters() {
+        // `XJ`, drawn two columns in from the left edge
+        let picture = (0..10)
+            .map(|y| format!("..{}..{}..", LARGE.glyphs[13].1[y], LARGE.glyphs[7].1[y]))
+            .collect::<Vec<String>>()
+            .join("\n");
+        assert_eq!(Some("XJ".to_string()), recognize(&picture));
+    }
+}
@@ -0,0 +1,144 @@
+// Small helpers for the shapes puzzle inputs come in. Each one takes the whole `source` input alongside the text it
+// works on (which must be a slice of `source`), so errors point at the line and column they came from.
+use std::str::FromStr;
+use crate::common::movement::{GridStorage, Orientation, Point};
+use crate::common::parse_number_in;
+use crate::error::{AocError, AocResult};
+
+pub fn lines(text: &str) -> impl Iterator<Item = &str> {
+    text.split('\n')
+}
+
+// Groups of lines separated by a blank line
+pub fn blocks(text: &str) -> impl Iterator<Item = &str> {
+    text.split("\n\n")
+}
+
+pub fn integer<T: FromStr>(source: &str, text: &str) -> AocResult<T> {
+    parse_number_in(source, text.trim())
+}
+
+// Every integer in `text`, ignoring whatever surrounds them, so `x=-2, y=15` gives -2 and 15
+pub fn integers<T: FromStr>(source: &str, text: &str) -> AocResult<Vec<T>> {
+    let bytes = text.as_bytes();
+    let mut values = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        // A `-` between two numbers, like in `2-4`, is a separator rather than a sign
+        let is_negative = bytes[i] == b'-'
+            && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)
+            && (i == 0 || !bytes[i - 1].is_ascii_alphanumeric());
+        if !is_negative && !bytes[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let digits = bytes[i + is_negative as usize..].iter().take_while(|c| c.is_ascii_digit()).count();
+        let end = i + is_negative as usize + digits;
+        values.push(parse_number_in(source, &text[i..end])?);
+        i = end;
+    }
+    Ok(values)
+}
+
+pub fn separated<'a, T, F>(text: &'a str, separator: &str, parse_item: F) -> AocResult<Vec<T>>
+    where F: FnMut(&'a str) -> AocResult<T> {
+    text.split(separator).map(parse_item).collect()
+}
+
+pub fn split_pair<'a>(source: &str, text: &'a str, separator: &str) -> AocResult<(&'a str, &'a str)> {
+    text.split_once(separator).ok_or_else(|| AocError::parse_in(source, text, format!("expected `{}` in `{}`", separator, text)))
+}
+
+// A `key: value` style line, with the whitespace around both sides trimmed
+pub fn key_value<'a>(source: &str, line: &'a str, separator: &str) -> AocResult<(&'a str, &'a str)> {
+    let (key, value) = split_pair(source, line, separator)?;
+    Ok((key.trim(), value.trim()))
+}
+
+pub fn key_values<'a>(source: &str, text: &'a str, separator: &str) -> AocResult<Vec<(&'a str, &'a str)>> {
+    lines(text).map(|line| key_value(source, line, separator)).collect()
+}
+
+// Matches `text` against a pattern like `move {} from {} to {}`, giving back what each `{}` matched. A field runs up
+// to the next literal part of the pattern, or to the end of the text for a trailing `{}`.
+pub fn fields<'a, const N: usize>(source: &str, text: &'a str, pattern: &str) -> AocResult<[&'a str; N]> {
+    let literals: Vec<&str> = pattern.split("{}").collect();
+    assert_eq!(N + 1, literals.len(), "pattern `{}` must have {} fields", pattern, N);
+    let mismatch = || AocError::parse_in(source, text, format!("expected `{}`", pattern));
+
+    let mut rest = text.strip_prefix(literals[0]).ok_or_else(mismatch)?;
+    let mut fields = [""; N];
+    for (field, literal) in fields.iter_mut().zip(&literals[1..]) {
+        let field_end = if literal.is_empty() { rest.len() } else { rest.find(literal).ok_or_else(mismatch)? };
+        *field = &rest[..field_end];
+        rest = &rest[field_end + literal.len()..];
+    }
+    if !rest.is_empty() {
+        return Err(mismatch());
+    }
+    Ok(fields)
+}
+
+// Reads a block of characters into a grid, one row per line. `cell` decides what each character becomes, with None
+// leaving that point out of the grid. Columns become x, and rows become y in the given orientation.
+pub fn char_grid<T, G, F>(text: &str, orientation: Orientation, mut cell: F) -> AocResult<G>
+    where G: GridStorage<T> + Default, F: FnMut(Point, char) -> AocResult<Option<T>> {
+    let rows: Vec<&str> = lines(text).collect();
+    let mut grid = G::default();
+    for (row, line) in rows.iter().enumerate() {
+        let y = match orientation {
+            Orientation::YDown => row as i128,
+            Orientation::YUp => (rows.len() - 1 - row) as i128
+        };
+        for (column, value) in line.chars().enumerate() {
+            let point = Point { x: column as i128, y };
+            if let Some(cell_value) = cell(point, value).map_err(|error| error.at(row + 1, column + 1))? {
+                grid.visit(point, cell_value);
+            }
+        }
+    }
+    Ok(grid)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::common::movement::Grid;
+    use super::*;
+
+    #[test]
+    pub fn parses_numbers() {
+        let source = "Sensor at x=2, y=-18: beacon at x=-2, y=15";
+        assert_eq!(Ok(vec![2, -18, -2, 15]), integers::<i32>(source, source));
+        assert_eq!(Ok(vec![5, 3]), integers::<u32>("5-3", "5-3"));
+        assert_eq!(Ok(-7), integer::<i64>(" -7 ", " -7 "));
+        assert_eq!(Err(AocError::parse_at(1, 6, "expected a number, got `300`")), integers::<u8>("5 to 300", "5 to 300"));
+        let source = "1,2,x";
+        assert_eq!(Err(AocError::parse_at(1, 5, "expected a number, got `x`")), separated(source, ",", |part| integer::<u32>(source, part)));
+    }
+
+    #[test]
+    pub fn matches_fields() {
+        let source = "move 3 from 1 to 2\nmove 1 to 2";
+        let (first, second) = source.split_once('\n').unwrap();
+        assert_eq!(Ok(["3", "1", "2"]), fields(source, first, "move {} from {} to {}"));
+        assert_eq!(Err(AocError::parse_at(2, 1, "expected `move {} from {} to {}`")), fields::<3>(source, second, "move {} from {} to {}"));
+        let monkeys = "root: pppw + sjmn\ndbpl:5";
+        assert_eq!(Ok(["root", "pppw + sjmn"]), fields(monkeys, &monkeys[..17], "{}: {}"));
+        assert_eq!(Ok(vec![("root", "pppw + sjmn"), ("dbpl", "5")]), key_values(monkeys, monkeys, ":"));
+    }
+
+    #[test]
+    pub fn reads_char_grids() {
+        let text = "#.\n.#\n##";
+        let grid: Grid<bool> = char_grid(text, Orientation::YUp, |_, value| Ok((value == '#').then_some(true))).unwrap();
+        assert!(grid.has_visited(&Point { x: 0, y: 2 }));
+        assert!(grid.has_visited(&Point { x: 1, y: 0 }));
+        assert!(!grid.has_visited(&Point { x: 1, y: 2 }));
+
+        let result: AocResult<Grid<()>> = char_grid(text, Orientation::YDown, |_, value| match value {
+            '.' => Ok(None),
+            _ => Err(AocError::parse(format!("unexpected `{}`", value)))
+        });
+        assert_eq!(Err(AocError::parse_at(1, 1, "unexpected `#`")), result.map(|_| ()));
+    }
+}
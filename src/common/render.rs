@@ -0,0 +1,445 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write as _;
+use std::path::Path;
+use std::time::Duration;
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use crate::common::movement::{Bounds, Orientation, Point};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+    pub const GREY: Rgb = Rgb(128, 128, 128);
+    pub const RED: Rgb = Rgb(220, 50, 47);
+    pub const GREEN: Rgb = Rgb(133, 153, 0);
+    pub const BLUE: Rgb = Rgb(38, 139, 210);
+    pub const YELLOW: Rgb = Rgb(231, 200, 60);
+}
+
+// One cell of a picture: the glyph used in text output and the colour used everywhere else
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Cell {
+    pub glyph: char,
+    pub colour: Rgb,
+}
+
+impl Cell {
+    pub fn new(glyph: char, colour: Rgb) -> Cell {
+        Cell { glyph, colour }
+    }
+
+    // Empty space, drawn as `.` on a black background
+    pub fn empty() -> Cell {
+        Cell::new('.', Rgb::BLACK)
+    }
+}
+
+// A rectangle of cells with row 0 at the top, ready to be written out in any of the supported formats
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize, fill: Cell) -> Frame {
+        Frame {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    // Draws every point within the bounds, asking `cell_at` what goes there
+    pub fn from_bounds<F: FnMut(&Point) -> Cell>(x_bounds: Bounds, y_bounds: Bounds, orientation: Orientation, mut cell_at: F) -> Frame {
+        let width = (x_bounds.max - x_bounds.min + 1) as usize;
+        let height = (y_bounds.max - y_bounds.min + 1) as usize;
+        let mut frame = Frame::new(width, height, Cell::empty());
+        for row in 0..height {
+            let y = match orientation {
+                Orientation::YUp => y_bounds.max - row as i128,
+                Orientation::YDown => y_bounds.min + row as i128
+            };
+            for column in 0..width {
+                frame.set(column, row, cell_at(&Point { x: x_bounds.min + column as i128, y }));
+            }
+        }
+        frame
+    }
+
+    pub fn set(&mut self, column: usize, row: usize, cell: Cell) {
+        self.cells[row * self.width + column] = cell;
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        // `max` keeps chunks happy with zero-width frames, which have no rows to give anyway
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn to_ascii(&self) -> String {
+        self.rows().map(|row| row.iter().map(|cell| cell.glyph).collect::<String>()).collect::<Vec<String>>().join("\n")
+    }
+
+    // Glyphs in their colours using 24-bit terminal escapes
+    pub fn to_ansi(&self) -> String {
+        self.rows().map(|row| {
+            let mut line = String::new();
+            let mut current_colour = None;
+            for cell in row {
+                if current_colour != Some(cell.colour) {
+                    let Rgb(r, g, b) = cell.colour;
+                    write!(line, "\x1b[38;2;{};{};{}m", r, g, b).unwrap();
+                    current_colour = Some(cell.colour);
+                }
+                line.push(cell.glyph);
+            }
+            line.push_str("\x1b[0m");
+            line
+        }).collect::<Vec<String>>().join("\n")
+    }
+
+    // Each cell becomes a `scale` by `scale` square of its colour, row by row from the top
+    fn pixels(&self, scale: usize) -> impl Iterator<Item = Vec<Rgb>> + '_ {
+        self.rows().flat_map(move |row| {
+            let pixel_row: Vec<Rgb> = row.iter().flat_map(|cell| std::iter::repeat_n(cell.colour, scale)).collect();
+            std::iter::repeat_n(pixel_row, scale)
+        })
+    }
+
+    pub fn to_png(&self, scale: usize) -> Vec<u8> {
+        let mut header = vec![];
+        header.extend((self.width as u32 * scale as u32).to_be_bytes());
+        header.extend((self.height as u32 * scale as u32).to_be_bytes());
+        // 8 bits per channel, RGB, default compression, filtering and no interlacing
+        header.extend([8, 2, 0, 0, 0]);
+
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        for pixel_row in self.pixels(scale) {
+            // Each row starts with its filter type, 0 being none
+            encoder.write_all(&[0]).unwrap();
+            encoder.write_all(&pixel_row.iter().flat_map(|&Rgb(r, g, b)| [r, g, b]).collect::<Vec<u8>>()).unwrap();
+        }
+        let image_data = encoder.finish().unwrap();
+
+        let mut bytes = b"\x89PNG\r\n\x1a\n".to_vec();
+        write_png_chunk(&mut bytes, b"IHDR", &header);
+        write_png_chunk(&mut bytes, b"IDAT", &image_data);
+        write_png_chunk(&mut bytes, b"IEND", &[]);
+        bytes
+    }
+}
+
+fn write_png_chunk(bytes: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    bytes.extend((data.len() as u32).to_be_bytes());
+    let start = bytes.len();
+    bytes.extend(kind);
+    bytes.extend(data);
+    let crc = crc32fast::hash(&bytes[start..]);
+    bytes.extend(crc.to_be_bytes());
+}
+
+// Frames of a simulation, shown one after the other as an animated GIF
+pub struct Animation {
+    frames: Vec<Frame>,
+    frame_delay: Duration,
+}
+
+impl Animation {
+    pub fn new(frame_delay: Duration) -> Animation {
+        Animation {
+            frames: vec![],
+            frame_delay,
+        }
+    }
+
+    // A single picture, which is saved as a PNG rather than a GIF
+    pub fn still(frame: Frame) -> Animation {
+        Animation {
+            frames: vec![frame],
+            frame_delay: Duration::ZERO,
+        }
+    }
+
+    pub fn push(&mut self, frame: Frame) {
+        self.frames.push(frame);
+    }
+
+    pub fn last(&self) -> Option<&Frame> {
+        self.frames.last()
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // Every frame must be the same size, and GIFs can't use more than 256 colours across all of them
+    pub fn to_gif(&self, scale: usize) -> Result<Vec<u8>, String> {
+        let first = self.frames.first().ok_or("An animation needs at least one frame")?;
+        let (width, height) = (first.width * scale, first.height * scale);
+        if let Some(frame) = self.frames.iter().find(|frame| (frame.width, frame.height) != (first.width, first.height)) {
+            return Err(format!("Frames must all be {}x{}, found one that is {}x{}", first.width, first.height, frame.width, frame.height));
+        }
+        if width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(format!("{}x{} is too large for a GIF", width, height));
+        }
+
+        let mut palette: Vec<Rgb> = vec![];
+        let mut palette_indices: HashMap<Rgb, u8> = HashMap::new();
+        for cell in self.frames.iter().flat_map(|frame| frame.cells.iter()) {
+            if let Entry::Vacant(entry) = palette_indices.entry(cell.colour) {
+                if palette.len() == 256 {
+                    return Err("GIFs can't use more than 256 colours".to_string());
+                }
+                entry.insert(palette.len() as u8);
+                palette.push(cell.colour);
+            }
+        }
+        // The colour table holds a power of two colours, at least 4 so the codes start at 2 bits
+        let palette_bits = (usize::BITS - (palette.len().max(4) - 1).leading_zeros()) as u8;
+        palette.resize(1 << palette_bits, Rgb::BLACK);
+
+        let mut bytes = b"GIF89a".to_vec();
+        bytes.extend((width as u16).to_le_bytes());
+        bytes.extend((height as u16).to_le_bytes());
+        // Global colour table present, 8 bits per channel, then the table size as a power of two
+        bytes.extend([0x80 | 0x70 | (palette_bits - 1), 0, 0]);
+        bytes.extend(palette.iter().flat_map(|&Rgb(r, g, b)| [r, g, b]));
+        // Loop forever
+        bytes.extend(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+        let delay = (self.frame_delay.as_millis() / 10).min(u16::MAX as u128) as u16;
+        for frame in &self.frames {
+            bytes.extend([0x21, 0xf9, 4, 0]);
+            bytes.extend(delay.to_le_bytes());
+            bytes.extend([0, 0]);
+
+            bytes.push(0x2c);
+            bytes.extend([0, 0, 0, 0]);
+            bytes.extend((width as u16).to_le_bytes());
+            bytes.extend((height as u16).to_le_bytes());
+            bytes.push(0);
+
+            let indices: Vec<u8> = frame.pixels(scale).flatten().map(|colour| palette_indices[&colour]).collect();
+            bytes.push(palette_bits);
+            for block in lzw_encode(&indices, palette_bits).chunks(255) {
+                bytes.push(block.len() as u8);
+                bytes.extend(block);
+            }
+            bytes.push(0);
+        }
+
+        bytes.push(0x3b);
+        Ok(bytes)
+    }
+
+    // A PNG for a single frame and a GIF for anything longer, along with the file extension that goes with it
+    pub fn encode(&self, scale: usize) -> Result<(&'static str, Vec<u8>), String> {
+        match self.frames.as_slice() {
+            [frame] => Ok(("png", frame.to_png(scale))),
+            _ => Ok(("gif", self.to_gif(scale)?))
+        }
+    }
+}
+
+const MAX_LZW_CODES: u16 = 4096;
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    buffered_bits: u8,
+}
+
+impl BitWriter {
+    // GIF packs codes starting from the least significant bit
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.buffered_bits;
+        self.buffered_bits += size;
+        while self.buffered_bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.buffered_bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.buffered_bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+// GIF flavoured LZW: variable-width codes that grow up to 12 bits, clearing the table when it fills up
+fn lzw_encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear_code: u16 = 1 << min_code_size;
+    let end_code = clear_code + 1;
+    let mut writer = BitWriter { bytes: vec![], buffer: 0, buffered_bits: 0 };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end_code + 1;
+    let mut code_size = min_code_size + 1;
+
+    writer.write(clear_code, code_size);
+    let mut prefix = match indices.first() {
+        Some(&first) => first as u16,
+        None => {
+            writer.write(end_code, code_size);
+            return writer.finish();
+        }
+    };
+
+    for &index in &indices[1..] {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+
+        writer.write(prefix, code_size);
+        if next_code < MAX_LZW_CODES {
+            table.insert((prefix, index), next_code);
+            next_code += 1;
+            // The decoder adds each code one step later than this, so it only needs the wider codes from here on
+            if next_code > (1 << code_size) && code_size < 12 {
+                code_size += 1;
+            }
+        } else {
+            writer.write(clear_code, code_size);
+            table.clear();
+            next_code = end_code + 1;
+            code_size = min_code_size + 1;
+        }
+        prefix = index as u16;
+    }
+
+    writer.write(prefix, code_size);
+    writer.write(end_code, code_size);
+    writer.finish()
+}
+
+pub fn save(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    fs::write(path, bytes).map_err(|err| format!("Could not write {}: {}", path.display(), err))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::Read;
+    use flate2::read::ZlibDecoder;
+    use super::*;
+
+    fn sample_frame() -> Frame {
+        Frame::from_bounds(Bounds::new(0, 2), Bounds::new(0, 1), Orientation::YUp, |point| match (point.x, point.y) {
+            (0, 0) => Cell::new('#', Rgb::RED),
+            (2, 1) => Cell::new('o', Rgb::YELLOW),
+            _ => Cell::empty()
+        })
+    }
+
+    // Undoes `lzw_encode`, following the GIF decoding rules rather than the encoder's bookkeeping
+    fn lzw_decode(bytes: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear_code = 1usize << min_code_size;
+        let end_code = clear_code + 1;
+        let reset_table = || (0..clear_code).map(|i| vec![i as u8]).chain([vec![], vec![]]).collect::<Vec<Vec<u8>>>();
+        let mut table = reset_table();
+        let mut code_size = min_code_size + 1;
+        let (mut buffer, mut buffered_bits, mut position) = (0u32, 0u8, 0);
+        let mut previous: Option<Vec<u8>> = None;
+        let mut output = vec![];
+        loop {
+            while buffered_bits < code_size {
+                buffer |= (bytes[position] as u32) << buffered_bits;
+                buffered_bits += 8;
+                position += 1;
+            }
+            let code = (buffer & ((1 << code_size) - 1)) as usize;
+            buffer >>= code_size;
+            buffered_bits -= code_size;
+
+            if code == clear_code {
+                table = reset_table();
+                code_size = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end_code {
+                return output;
+            }
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => [previous.clone(), vec![previous[0]]].concat(),
+                (None, None) => panic!("code {} before anything was decoded", code)
+            };
+            if let Some(previous) = previous {
+                if table.len() < MAX_LZW_CODES as usize {
+                    table.push([previous, vec![entry[0]]].concat());
+                }
+                if table.len() == 1 << code_size && code_size < 12 {
+                    code_size += 1;
+                }
+            }
+            output.extend(&entry);
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    pub fn renders_text() {
+        let frame = sample_frame();
+        assert_eq!("..o\n#..", frame.to_ascii());
+        assert_eq!("\x1b[38;2;0;0;0m..\x1b[38;2;231;200;60mo\x1b[0m\n\x1b[38;2;220;50;47m#\x1b[38;2;0;0;0m..\x1b[0m", frame.to_ansi());
+        let flipped = Frame::from_bounds(Bounds::new(0, 2), Bounds::new(0, 1), Orientation::YDown, |point| Cell::new(if point.y == 0 { '0' } else { '1' }, Rgb::WHITE));
+        assert_eq!("000\n111", flipped.to_ascii());
+    }
+
+    #[test]
+    pub fn renders_images() {
+        let frame = sample_frame();
+        let png = frame.to_png(2);
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR\x00\x00\x00\x06\x00\x00\x00\x04"));
+        let idat_length = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+        assert_eq!(b"IDAT", &png[37..41]);
+        let mut rows = vec![];
+        ZlibDecoder::new(&png[41..41 + idat_length]).read_to_end(&mut rows).unwrap();
+        assert_eq!(4 * (1 + 6 * 3), rows.len());
+        assert_eq!([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 231, 200, 60, 231, 200, 60], rows[..19]);
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+    }
+
+    #[test]
+    pub fn encodes_animation() {
+        let mut animation = Animation::new(Duration::from_millis(100));
+        animation.push(sample_frame());
+        animation.push(Frame::new(3, 2, Cell::new('#', Rgb::GREEN)));
+        let gif = animation.to_gif(1).unwrap();
+        assert!(gif.starts_with(b"GIF89a\x03\x00\x02\x00"));
+        assert!(gif.ends_with(b"\x3b"));
+        assert_eq!(Ok(("gif", gif)), animation.encode(1));
+
+        animation.push(Frame::new(4, 2, Cell::empty()));
+        assert!(animation.to_gif(1).is_err());
+
+        let still = Animation::still(sample_frame());
+        assert_eq!(Ok(("png", sample_frame().to_png(1))), still.encode(1));
+        assert_eq!(Some(&sample_frame()), still.last());
+        assert!(Animation::new(Duration::ZERO).encode(1).is_err());
+    }
+
+    #[test]
+    pub fn lzw_round_trips() {
+        let long: Vec<u8> = (0..20_000u32).map(|i| ((i * 7 + i / 13) % 5) as u8).collect();
+        for indices in [vec![], vec![1], vec![0, 0, 0, 0, 1, 1, 2, 0, 0, 0, 0], long] {
+            assert_eq!(indices, lzw_decode(&lzw_encode(&indices, 3), 3));
+        }
+        let noisy: Vec<u8> = (0..50_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+        assert_eq!(noisy, lzw_decode(&lzw_encode(&noisy, 8), 8));
+    }
+}
@@ -1,9 +1,26 @@
+use std::any::Any;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc;
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::alloc_stats;
+use crate::alloc_stats::AllocStats;
+use crate::cancel::CancellationToken;
+use crate::common::numeric::Arithmetic;
+use crate::common::render::Animation;
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Part, PartContext, Solution};
+use crate::vm::debugger::Trace;
+
 pub mod day1;
 pub mod day2;
 pub mod day3;
 pub mod day4;
 pub mod day5;
 pub mod day6;
+pub mod day7;
 pub mod day8;
 pub mod day9;
 pub mod day10;
@@ -20,3 +37,312 @@ pub mod day20;
 pub mod day21;
 pub mod day22;
 pub mod day23;
+
+// How long to wait past the budget for a part that ignores its cancellation token before leaving it behind
+const ABANDON_GRACE: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PartOutcome {
+    Solved(Answer),
+    TimedOut,
+    Failed(AocError),
+}
+
+#[derive(Clone)]
+pub struct PartResult {
+    pub part: Part,
+    pub outcome: PartOutcome,
+    pub elapsed: Duration,
+    // Only counted when built with the `alloc-stats` feature
+    pub memory: Option<AllocStats>,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct RunSettings {
+    pub parallel_parts: bool,
+    pub part_timeout: Option<Duration>,
+    pub arithmetic: Arithmetic,
+}
+
+pub struct DayResult {
+    pub parse_elapsed: Duration,
+    pub parts: Vec<PartResult>,
+}
+
+#[derive(Copy, Clone)]
+pub struct Day {
+    pub number: u32,
+    trim_input: bool,
+    run: fn(&str, &[Part], &RunSettings, &PartReporter) -> AocResult<DayResult>,
+    render: fn(&str) -> AocResult<Option<Animation>>,
+    trace: fn(&str, &[String]) -> AocResult<Option<Trace>>,
+}
+
+// Hears about each part as soon as it has finished, possibly from another thread
+type PartReporter<'a> = dyn Fn(&PartResult) + Sync + 'a;
+
+// What a day running on its own thread sends back
+enum Progress {
+    Part(PartResult),
+    Finished(AocResult<DayResult>),
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+        (Some(message), _) => message.to_string(),
+        (_, Some(message)) => message.clone(),
+        _ => "unknown cause".to_string()
+    }
+}
+
+// Turns a panic in `f` into an error, so one broken solver doesn't take the rest of the run down with it
+fn catch_panic<R, F: FnOnce() -> AocResult<R>>(f: F) -> AocResult<R> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| Err(AocError::panic(panic_message(payload.as_ref()))))
+}
+
+fn run_part<S: Solution>(parsed: &S::Input<'_>, part: Part, settings: &RunSettings) -> PartResult {
+    let context = PartContext {
+        cancel: match settings.part_timeout {
+            Some(budget) => CancellationToken::after(budget),
+            None => CancellationToken::never()
+        },
+        arithmetic: settings.arithmetic,
+    };
+    let part_start = Instant::now();
+    let (answer, memory) = alloc_stats::measure(|| catch_panic(|| match part {
+        Part::One => S::part1_with(parsed, &context),
+        Part::Two => S::part2_with(parsed, &context)
+    }));
+    PartResult {
+        part,
+        // Late answers might have been cut short, so they're not trusted
+        outcome: match answer {
+            _ if context.cancel.is_cancelled() => PartOutcome::TimedOut,
+            Ok(answer) => PartOutcome::Solved(answer),
+            Err(error) => PartOutcome::Failed(error)
+        },
+        elapsed: part_start.elapsed(),
+        memory,
+    }
+}
+
+fn run<S: Solution>(input: &str, parts: &[Part], settings: &RunSettings, report: &PartReporter) -> AocResult<DayResult> {
+    let parse_start = Instant::now();
+    let parsed = catch_panic(|| S::parse(input))?;
+    let parse_elapsed = parse_start.elapsed();
+
+    let parts = if settings.parallel_parts {
+        let parsed = &parsed;
+        thread::scope(|scope| {
+            let handles: Vec<_> = parts.iter().map(|&part| scope.spawn(move || {
+                let part_result = run_part::<S>(parsed, part, settings);
+                report(&part_result);
+                part_result
+            })).collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        })
+    } else {
+        parts.iter().map(|&part| {
+            let part_result = run_part::<S>(&parsed, part, settings);
+            report(&part_result);
+            part_result
+        }).collect()
+    };
+
+    Ok(DayResult {
+        parse_elapsed,
+        parts,
+    })
+}
+
+fn render<S: Solution>(input: &str) -> AocResult<Option<Animation>> {
+    catch_panic(|| S::parse(input).and_then(|parsed| S::render(&parsed)))
+}
+
+fn trace<S: Solution>(input: &str, breakpoints: &[String]) -> AocResult<Option<Trace>> {
+    catch_panic(|| S::parse(input).and_then(|parsed| S::trace(&parsed, breakpoints)))
+}
+
+impl Day {
+    fn new<S: Solution>(number: u32) -> Day {
+        Day {
+            number,
+            trim_input: S::TRIM_INPUT,
+            run: run::<S>,
+            render: render::<S>,
+            trace: trace::<S>,
+        }
+    }
+
+    // None for days without anything to draw
+    pub fn render(&self, input: &str) -> AocResult<Option<Animation>> {
+        if self.trim_input {
+            (self.render)(input.trim())
+        } else {
+            (self.render)(input)
+        }
+    }
+
+    // None for days that don't run a program
+    pub fn trace(&self, input: &str, breakpoints: &[String]) -> AocResult<Option<Trace>> {
+        if self.trim_input {
+            (self.trace)(input.trim(), breakpoints)
+        } else {
+            (self.trace)(input, breakpoints)
+        }
+    }
+
+    pub fn run(&self, input: &str, parts: &[Part], settings: &RunSettings) -> AocResult<DayResult> {
+        self.run_reporting(input, parts, settings, &|_| ())
+    }
+
+    fn run_reporting(&self, input: &str, parts: &[Part], settings: &RunSettings, report: &PartReporter) -> AocResult<DayResult> {
+        if self.trim_input {
+            (self.run)(input.trim(), parts, settings, report)
+        } else {
+            (self.run)(input, parts, settings, report)
+        }
+    }
+
+    // Like run, but a solver that ignores its cancellation token can't hold up the caller for much longer than
+    // its budget. It's left running on its own thread instead, and the parts that hadn't finished by then are reported
+    // as timed out.
+    pub fn run_within_budget(&self, input: String, parts: &[Part], settings: &RunSettings) -> AocResult<DayResult> {
+        let part_timeout = match settings.part_timeout {
+            Some(part_timeout) => part_timeout,
+            None => return self.run(input.as_str(), parts, settings)
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        let (day, day_parts, day_settings) = (*self, parts.to_vec(), *settings);
+        thread::spawn(move || {
+            // The receiver is gone if we were abandoned, and there's nobody left to tell
+            let part_sender = sender.clone();
+            let report = move |part_result: &PartResult| {
+                let _ = part_sender.send(Progress::Part(part_result.clone()));
+            };
+            let _ = sender.send(Progress::Finished(day.run_reporting(input.as_str(), &day_parts, &day_settings, &report)));
+        });
+
+        // Parsing gets a budget of its own, on top of one per part
+        let day_budget = part_timeout * (parts.len() as u32 + 1) + ABANDON_GRACE;
+        let start = Instant::now();
+        let mut finished_parts: Vec<PartResult> = vec![];
+        loop {
+            match receiver.recv_timeout(day_budget.saturating_sub(start.elapsed())) {
+                Ok(Progress::Part(part_result)) => finished_parts.push(part_result),
+                Ok(Progress::Finished(result)) => return result,
+                Err(RecvTimeoutError::Timeout) => break,
+                // Panics are caught inside `run`, so this is something going wrong outside the solver
+                Err(RecvTimeoutError::Disconnected) => return Err(AocError::panic("the day stopped without reporting a result"))
+            }
+        }
+
+        Ok(DayResult {
+            parse_elapsed: Duration::ZERO,
+            parts: parts.iter().map(|&part| match finished_parts.iter().position(|part_result| part_result.part == part) {
+                Some(index) => finished_parts.swap_remove(index),
+                None => PartResult {
+                    part,
+                    outcome: PartOutcome::TimedOut,
+                    elapsed: start.elapsed(),
+                    memory: None,
+                }
+            }).collect(),
+        })
+    }
+}
+
+pub fn all() -> Vec<Day> {
+    vec![
+        Day::new::<day1::Day1>(1),
+        Day::new::<day2::Day2>(2),
+        Day::new::<day3::Day3>(3),
+        Day::new::<day4::Day4>(4),
+        Day::new::<day5::Day5>(5),
+        Day::new::<day6::Day6>(6),
+        Day::new::<day7::Day7>(7),
+        Day::new::<day8::Day8>(8),
+        Day::new::<day9::Day9>(9),
+        Day::new::<day10::Day10>(10),
+        Day::new::<day11::Day11>(11),
+        Day::new::<day12::Day12>(12),
+        Day::new::<day13::Day13>(13),
+        Day::new::<day14::Day14>(14),
+        Day::new::<day15::Day15>(15),
+        Day::new::<day16::Day16>(16),
+        Day::new::<day17::Day17>(17),
+        Day::new::<day18::Day18>(18),
+        Day::new::<day19::Day19>(19),
+        Day::new::<day20::Day20>(20),
+        Day::new::<day21::Day21>(21),
+        Day::new::<day22::Day22>(22),
+        Day::new::<day23::Day23>(23),
+    ]
+}
+
+pub fn find(number: u32) -> Option<Day> {
+    all().into_iter().find(|day| day.number == number)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    // Part 1 panics while part 2 works, and parsing panics on an empty input
+    struct Broken;
+
+    impl Solution for Broken {
+        type Input<'a> = &'a str;
+
+        fn parse(input: &str) -> AocResult<&str> {
+            assert!(!input.is_empty(), "no input");
+            Ok(input)
+        }
+
+        fn part1(_input: &&str) -> AocResult<Answer> {
+            panic!("part 1 is broken")
+        }
+
+        fn part2(input: &&str) -> AocResult<Answer> {
+            Ok(input.len().into())
+        }
+    }
+
+    #[test]
+    pub fn reports_panics() {
+        let day = Day::new::<Broken>(99);
+        let result = day.run("abc", &Part::all(), &RunSettings::default()).unwrap();
+        let outcomes: Vec<PartOutcome> = result.parts.into_iter().map(|part_result| part_result.outcome).collect();
+        assert_eq!(vec![PartOutcome::Failed(AocError::panic("part 1 is broken")), PartOutcome::Solved(Answer::Number(3))], outcomes);
+        assert_eq!(Some(AocError::panic("no input")), day.run("", &Part::all(), &RunSettings::default()).err());
+    }
+
+    // Part 1 is quick while part 2 never looks at its cancellation token
+    struct Stuck;
+
+    impl Solution for Stuck {
+        type Input<'a> = &'a str;
+
+        fn parse(input: &str) -> AocResult<&str> {
+            Ok(input)
+        }
+
+        fn part1(input: &&str) -> AocResult<Answer> {
+            Ok(input.len().into())
+        }
+
+        fn part2(_input: &&str) -> AocResult<Answer> {
+            thread::sleep(Duration::from_secs(5));
+            Ok(Answer::Number(0))
+        }
+    }
+
+    #[test]
+    pub fn keeps_finished_parts() {
+        let settings = RunSettings { part_timeout: Some(Duration::from_millis(50)), ..RunSettings::default() };
+        let result = Day::new::<Stuck>(99).run_within_budget("abc".to_string(), &Part::all(), &settings).unwrap();
+        let outcomes: Vec<PartOutcome> = result.parts.into_iter().map(|part_result| part_result.outcome).collect();
+        assert_eq!(vec![PartOutcome::Solved(Answer::Number(3)), PartOutcome::TimedOut], outcomes);
+    }
+}
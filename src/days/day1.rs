@@ -1,25 +1,25 @@
-use crate::common;
+use crate::common::parse;
+use crate::error::AocResult;
+use crate::solution::{Answer, Solution};
 
-struct Day1 {
+pub struct Day1 {
     inventories: Vec<i32>
 }
 
-fn parse_input(input: &str) -> Vec<i32> {
-    let mut inventories = input
-        .trim()
-        .split("\n\n")
-        .map(|inventory| inventory.split("\n").map(|value| value.parse::<i32>().expect("Could not parse to int")).sum::<i32>())
-        .collect::<Vec<i32>>();
+fn parse_input(input: &str) -> AocResult<Vec<i32>> {
+    let mut inventories = parse::blocks(input.trim())
+        .map(|inventory| parse::lines(inventory).map(|value| parse::integer::<i32>(input, value)).sum::<AocResult<i32>>())
+        .collect::<AocResult<Vec<i32>>>()?;
     inventories.sort_by(|a, b| b.cmp(a));
-    inventories
+    Ok(inventories)
 }
 
 
 impl Day1 {
-    fn new(input: &str) -> Day1 {
-        Day1 {
-            inventories: parse_input(input)
-        }
+    fn new(input: &str) -> AocResult<Day1> {
+        Ok(Day1 {
+            inventories: parse_input(input)?
+        })
     }
 
     pub fn part1(&self) -> i32 {
@@ -31,12 +31,20 @@ impl Day1 {
     }
 }
 
-pub fn run() {
-    println!("Day 1");
-    let input = common::read_input(1);
-    let day = Day1::new(input.as_str());
-    println!("Part 1: {}", day.part1());
-    println!("Part 2: {}", day.part2());
+impl Solution for Day1 {
+    type Input<'a> = Day1;
+
+    fn parse(input: &str) -> AocResult<Day1> {
+        Day1::new(input)
+    }
+
+    fn part1(input: &Day1) -> AocResult<Answer> {
+        Ok(input.part1().into())
+    }
+
+    fn part2(input: &Day1) -> AocResult<Answer> {
+        Ok(input.part2().into())
+    }
 }
 
 #[cfg(test)]
@@ -60,7 +68,7 @@ mod tests {
 
 10000";
 
-        let mut day = Day1::new(input);
+        let mut day = Day1::new(input).unwrap();
         assert_eq!(24000, day.part1());
     }
 }
\ No newline at end of file
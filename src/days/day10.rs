@@ -1,54 +1,109 @@
-use itertools::Itertools;
-use crate::common::{read_input};
-use crate::vm::{VirtualMachine, Instruction};
-
-const CRT_WIDTH: usize = 40;
-const CRT_HEIGHT: usize = 6;
-
-fn part1(input: &str) -> i128 {
-    let instructions = input.split("\n").map(|line| VirtualMachine::parse_instruction(line));
-    let mut vm = VirtualMachine::new();
-    let mut out: Vec<i128> = vec![];
-    for instruction in instructions {
-        vm.execute_instruction(instruction, |vm_ref| -> () {
-            let cycle = vm_ref.get_cycle() as i128;
-            if ((cycle - 20) % 40) == 0 {
-                let x = vm_ref.get_register_value("x").expect("Register X is missing");
-                out.push(cycle * x);
-            }
-        });
-    }
-    out.iter().sum()
+use crate::common::movement::{Bounds, Orientation};
+use crate::common::ocr;
+use crate::common::render::{Animation, Cell, Frame, Rgb};
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Solution};
+use crate::vm::{VirtualMachine, Instruction, RegisterSet};
+use crate::vm::assembler::assemble;
+use crate::vm::debugger;
+use crate::vm::debugger::Trace;
+use crate::vm::device::{Crt, SignalSampler};
+
+// The most cycles a program may take. The real inputs take 240, and programs with jumps could go on forever.
+const MAX_CYCLES: usize = 100_000;
+
+// The CPU only has x, which starts at 1
+fn registers() -> RegisterSet {
+    RegisterSet::new().with("x", 1)
 }
 
-fn part2(input: &str) -> String {
-    let instructions = input.split("\n").map(|line| VirtualMachine::parse_instruction(line));
-    let mut crt: Vec<Vec<char>> = vec![vec![]; CRT_HEIGHT];
-    let mut vm = VirtualMachine::new();
-
-    for instruction in instructions {
-        vm.execute_instruction(instruction, |vm_ref| {
-            let cycle = vm_ref.get_cycle();
-            let draw_y = (cycle - 1) / CRT_WIDTH;
-            let draw_x = ((cycle - 1) % CRT_WIDTH) as i128;
-            let x_register = vm_ref.get_register_value("x").unwrap_or(1);
-            let is_sprite_visible = (x_register - 1..=x_register + 1).contains(&draw_x);
-            crt[draw_y].push(if is_sprite_visible { '#' } else { '.' });
-        });
+fn parse_input(input: &str) -> AocResult<Vec<Instruction>> {
+    let program = assemble(input)?;
+    let register_set = registers();
+    for (instruction, &line_number) in program.instructions.iter().zip(&program.lines) {
+        register_set.resolve(instruction).map_err(|err| err.at_line(line_number))?;
     }
+    Ok(program.instructions)
+}
+
+// A CPU running the program, with nothing attached yet
+fn load(instructions: &[Instruction]) -> VirtualMachine {
+    let mut vm = VirtualMachine::new(registers());
+    vm.load(instructions.to_vec()).expect("registers are checked when parsing");
+    vm
+}
+
+fn part1(instructions: &[Instruction]) -> AocResult<Answer> {
+    let mut vm = load(instructions);
+    let sampler = vm.attach(Box::new(SignalSampler::new("x", 20, 40))).unwrap();
+    vm.run(MAX_CYCLES)?;
+    Ok(vm.device_output(sampler))
+}
+
+// What the CRT shows once the program has run, one line per row
+fn draw_crt(instructions: &[Instruction]) -> AocResult<String> {
+    let mut vm = load(instructions);
+    let crt = vm.attach(Box::new(Crt::new("x"))).unwrap();
+    // Later cycles would have nowhere to draw
+    vm.run_for(Crt::WIDTH * Crt::HEIGHT)?;
+    Ok(vm.device_output(crt).to_string())
+}
 
-    crt.iter().map(|line| line.iter().join("")).join("\n")
+// The letters on the CRT, or the picture itself when it doesn't spell anything
+fn part2(instructions: &[Instruction]) -> AocResult<String> {
+    let picture = draw_crt(instructions)?;
+    Ok(ocr::recognize(&picture).unwrap_or(picture))
 }
 
-pub fn run() {
-    println!("Day 10");
-    let input = read_input(10);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2:\n{}", part2(input.as_str()));
+// The CRT as it was drawn, for reading it by eye when part 2 has already turned it into letters
+fn render(instructions: &[Instruction]) -> AocResult<Frame> {
+    let picture = draw_crt(instructions)?;
+    let rows: Vec<&[u8]> = picture.lines().map(|row| row.as_bytes()).collect();
+    let x_bounds = Bounds::new(0, Crt::WIDTH as i128 - 1);
+    let y_bounds = Bounds::new(0, rows.len() as i128 - 1);
+    Ok(Frame::from_bounds(x_bounds, y_bounds, Orientation::YDown, |point| {
+        match rows[point.y as usize].get(point.x as usize) {
+            Some(b'#') => Cell::new('#', Rgb::GREEN),
+            _ => Cell::empty()
+        }
+    }))
+}
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    type Input<'a> = Vec<Instruction>;
+
+    fn parse(input: &str) -> AocResult<Vec<Instruction>> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Vec<Instruction>) -> AocResult<Answer> {
+        part1(input)
+    }
+
+    fn part2(input: &Vec<Instruction>) -> AocResult<Answer> {
+        part2(input).map(Answer::from)
+    }
+
+    fn render(input: &Vec<Instruction>) -> AocResult<Option<Animation>> {
+        render(input).map(|frame| Some(Animation::still(frame)))
+    }
+
+    fn trace(input: &Vec<Instruction>, breakpoints: &[String]) -> AocResult<Option<Trace>> {
+        // The table for a program that goes round the same loop forever would never end
+        if let Some(cycle) = load(input).find_loop(MAX_CYCLES as u128) {
+            return Err(AocError::parse(format!("the program repeats every {} instruction(s) after the first {}, so it never stops",
+                                               cycle.length, cycle.start)));
+        }
+        debugger::trace_program(load(input), breakpoints, MAX_CYCLES).map(Some)
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use crate::error::AocError;
+    use crate::vm::debugger::{Breakpoint, Debugger, StopReason};
     use super::*;
 
     const INPUT: &str = "addx 15
@@ -200,16 +255,68 @@ noop";
 
     #[test]
     pub fn part1() {
-        assert_eq!(13140, super::part1(INPUT));
+        assert_eq!(Ok(Answer::Number(13140)), super::part1(&parse_input(INPUT).unwrap()));
     }
 
     #[test]
-    pub fn part2() {
+    pub fn x_during_cycle() {
+        // The signal strength uses x as it is during a cycle, before the instruction running then has finished
+        let mut debugger = Debugger::new(load(&parse_input(INPUT).unwrap()));
+        debugger.add_breakpoint(Breakpoint::Cycle(20));
+        assert_eq!(StopReason::Breakpoint(0), debugger.run());
+        assert_eq!(Some(21), debugger.vm().get_register_value("x"));
+    }
+
+    #[test]
+    pub fn refuses_to_trace_loops() {
+        let looping = assemble("start: noop\naddx 0\njmp start").unwrap().instructions;
+        assert_eq!(
+            Err(AocError::parse("the program repeats every 3 instruction(s) after the first 0, so it never stops")),
+            Day10::trace(&looping, &[]).map(|trace| trace.is_some())
+        );
+        // x keeps growing, so the state never repeats
+        let counting = assemble("start: noop\naddx 1\njmp start").unwrap().instructions;
+        assert_eq!(
+            Err(AocError::parse("the program is still running after 100000 cycle(s)")),
+            Day10::trace(&counting, &[]).map(|trace| trace.is_some())
+        );
+        assert_eq!(Err(AocError::parse("the program is still running after 100000 cycle(s)")), super::part1(&counting));
+        let trace = Day10::trace(&parse_input(INPUT).unwrap(), &[]).unwrap().unwrap();
+        assert_eq!(240, trace.cycles);
+    }
+
+    #[test]
+    pub fn faults_on_overflow() {
+        let instructions = parse_input("mov x -170141183460469231731687303715884105728\nnoop").unwrap();
+        // x is still 1 while the `mov` runs
+        assert_eq!(Ok("#.".to_string()), draw_crt(&instructions));
+        let instructions = parse_input("mov x 170141183460469231731687303715884105727\nnoop\nnoop").unwrap();
+        assert!(draw_crt(&instructions).is_ok());
+        let instructions = parse_input(&format!("mov x {}\n{}", i128::MAX / 10, "noop\n".repeat(20))).unwrap();
+        assert_eq!(Err(AocError::arithmetic(format!("signal strength 20 * {} overflows a 128-bit integer", i128::MAX / 10))),
+                   super::part1(&instructions));
+    }
+
+    #[test]
+    pub fn rejects_other_registers() {
+        assert_eq!(Err(AocError::parse("undeclared register `y`").at_line(2)), parse_input("noop\naddy 3"));
+    }
+
+    #[test]
+    pub fn draws_crt() {
         assert_eq!("##..##..##..##..##..##..##..##..##..##..
 ###...###...###...###...###...###...###.
 ####....####....####....####....####....
 #####.....#####.....#####.....#####.....
 ######......######......######......####
-#######.......#######.......#######.....", super::part2(INPUT));
+#######.......#######.......#######.....", draw_crt(&parse_input(INPUT).unwrap()).unwrap());
+        assert_eq!(draw_crt(&parse_input(INPUT).unwrap()), render(&parse_input(INPUT).unwrap()).map(|frame| frame.to_ascii()));
+    }
+
+    #[test]
+    pub fn part2() {
+        // The example doesn't spell anything, so the picture comes back as it is
+        let instructions = parse_input(INPUT).unwrap();
+        assert_eq!(draw_crt(&instructions), super::part2(&instructions));
     }
 }
@@ -1,148 +1,212 @@
 use std::collections::{HashMap, HashSet, VecDeque};
-use itertools::Itertools;
 
-use crate::common::read_input;
+use crate::common::parse_number_in;
+use crate::common::numeric::{with_number_type, Number};
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, PartContext, Solution};
 
+#[derive(Clone)]
 enum Operation {
     Add,
     Multiply
 }
 
+#[derive(Clone)]
 enum Identifier {
     Old,
-    Value(u128)
+    Value(i128)
 }
 
 impl Identifier {
-    pub fn value(&self, old: &u128) -> u128 {
+    pub fn value<N: Number>(&self, old: &N) -> N {
         match self {
-            Identifier::Old => *old,
-            Identifier::Value(value) => *value
+            Identifier::Old => old.clone(),
+            Identifier::Value(value) => N::from_i128(*value)
         }
     }
 }
 
 type MonkeyEquation = (Identifier, Operation, Identifier);
 
-fn parse_identifier(value: &str) -> Identifier {
+fn parse_identifier(source: &str, value: &str) -> AocResult<Identifier> {
     if value == "old" {
-        return Identifier::Old;
+        return Ok(Identifier::Old);
     }
-    return Identifier::Value(value.parse().unwrap());
+    Ok(Identifier::Value(parse_number_in(source, value)?))
 }
 
-fn parse_equation(equation: &str) -> MonkeyEquation {
+fn parse_equation(source: &str, equation: &str) -> AocResult<MonkeyEquation> {
     let pieces: Vec<&str> = equation.split(" ").collect();
-    let left = parse_identifier(pieces[0]);
+    if pieces.len() != 3 {
+        return Err(AocError::parse_in(source, equation, "expected `<left> <operation> <right>`"));
+    }
+    let left = parse_identifier(source, pieces[0])?;
     let operation = match pieces[1] {
         "*" => Operation::Multiply,
         "+" => Operation::Add,
-        _ => panic!("bad op str")
+        _ => return Err(AocError::parse_in(source, pieces[1], format!("unknown operation `{}`", pieces[1])))
     };
-    let right = parse_identifier(pieces[2]);
-    return (left, operation, right);
+    let right = parse_identifier(source, pieces[2])?;
+    return Ok((left, operation, right));
+}
+
+fn field_after<'a>(source: &str, line: &'a str, marker: &str) -> AocResult<&'a str> {
+    line.split_once(marker)
+        .map(|(_, value)| value)
+        .ok_or_else(|| AocError::parse_in(source, line, format!("expected `{}`", marker.trim())))
 }
 
-struct Monkey {
-    items: VecDeque<u128>,
-    is_bored: bool,
+// Worry levels are kept as whichever number type the rounds are run with
+#[derive(Clone)]
+pub struct Monkey<N = i128> {
+    items: VecDeque<N>,
     operation: MonkeyEquation,
-    test_amount: u128,
+    test_amount: i128,
     true_pass_id: usize,
     false_pass_id: usize,
     inspected_items: usize,
 }
 
 impl Monkey {
-    pub fn parse(lines: Vec<&str>, is_bored: bool) -> Monkey {
+    pub fn parse(source: &str, lines: Vec<&str>) -> AocResult<Monkey> {
+        if lines.len() < 6 {
+            return Err(AocError::parse_in(source, lines[0], "expected 6 lines describing the monkey"));
+        }
         let starting_items_raw = lines[1];
         let operation_raw = lines[2];
         let test = lines[3];
         let if_true = lines[4];
         let if_false = lines[5];
-        let (_, starting_item_values) = starting_items_raw.split_once("items: ").unwrap();
-        let starting_items: Vec<u128> = starting_item_values.split(", ").map(|item| u128::from(item.parse::<u128>().unwrap())).collect();
-        let (_, true_monkey_id_str) = if_true.split_once("monkey ").unwrap();
-        let (_, false_monkey_id_str) = if_false.split_once("monkey ").unwrap();
-        let (_, operation) = operation_raw.split_once("new = ").unwrap();
-        let (_, divisible_by_str) = test.split_once("by ").unwrap();
-        Monkey {
-            is_bored,
+        let starting_item_values = field_after(source, starting_items_raw, "items: ")?;
+        let starting_items: Vec<i128> = starting_item_values.split(", ").map(|item| parse_number_in(source, item)).collect::<AocResult<Vec<i128>>>()?;
+        let true_monkey_id_str = field_after(source, if_true, "monkey ")?;
+        let false_monkey_id_str = field_after(source, if_false, "monkey ")?;
+        let operation = field_after(source, operation_raw, "new = ")?;
+        let divisible_by_str = field_after(source, test, "by ")?;
+        Ok(Monkey {
             items: VecDeque::from(starting_items),
-            operation: parse_equation(operation),
-            true_pass_id: true_monkey_id_str.parse().unwrap(),
-            false_pass_id: false_monkey_id_str.parse().unwrap(),
-            test_amount: divisible_by_str.parse().unwrap(),
+            operation: parse_equation(source, operation)?,
+            true_pass_id: parse_number_in(source, true_monkey_id_str)?,
+            false_pass_id: parse_number_in(source, false_monkey_id_str)?,
+            test_amount: parse_number_in(source, divisible_by_str)?,
             inspected_items: 0
+        })
+    }
+
+    fn with_number_type<N: Number>(&self) -> Monkey<N> {
+        Monkey {
+            items: self.items.iter().map(|&item| N::from_i128(item)).collect(),
+            operation: self.operation.clone(),
+            test_amount: self.test_amount,
+            true_pass_id: self.true_pass_id,
+            false_pass_id: self.false_pass_id,
+            inspected_items: self.inspected_items,
         }
     }
 }
 
-fn do_operation((left, operation, right): &MonkeyEquation, value: &u128) -> u128 {
+fn do_operation<N: Number>((left, operation, right): &MonkeyEquation, value: &N) -> AocResult<N> {
     match operation {
-        Operation::Add => left.value(value) + right.value(value),
-        Operation::Multiply => left.value(value) * right.value(value)
+        Operation::Add => left.value(value).try_add(&right.value(value)),
+        Operation::Multiply => left.value(value).try_mul(&right.value(value))
     }
 }
 
-fn do_monkey_diff(monkey: &mut Monkey, mod_amount: u128) -> HashMap<usize, Vec<u128>> {
-    let mut diff: HashMap<usize, Vec<u128>> = HashMap::new();
-    while !monkey.items.is_empty() {
+fn do_monkey_diff<N: Number>(monkey: &mut Monkey<N>, is_bored: bool, mod_amount: &N) -> AocResult<HashMap<usize, Vec<N>>> {
+    let mut diff: HashMap<usize, Vec<N>> = HashMap::new();
+    let test_amount = N::from_i128(monkey.test_amount);
+    while let Some(mut item) = monkey.items.pop_front() {
         monkey.inspected_items += 1;
-        let mut item = monkey.items.pop_front().unwrap();
-        item = do_operation(&monkey.operation, &item);
-        if monkey.is_bored {
-            item /= u128::from(3 as u8);
-        }
-        item %= mod_amount;
-        if item % monkey.test_amount == 0 {
-            let true_pass_id = monkey.true_pass_id;
-            diff.entry(true_pass_id).or_insert(vec![]).push(item);
-        } else {
-            let false_pass_id = monkey.false_pass_id;
-            diff.entry(false_pass_id).or_insert(vec![]).push(item);
+        item = do_operation(&monkey.operation, &item)?;
+        if is_bored {
+            item = item.try_div(&N::from_i128(3))?;
         }
+        item = item.try_rem(mod_amount)?;
+        let pass_id = if item.try_rem(&test_amount)?.is_zero() { monkey.true_pass_id } else { monkey.false_pass_id };
+        diff.entry(pass_id).or_default().push(item);
     }
-    diff
+    Ok(diff)
 }
 
-fn do_round(monkeys: &mut Vec<Monkey>, mod_amount: u128) {
+fn do_round<N: Number>(monkeys: &mut [Monkey<N>], is_bored: bool, mod_amount: &N) -> AocResult<()> {
     for i in 0..monkeys.len() {
-        let item_diff = do_monkey_diff(&mut monkeys[i], mod_amount);
+        let item_diff = do_monkey_diff(&mut monkeys[i], is_bored, mod_amount)?;
         for (monkey_id, items) in item_diff {
             monkeys[monkey_id].items.extend(items);
         }
     }
+    Ok(())
 }
 
-fn do_rounds(input: &str, is_bored: bool, rounds: usize) -> usize {
-    let mut monkeys: Vec<Monkey> = input.split("\n\n").map(|group| Monkey::parse(group.split("\n").collect(), is_bored)).collect();
-    let unique_divisors: HashSet<u128> = monkeys.iter().map(|monkey| monkey.test_amount).collect();
-    let mod_amount: u128 = unique_divisors.iter().fold(1, |a, b| a * b) as u128;
+fn parse_input(input: &str) -> AocResult<Vec<Monkey>> {
+    let monkeys = input.split("\n\n")
+        .map(|group| Monkey::parse(input, group.split("\n").collect()))
+        .collect::<AocResult<Vec<Monkey>>>()?;
+
+    for monkey in &monkeys {
+        for pass_id in [monkey.true_pass_id, monkey.false_pass_id] {
+            if pass_id >= monkeys.len() {
+                return Err(AocError::parse(format!("monkey {} does not exist", pass_id)));
+            }
+        }
+    }
+
+    Ok(monkeys)
+}
+
+fn do_rounds<N: Number>(starting_monkeys: &[Monkey], is_bored: bool, rounds: usize) -> AocResult<N> {
+    let mut monkeys: Vec<Monkey<N>> = starting_monkeys.iter().map(|monkey| monkey.with_number_type()).collect();
+    // Every test still passes or fails the same way with worry levels kept below the product of the divisors
+    let unique_divisors: HashSet<i128> = monkeys.iter().map(|monkey| monkey.test_amount).collect();
+    let mod_amount = unique_divisors.iter().try_fold(N::from_i128(1), |product, &divisor| product.try_mul(&N::from_i128(divisor)))?;
     for _ in 0..rounds {
-        do_round(&mut monkeys, mod_amount);
+        do_round(&mut monkeys, is_bored, &mod_amount)?;
     }
     monkeys.sort_by(|a, b| b.inspected_items.cmp(&a.inspected_items));
-    monkeys[0].inspected_items * monkeys[1].inspected_items
+    N::from_i128(monkeys[0].inspected_items as i128).try_mul(&N::from_i128(monkeys[1].inspected_items as i128))
 }
 
-fn part1(input: &str) -> usize {
-    do_rounds(input, true, 20)
+fn part1<N: Number>(monkeys: &[Monkey]) -> AocResult<N> {
+    do_rounds(monkeys, true, 20)
 }
 
-fn part2(input: &str) -> usize {
-    do_rounds(input, false, 10_000)
+fn part2<N: Number>(monkeys: &[Monkey]) -> AocResult<N> {
+    do_rounds(monkeys, false, 10_000)
 }
 
-pub fn run() {
-    println!("Day 11");
-    let input = read_input(11);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+pub struct Day11;
+
+impl Solution for Day11 {
+    type Input<'a> = Vec<Monkey>;
+
+    fn parse(input: &str) -> AocResult<Vec<Monkey>> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Vec<Monkey>) -> AocResult<Answer> {
+        part1::<i128>(input).map(Answer::from)
+    }
+
+    fn part1_with(input: &Vec<Monkey>, context: &PartContext) -> AocResult<Answer> {
+        with_number_type!(context.arithmetic, N => part1::<N>(input).map(|value| value.to_answer()))
+    }
+
+    fn part2(input: &Vec<Monkey>) -> AocResult<Answer> {
+        part2::<i128>(input).map(Answer::from)
+    }
+
+    fn part2_with(input: &Vec<Monkey>, context: &PartContext) -> AocResult<Answer> {
+        with_number_type!(context.arithmetic, N => part2::<N>(input).map(|value| value.to_answer()))
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use num_bigint::BigInt;
+    use crate::common::numeric::Checked;
+    use crate::error::AocError;
+    use super::Monkey;
+
     const INPUT: &str = "Monkey 0:
   Starting items: 79, 98
   Operation: new = old * 19
@@ -173,11 +237,29 @@ Monkey 3:
 
     #[test]
     pub fn part1() {
-        assert_eq!(10605, super::part1(INPUT));
+        assert_eq!(Ok(10605), super::part1::<i128>(&super::parse_input(INPUT).unwrap()));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(2713310158, super::part2(INPUT));
+        assert_eq!(Ok(2713310158), super::part2::<i128>(&super::parse_input(INPUT).unwrap()));
+    }
+
+    #[test]
+    pub fn reports_overflow() {
+        // The product of the divisors alone is more than 128 bits can hold
+        let divisor = "1000000000000000000000";
+        let input = INPUT.replace("divisible by 23", &format!("divisible by {}", divisor))
+            .replace("divisible by 19", &format!("divisible by {}1", divisor));
+        let monkeys = super::parse_input(&input).unwrap();
+        assert!(super::part2::<Checked>(&monkeys).is_err());
+        assert_eq!(Ok(BigInt::from(10605)), super::part1::<BigInt>(&super::parse_input(INPUT).unwrap()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn invalid_monkey() {
+        let input = INPUT.replacen("divisible by 23", "divisible by seven", 1);
+        let lines: Vec<&str> = input.split("\n").take(6).collect();
+        assert_eq!(Some(AocError::parse_at(4, 22, "expected a number, got `seven`")), Monkey::parse(&input, lines).err());
+    }
+}
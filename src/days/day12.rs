@@ -1,16 +1,14 @@
-use std::cmp::Reverse;
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::hash::Hash;
-use itertools::Itertools;
-use priority_queue::PriorityQueue;
-use crate::common::movement::{Bounds, Grid, GridDirection, Point};
+use crate::common::movement::{DenseGrid, GridDirection, GridStorage, Orientation, Point};
 
-use crate::common::{char_alphabet_position, read_input};
+use crate::common::{char_alphabet_position, parse};
+use crate::common::graph::bfs;
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Solution};
 
-struct HillMap {
+pub struct HillMap {
     start: Point,
     goal: Point,
-    graph: Grid<u32>,
+    graph: DenseGrid<u32>,
 }
 
 impl HillMap {
@@ -43,116 +41,83 @@ impl HillMap {
             return None;
         }).collect()
     }
-
-    pub fn get_all_points(&self) -> Vec<Point> {
-        self.graph.get_y_bounds().to_range()
-            .flat_map(|y| self.graph.get_x_bounds().to_range().map(|x| Point { x, y }).collect::<Vec<Point>>())
-            .collect()
-    }
 }
 
-fn parse_input(input: &str) -> HillMap {
-    let lines: Vec<&str> = input.split("\n").collect();
+fn parse_input(input: &str) -> AocResult<HillMap> {
+    let lines: Vec<&str> = parse::lines(input).collect();
     let width = lines[0].len();
-    let height = lines.len();
-    let mut hill_map = HillMap {
-        start: Point::zero(),
-        goal: Point::zero(),
-        graph: Grid::new(),
-    };
-
-    for y in 0..height {
-        let line = lines[y];
-        for (x, value) in line.chars().enumerate() {
-            let position = Point { x: x as i128, y: y as i128 };
-
-            let elevation = match value {
-                'S' => {
-                    hill_map.start = position.clone();
-                    char_alphabet_position('a', true /*is_lowercase*/)
-                }
-                'E' => {
-                    hill_map.goal = position.clone();
-                    char_alphabet_position('z', true /*is_lowercase*/)
-                }
-                _ => char_alphabet_position(value, true /*is_lowercase*/)
-            };
-
-            hill_map.graph.visit(position, elevation);
+    for (y, line) in lines.iter().enumerate() {
+        if line.len() != width {
+            return Err(AocError::parse(format!("expected {} columns, got {}", width, line.len())).at_line(y + 1));
         }
     }
 
-    assert_eq!(Bounds::new(0, width as i128 - 1), hill_map.graph.get_x_bounds());
-    assert_eq!(Bounds::new(0, height as i128 - 1), hill_map.graph.get_y_bounds());
-
-    hill_map
-}
-
-fn map_djikstra<F>(map: &HillMap, source: Point, get_neighbors: F) -> (HashMap<Point, u128>, HashMap<Point, Point>)
-    where F: Fn(&Point) -> Vec<Point> {
-    let mut distances: HashMap<Point, u128> = HashMap::new();
-    let mut paths: HashMap<Point, Point> = HashMap::new();
-
-    let all_points = map.get_all_points();
-
-    for point in &all_points {
-        distances.insert(point.clone(), u128::MAX);
-    }
-    distances.insert(source, 0);
-
-    // let mut queue: HashSet<Point> = HashSet::from_iter(all_points.iter().map(|point| point.clone()));
-    let mut queue: PriorityQueue<Point, Reverse<u128>> = PriorityQueue::from_iter(all_points.iter().map(|point| (point.clone(), Reverse(distances.get(point).unwrap().clone()))));
-    while !queue.is_empty() {
-        let (current_point, distance_rev) = queue.pop().unwrap();
-        let Reverse(distance) = distance_rev;
-        if distance == u128::MAX {
-            // no other nodes can reach me, I am the last node(s)
-            continue;
-        }
-        let new_distance_to_neighbors = distance + 1;
-        for neighbor_point in get_neighbors(&current_point) {
-            let mut current_distance_to_neighbor = distances.get_mut(&neighbor_point).unwrap();
-            if new_distance_to_neighbors < *current_distance_to_neighbor {
-                *current_distance_to_neighbor = new_distance_to_neighbors;
-                paths.insert(neighbor_point, current_point.clone());
-                queue.change_priority(&neighbor_point, Reverse(new_distance_to_neighbors));
+    let mut start = None;
+    let mut goal = None;
+    let graph: DenseGrid<u32> = parse::char_grid(input, Orientation::YDown, |position, value| {
+        let elevation = match value {
+            'S' => {
+                start = Some(position);
+                char_alphabet_position('a', true /*is_lowercase*/)
             }
-        }
-    }
-
-    (distances, paths)
+            'E' => {
+                goal = Some(position);
+                char_alphabet_position('z', true /*is_lowercase*/)
+            }
+            'a'..='z' => char_alphabet_position(value, true /*is_lowercase*/),
+            _ => return Err(AocError::parse(format!("unexpected elevation `{}`", value)))
+        };
+        Ok(Some(elevation))
+    })?;
+
+    let (start, goal) = match (start, goal) {
+        (Some(start), Some(goal)) => (start, goal),
+        _ => return Err(AocError::parse("expected both a start `S` and a goal `E`"))
+    };
+    Ok(HillMap {
+        start,
+        goal,
+        graph,
+    })
 }
 
 fn path_to_exit(map: &HillMap) -> u128 {
-    let (distances, _) = map_djikstra(map, map.start, |current_point| map.get_accessible_neighbors(&current_point));
-    distances[&map.goal]
+    bfs([map.start], |current_point| map.get_accessible_neighbors(current_point), |&point| point == map.goal)
+        .goal_distance()
+        .unwrap()
 }
 
 fn path_from_exit(map: &HillMap) -> u128 {
-    let (distances, _) = map_djikstra(map, map.goal, |current_point| map.get_neighbors_that_could_access(&current_point));
-    distances.iter().filter_map(|(point, distance)| {
-        if *map.graph.get_value(point).unwrap() != 0 {
-            return None
-        }
-        Some(distance.clone())
-    }).min().unwrap()
+    // Walking backwards from the exit, the first lowest point reached is the closest one
+    bfs([map.goal], |current_point| map.get_neighbors_that_could_access(current_point), |point| *map.graph.get_value(point).unwrap() == 0)
+        .goal_distance()
+        .unwrap()
 }
 
-fn part1(input: &str) -> u128 {
-    let map = parse_input(input);
-    path_to_exit(&map)
+fn part1(map: &HillMap) -> u128 {
+    path_to_exit(map)
 }
 
-fn part2(input: &str) -> u128 {
-    let map = parse_input(input);
-    path_from_exit(&map)
+fn part2(map: &HillMap) -> u128 {
+    path_from_exit(map)
 }
 
-pub fn run() {
-    println!("Day 12");
-    let input = read_input(12);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+pub struct Day12;
+
+impl Solution for Day12 {
+    type Input<'a> = HillMap;
+
+    fn parse(input: &str) -> AocResult<HillMap> {
+        parse_input(input)
+    }
+
+    fn part1(input: &HillMap) -> AocResult<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &HillMap) -> AocResult<Answer> {
+        Ok(part2(input).into())
+    }
 }
 
 #[cfg(test)]
@@ -165,11 +130,11 @@ abdefghi";
 
     #[test]
     pub fn part1() {
-        assert_eq!(31, super::part1(INPUT));
+        assert_eq!(31, super::part1(&super::parse_input(INPUT).unwrap()));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(29, super::part2(INPUT));
+        assert_eq!(29, super::part2(&super::parse_input(INPUT).unwrap()));
     }
 }
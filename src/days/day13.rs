@@ -2,12 +2,13 @@ use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::Formatter;
 use itertools::{Itertools, min};
-use regex::Regex;
-use crate::common::{read_input};
+use crate::common::parse;
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Solution};
 use crate::vm::{VirtualMachine, Instruction};
 
 #[derive(Debug, Eq, PartialEq)]
-enum ListItem {
+pub enum ListItem {
     Value(u128),
     List(Vec<ListItem>)
 }
@@ -21,42 +22,49 @@ impl fmt::Display for ListItem {
     }
 }
 
-type ListPair = (ListItem, ListItem);
+pub type ListPair = (ListItem, ListItem);
 
-fn parse_list(input: &str) -> ListItem {
-    let number_regex = Regex::new(r"\d+").unwrap();
+fn parse_list(source: &str, input: &str) -> AocResult<ListItem> {
     let mut list_stack: Vec<Vec<ListItem>> = vec![];
     let mut i = 0;
     while i < input.len() {
-        match input.chars().nth(i).unwrap() {
-            '[' => list_stack.push(vec![]),
-            ']' => {
-                let child_list = list_stack.pop().unwrap();
+        let unexpected = |message: &str| AocError::parse_in(source, &input[i..], message);
+        match input.as_bytes()[i] {
+            b'[' => list_stack.push(vec![]),
+            b']' => {
+                let child_list = list_stack.pop().ok_or_else(|| unexpected("unmatched `]`"))?;
                 if list_stack.is_empty() {
                     // the child we just popped was the parent
-                    return ListItem::List(child_list);
+                    if i + 1 != input.len() {
+                        return Err(AocError::parse_in(source, &input[i + 1..], "unexpected text after the list"));
+                    }
+                    return Ok(ListItem::List(child_list));
                 } else {
                     list_stack.last_mut().unwrap().push(ListItem::List(child_list));
                 }
             },
-            ',' => (),
+            b',' => (),
             _ => {
-                let end_position = number_regex.find(&input[i..]).unwrap().end();
-                let value = input[i..i+end_position].parse().expect("Could not parse value");
-                list_stack.last_mut().unwrap().push(ListItem::Value(value));
+                let end_position = input[i..].bytes().take_while(u8::is_ascii_digit).count();
+                if end_position == 0 {
+                    return Err(unexpected("expected `[`, `]`, `,` or a number"));
+                }
+                let value = parse::integer(source, &input[i..i+end_position])?;
+                list_stack.last_mut().ok_or_else(|| unexpected("expected the packet to start with `[`"))?.push(ListItem::Value(value));
                 i += end_position;
                 continue;
             }
         }
         i += 1;
     }
-    panic!("List did not close");
+    Err(AocError::parse_in(source, input, "list did not close"))
 }
 
-fn parse_pairs(input: &str) -> Vec<ListPair> {
-    input.split("\n\n").map(|group| {
-        let (left, right) = group.split_once("\n").unwrap();
-        (parse_list(left), parse_list(right))
+fn parse_pairs(input: &str) -> AocResult<Vec<ListPair>> {
+    parse::blocks(input).map(|group| {
+        let (left, right) = group.split_once("\n")
+            .ok_or_else(|| AocError::parse_in(input, group, "expected a pair of packets"))?;
+        Ok((parse_list(input, left)?, parse_list(input, right)?))
     }).collect()
 }
 
@@ -65,31 +73,22 @@ fn value_as_list(value: &u128) -> ListItem {
 }
 
 fn get_left_ordering(left: &ListItem, right: &ListItem) -> Ordering {
-    if let (ListItem::Value(left_value), ListItem::Value(right_value)) = (&left, &right) {
-        return left_value.cmp(right_value);
-    }
-
-    if let (ListItem::List(left_items), ListItem::List(right_items)) = (left, right) {
-        let shared_item_count = std::cmp::min(left_items.len(), right_items.len());
-        for i in 0..shared_item_count {
-             match get_left_ordering(&left_items[i], &right_items[i]) {
-                Ordering::Less => return Ordering::Less,
-                Ordering::Greater => return Ordering::Greater,
-                Ordering::Equal => ()
+    match (left, right) {
+        (ListItem::Value(left_value), ListItem::Value(right_value)) => left_value.cmp(right_value),
+        (ListItem::List(left_items), ListItem::List(right_items)) => {
+            let shared_item_count = std::cmp::min(left_items.len(), right_items.len());
+            for i in 0..shared_item_count {
+                match get_left_ordering(&left_items[i], &right_items[i]) {
+                    Ordering::Less => return Ordering::Less,
+                    Ordering::Greater => return Ordering::Greater,
+                    Ordering::Equal => ()
+                }
             }
+            left_items.len().cmp(&right_items.len())
         }
-        return left_items.len().cmp(&right_items.len());
-    } else {
-        if let ListItem::Value(left_value) = left {
-            let left_list = value_as_list(left_value);
-            return get_left_ordering(&left_list, right);
-        } else if let ListItem::Value(right_value) = right {
-            let right_list = value_as_list(right_value);
-            return get_left_ordering(left, &right_list);
-        }
+        (ListItem::Value(left_value), ListItem::List(_)) => get_left_ordering(&value_as_list(left_value), right),
+        (ListItem::List(_), ListItem::Value(right_value)) => get_left_ordering(left, &value_as_list(right_value))
     }
-
-    panic!();
 }
 
 fn part1(input: &Vec<ListPair>) -> usize {
@@ -103,7 +102,7 @@ fn part1(input: &Vec<ListPair>) -> usize {
     }).sum::<usize>()
 }
 
-fn part2(input: &Vec<ListPair>) -> usize {
+fn part2(input: &Vec<ListPair>) -> AocResult<usize> {
     let divider_packets = vec![
         ListItem::List(vec![ListItem::List(vec![ListItem::Value(2)])]),
         ListItem::List(vec![ListItem::List(vec![ListItem::Value(6)])])
@@ -122,22 +121,32 @@ fn part2(input: &Vec<ListPair>) -> usize {
                 if let ListItem::Value(value) = child_list.first().unwrap() {
                     match value {
                         2 => first_divider_index = i + 1,
-                        6 => return first_divider_index * (i + 1),
+                        6 => return Ok(first_divider_index * (i + 1)),
                         _ => ()
                     }
                 }
             }
         }
     }
-    panic!("Could not find divider packets");
+    Err(AocError::parse("could not find the divider packets after sorting"))
 }
 
-pub fn run() {
-    println!("Day 13");
-    let input = read_input(13);
-    let pairs = parse_pairs(input.as_str());
-    println!("Part 1: {}", part1(&pairs));
-    println!("Part 2: {}", part2(&pairs));
+pub struct Day13;
+
+impl Solution for Day13 {
+    type Input<'a> = Vec<ListPair>;
+
+    fn parse(input: &str) -> AocResult<Vec<ListPair>> {
+        parse_pairs(input)
+    }
+
+    fn part1(input: &Vec<ListPair>) -> AocResult<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Vec<ListPair>) -> AocResult<Answer> {
+        part2(input).map(Answer::from)
+    }
 }
 
 #[cfg(test)]
@@ -170,13 +179,13 @@ pub mod tests {
 
     #[test]
     pub fn part1() {
-        let pairs = parse_pairs(INPUT);
+        let pairs = parse_pairs(INPUT).unwrap();
         assert_eq!(13, super::part1(&pairs));
     }
 
     #[test]
     pub fn part2() {
-        let pairs = parse_pairs(INPUT);
-        assert_eq!(140, super::part2(&pairs));
+        let pairs = parse_pairs(INPUT).unwrap();
+        assert_eq!(Ok(140), super::part2(&pairs));
     }
 }
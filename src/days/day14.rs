@@ -1,16 +1,21 @@
 use std::ops::RangeInclusive;
-use itertools::{all, Itertools};
+use std::time::Duration;
+use itertools::all;
 use num_traits::abs;
 use crate::common;
-use crate::common::movement::{Grid, GridDirection, Point};
-use crate::common::read_input;
-
-enum GridElement {
+use crate::common::movement::{Bounds, DenseGrid, GridDirection, GridStorage, Orientation, Point};
+use crate::common::parse_number_in;
+use crate::common::render::{Animation, Cell, Frame, Rgb};
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Solution};
+
+#[derive(Clone)]
+pub enum GridElement {
     Sand,
     RockWall,
 }
 
-type SandGrid = Grid<GridElement>;
+pub type SandGrid = DenseGrid<GridElement>;
 
 const SAND_FILL_SOURCE: Point = Point { x: 500, y: 0 };
 
@@ -71,16 +76,25 @@ fn straight_line_between(a: &Point, b: &Point) -> Vec<Point> {
     }
 }
 
-fn parse_input(input: &str) -> SandGrid {
-    let mut grid = Grid::new();
+fn parse_input(input: &str) -> AocResult<SandGrid> {
+    let mut grid = SandGrid::new();
 
     for line in input.split("\n") {
         let points: Vec<Point> = line.split(" -> ").map(|point_raw| {
-            let (x, y) = point_raw.split_once(',').unwrap();
-            Point { x: x.parse::<i128>().unwrap(), y: -(y.parse::<i128>().unwrap()) }
-        }).collect();
+            let (x, y) = point_raw.split_once(',')
+                .ok_or_else(|| AocError::parse_in(input, point_raw, "expected a point like `x,y`"))?;
+            Ok(Point { x: parse_number_in(input, x)?, y: -parse_number_in::<i128>(input, y)? })
+        }).collect::<AocResult<Vec<Point>>>()?;
+
+        if points.len() < 2 {
+            return Err(AocError::parse_in(input, line, "expected at least two points"));
+        }
 
-        assert!(points.len() > 1);
+        for pair in points.windows(2) {
+            if pair[0].x != pair[1].x && pair[0].y != pair[1].y {
+                return Err(AocError::parse_in(input, line, "rock paths must be horizontal or vertical"));
+            }
+        }
 
         let mut last_point = points.first().unwrap();
         for next_point in &points[1..] {
@@ -91,24 +105,19 @@ fn parse_input(input: &str) -> SandGrid {
         }
     }
 
-    grid
+    Ok(grid)
 }
 
-fn render_grid(grid: &SandGrid) -> String {
-    grid.get_y_bounds().to_range().rev().map(|y| grid.get_x_bounds().to_range().map(|x| {
-        let point = Point { x, y };
-        match grid.get_value(&point) {
-            Some(element) => match(element) {
-                GridElement::RockWall => "#",
-                GridElement::Sand => "o"
-            },
-            None => "."
-        }
-    }).join("")).join("\n")
+fn render_grid(grid: &SandGrid, x_bounds: Bounds, y_bounds: Bounds) -> Frame {
+    Frame::from_bounds(x_bounds, y_bounds, Orientation::YUp, |point| match grid.get_value(point) {
+        Some(GridElement::RockWall) => Cell::new('#', Rgb::GREY),
+        Some(GridElement::Sand) => Cell::new('o', Rgb::YELLOW),
+        None => Cell::empty()
+    })
 }
 
-fn part1(input: &str) -> u128 {
-    let mut grid = parse_input(input);
+fn part1(starting_grid: &SandGrid) -> u128 {
+    let mut grid = starting_grid.clone();
     let mut sand_units_placed = 0;
     loop {
         if let Some(point) = get_sand_resting_point(&grid, None /*floor_y_opt*/) {
@@ -120,8 +129,8 @@ fn part1(input: &str) -> u128 {
     }
 }
 
-fn part2(input: &str) -> u128 {
-    let mut grid = parse_input(input);
+fn part2(starting_grid: &SandGrid) -> u128 {
+    let mut grid = starting_grid.clone();
     let mut sand_units_placed = 0;
     let floor_y = grid.get_y_bounds().min - 2;
     loop {
@@ -137,11 +146,49 @@ fn part2(input: &str) -> u128 {
     }
 }
 
-pub fn run() {
-    println!("Day 14");
-    let input = read_input(14);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+const GRAINS_PER_FRAME: usize = 25;
+
+// Sand piling up in the cave until it starts falling into the abyss, a few grains at a time
+fn render(starting_grid: &SandGrid) -> Animation {
+    // Sand can pile up above the rocks, so the frames reach up to the source of the sand
+    let x_bounds = starting_grid.get_x_bounds();
+    let y_bounds = Bounds::new(starting_grid.get_y_bounds().min, SAND_FILL_SOURCE.y);
+
+    let mut animation = Animation::new(Duration::from_millis(50));
+    let mut grid = starting_grid.clone();
+    animation.push(render_grid(&grid, x_bounds, y_bounds));
+    let mut sand_units_placed = 0;
+    while let Some(point) = get_sand_resting_point(&grid, None) {
+        grid.visit(point, GridElement::Sand);
+        sand_units_placed += 1;
+        if sand_units_placed % GRAINS_PER_FRAME == 0 {
+            animation.push(render_grid(&grid, x_bounds, y_bounds));
+        }
+    }
+    animation.push(render_grid(&grid, x_bounds, y_bounds));
+    animation
+}
+
+pub struct Day14;
+
+impl Solution for Day14 {
+    type Input<'a> = SandGrid;
+
+    fn parse(input: &str) -> AocResult<SandGrid> {
+        parse_input(input)
+    }
+
+    fn part1(input: &SandGrid) -> AocResult<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &SandGrid) -> AocResult<Answer> {
+        Ok(part2(input).into())
+    }
+
+    fn render(input: &SandGrid) -> AocResult<Option<Animation>> {
+        Ok(Some(render(input)))
+    }
 }
 
 #[cfg(test)]
@@ -151,11 +198,11 @@ pub mod tests {
 
     #[test]
     pub fn part1() {
-        assert_eq!(24, super::part1(INPUT));
+        assert_eq!(24, super::part1(&super::parse_input(INPUT).unwrap()));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(93, super::part2(INPUT));
+        assert_eq!(93, super::part2(&super::parse_input(INPUT).unwrap()));
     }
 }
\ No newline at end of file
@@ -1,43 +1,35 @@
 use std::collections::{HashMap, HashSet};
-use itertools::max;
-use regex::Regex;
-use crate::common::movement::{Bounds, Point};
-use crate::common::read_input;
+use crate::common::interval::{Interval, IntervalSet};
+use crate::common::movement::Point;
+use crate::common::parse;
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Solution};
 
-fn parse_input(input: &str) -> HashMap<Point, Point> {
-    let point_regex = Regex::new(r"x=(-?\d+), y=(-?\d+)").unwrap();
-
-    input.split("\n")
+fn parse_input(input: &str) -> AocResult<HashMap<Point, Point>> {
+    parse::lines(input)
         .map(|line| {
-            let points: Vec<Point> = point_regex.captures_iter(line).map(|capture| Point { x: capture.get(1).unwrap().as_str().parse().unwrap(), y: capture.get(2).unwrap().as_str().parse().unwrap() }).collect();
-            (points[0], points[1])
+            let coordinates: Vec<i128> = parse::integers(input, line)?;
+            if coordinates.len() != 4 {
+                return Err(AocError::parse_in(input, line, "expected a sensor and a beacon position like `x=1, y=2`"));
+            }
+            Ok((Point { x: coordinates[0], y: coordinates[1] }, Point { x: coordinates[2], y: coordinates[3] }))
         })
         .collect()
 }
 
-fn part1(input: &str, y: i128) -> u128 {
-    let beacons_by_sensor = parse_input(input);
-    let sensor_radii: HashMap<Point, u128> = beacons_by_sensor.iter().map(|(sensor, beacon)| (sensor.clone(), sensor.manhattan_dist(beacon))).collect();
-    let beacons: HashSet<Point> = beacons_by_sensor.values().map(|point| point.clone()).collect();
-    let max_radius_for_line = *sensor_radii.iter().filter_map(|(sensor, radius)| {
-        if (y - sensor.y).abs() > *radius as i128 {
-            None
-        } else {
-            Some(radius)
-        }
-    }).max().unwrap() as i128;
-    let mut x_bounds: Bounds<i128> = Bounds::new(0, 0);
-    beacons_by_sensor.keys().for_each(|sensor| x_bounds.update(sensor.x));
-    let mut positions_without_beacon = 0;
-    println!("Checking beacon positions between {} and {}", x_bounds.min - max_radius_for_line, x_bounds.min + max_radius_for_line);
-    for x in x_bounds.min - max_radius_for_line..=x_bounds.max + max_radius_for_line {
-        let point = Point { x, y };
-        let can_beacon_exist = sensor_radii.iter().all(|(beacon, &radius)| point.manhattan_dist(beacon) > radius);
-        if !can_beacon_exist && !beacons.contains(&point) {
-            positions_without_beacon += 1;
-        }
-    }
-    positions_without_beacon
+fn part1(beacons_by_sensor: &HashMap<Point, Point>, y: i128) -> AocResult<u128> {
+    // Each sensor rules out a run of the row that narrows the further the row is from it
+    let covered: IntervalSet = beacons_by_sensor.iter().filter_map(|(sensor, beacon)| {
+        let reach = sensor.manhattan_dist(beacon) as i128 - (y - sensor.y).abs();
+        // A sensor too far from the row has a negative reach, which gives no interval
+        Interval::new(sensor.x - reach, sensor.x + reach)
+    }).collect();
+    let beacons_in_row: HashSet<i128> = beacons_by_sensor.values()
+        .filter(|beacon| beacon.y == y && covered.contains_value(beacon.x))
+        .map(|beacon| beacon.x)
+        .collect();
+    let covered_count = covered.len().ok_or_else(|| AocError::arithmetic(format!("too many positions in row {} to count", y)))?;
+    Ok(covered_count - beacons_in_row.len() as u128)
 }
 
 fn points_n_away(source_point: &Point, n: u128) -> Vec<Point> {
@@ -47,13 +39,12 @@ fn points_n_away(source_point: &Point, n: u128) -> Vec<Point> {
         let y = (n - i) as i128;
         n_away_square.extend(vec![Point { x, y }, Point { x: -x, y }, Point { x: -x, y: -y }, Point { x, y: -y }]);
     }
-    n_away_square.iter().map(|point| source_point.add(point)).collect()
+    n_away_square.iter().map(|point| *source_point + *point).collect()
 }
 
-fn part2(input: &str, max_coordinate: u128) -> u128 {
+fn part2(beacons_by_sensor: &HashMap<Point, Point>, max_coordinate: u128) -> AocResult<u128> {
     let x_range = 0..=max_coordinate as i128;
     let y_range = 0..=max_coordinate as i128;
-    let beacons_by_sensor = parse_input(input);
     let sensor_radii: HashMap<Point, u128> = beacons_by_sensor.iter().map(|(sensor, beacon)| (sensor.clone(), sensor.manhattan_dist(beacon))).collect();
     for (sensor, radius) in sensor_radii.iter() {
         for point in points_n_away(sensor, radius + 1) {
@@ -62,22 +53,38 @@ fn part2(input: &str, max_coordinate: u128) -> u128 {
             }
 
             if sensor_radii.iter().all(|(other_sensor, other_radius)| point.manhattan_dist(other_sensor) > *other_radius) {
-                return (point.x as u128 * 4_000_000) + point.y as u128;
+                return Ok((point.x as u128 * 4_000_000) + point.y as u128);
             }
         }
     }
-    panic!("Did not find a point that satisfies all conditions.");
+    Err(AocError::parse(format!("expected a position from 0 to {} that no sensor reaches", max_coordinate)))
 }
 
-pub fn run() {
-    println!("Day 15");
-    let input = read_input(15);
-    println!("Part 1: {}", part1(input.as_str(), 2_000_000));
-    println!("Part 2: {}", part2(input.as_str(), 4_000_000));
+const PART_1_ROW: i128 = 2_000_000;
+const PART_2_MAX_COORDINATE: u128 = 4_000_000;
+
+pub struct Day15;
+
+impl Solution for Day15 {
+    type Input<'a> = HashMap<Point, Point>;
+
+    fn parse(input: &str) -> AocResult<HashMap<Point, Point>> {
+        parse_input(input)
+    }
+
+    fn part1(input: &HashMap<Point, Point>) -> AocResult<Answer> {
+        part1(input, PART_1_ROW).map(Answer::from)
+    }
+
+    fn part2(input: &HashMap<Point, Point>) -> AocResult<Answer> {
+        part2(input, PART_2_MAX_COORDINATE).map(Answer::from)
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use crate::error::AocError;
+
     const INPUT: &str = "Sensor at x=2, y=18: closest beacon is at x=-2, y=15
 Sensor at x=9, y=16: closest beacon is at x=10, y=16
 Sensor at x=13, y=2: closest beacon is at x=15, y=3
@@ -95,11 +102,13 @@ Sensor at x=20, y=1: closest beacon is at x=15, y=3";
 
     #[test]
     pub fn part1() {
-        assert_eq!(26, super::part1(INPUT, 10));
+        assert_eq!(Ok(26), super::part1(&super::parse_input(INPUT).unwrap(), 10));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(56000011, super::part2(INPUT, 20));
+        assert_eq!(Ok(56000011), super::part2(&super::parse_input(INPUT).unwrap(), 20));
+        let everywhere = super::parse_input("Sensor at x=0, y=0: closest beacon is at x=10, y=0").unwrap();
+        assert_eq!(Err(AocError::parse("expected a position from 0 to 5 that no sensor reaches")), super::part2(&everywhere, 5));
     }
 }
@@ -1,60 +1,46 @@
-use std::cmp::{max, Reverse};
-use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
-use itertools::{all, Itertools};
-use priority_queue::PriorityQueue;
+use std::cmp::max;
+use std::collections::{HashMap, HashSet};
 use regex::Regex;
-use crate::common::movement::{Bounds, Point};
-use crate::common::read_input;
+use crate::common::graph::all_pairs_steps;
+use crate::common::parse_number_in;
+use crate::error::{AocError, AocResult};
+use crate::cancel::CancellationToken;
+use crate::solution::{Answer, PartContext, Solution};
 
-struct ValveData<'a> {
+pub struct ValveData<'a> {
     name: &'a str,
     flow_rate: u128,
     tunnels: Vec<&'a str>
 }
 
-fn parse_input(input: &str) -> Vec<ValveData> {
-    let valve_regex = Regex::new(r"Valve (?P<name>[A-Z]{2}) has flow rate=(?P<rate>\d+); .+? (?P<tunnels>(?:[A-Z]{2}(?:, )?)+)").unwrap();
+fn parse_input(input: &str) -> AocResult<Vec<ValveData<'_>>> {
+    let valve_regex = Regex::new(r"^Valve (?P<name>[A-Z]{2}) has flow rate=(?P<rate>\d+); tunnels? leads? to valves? (?P<tunnels>[A-Z]{2}(?:, [A-Z]{2})*)$").unwrap();
 
-    valve_regex.captures_iter(input)
-        .map(|capture| {
+    let valves = input.split("\n")
+        .enumerate()
+        .map(|(i, line)| {
+            let capture = valve_regex.captures(line)
+                .ok_or_else(|| AocError::parse("expected `Valve <name> has flow rate=<rate>; tunnels lead to valves <names>`").at_line(i + 1))?;
             let name = capture.name("name").unwrap().as_str();
-            let flow_rate: u128 = capture.name("rate").unwrap().as_str().parse().unwrap();
+            let flow_rate: u128 = parse_number_in(input, capture.name("rate").unwrap().as_str())?;
             let tunnels: Vec<&str> = capture.name("tunnels").unwrap().as_str().split(", ").collect();
 
-            ValveData {
+            Ok(ValveData {
                 name,
                 flow_rate,
                 tunnels
-            }
+            })
         })
-        .collect()
-}
-
-fn dijkstra_step_count<'a>(valves: &'a HashMap<&'a str, &'a ValveData<'a>>, start_valve_name: &'a str) -> HashMap<&'a str, u128> {
-    let mut distances: HashMap<&str, u128> = HashMap::from_iter(valves.keys().into_iter().map(|&valve_name| (valve_name, u128::MAX)));
-    distances.insert(start_valve_name, 0);
-
-    let mut queue: PriorityQueue<&str, Reverse<u128>> = PriorityQueue::new();
-    for (&valve_name, &valve_data) in valves {
-        queue.push(valve_name, Reverse(*distances.get(valve_name).unwrap()));
-    }
-
-    while !queue.is_empty() {
-        let (current_name, Reverse(distance)) = queue.pop().unwrap();
-        let current_data = *valves.get(current_name).unwrap();
-        let current_neighbor_distance = distance + 1;
-
-        for &neighbor_name in &current_data.tunnels {
-            let existing_neighbor_distance = *distances.get(neighbor_name).unwrap();
+        .collect::<AocResult<Vec<ValveData>>>()?;
 
-            if current_neighbor_distance < existing_neighbor_distance {
-                distances.insert(neighbor_name, current_neighbor_distance);
-                queue.change_priority(neighbor_name, Reverse(current_neighbor_distance));
-            }
+    let names: HashSet<&str> = valves.iter().map(|valve| valve.name).collect();
+    for valve in &valves {
+        if let Some(unknown) = valve.tunnels.iter().find(|tunnel| !names.contains(*tunnel)) {
+            return Err(AocError::parse_in(input, unknown, format!("valve {} does not exist", unknown)));
         }
     }
 
-    distances
+    Ok(valves)
 }
 
 struct PressureSearchData<'a> {
@@ -63,10 +49,15 @@ struct PressureSearchData<'a> {
     open_valves: HashSet<&'a str>,
     minutes_remaining: u128,
     current_valve_name: &'a str,
+    cancel: &'a CancellationToken,
 }
 
 fn open_next_valve(data: PressureSearchData) -> u128 {
-    let PressureSearchData { all_valve_distances, valves, open_valves, minutes_remaining, current_valve_name } = data;
+    let PressureSearchData { all_valve_distances, valves, open_valves, minutes_remaining, current_valve_name, cancel } = data;
+
+    if cancel.is_cancelled() {
+        return 0;
+    }
 
     let current_pressure_per_minute: u128 = valves.iter().filter_map(|(&valve_name, &valve)| {
         if open_valves.contains(valve_name) {
@@ -92,7 +83,10 @@ fn open_next_valve(data: PressureSearchData) -> u128 {
 
     let mut max_flow_found = 0;
     for next_valve_to_open in unopened_valves {
-        let minutes_spent_opening_valve = valve_distances.get(next_valve_to_open.name).unwrap() + 1;
+        let minutes_spent_opening_valve = match valve_distances.get(next_valve_to_open.name) {
+            Some(distance) => distance + 1,
+            None => continue
+        };
         if minutes_spent_opening_valve > minutes_remaining {
             continue;
         }
@@ -105,44 +99,60 @@ fn open_next_valve(data: PressureSearchData) -> u128 {
             valves,
             open_valves: open_valves_with_next,
             minutes_remaining: minutes_remaining - minutes_spent_opening_valve,
-            current_valve_name: next_valve_to_open.name
+            current_valve_name: next_valve_to_open.name,
+            cancel
         }));
     }
 
     max_flow_found
 }
 
-fn part1(input: &str) -> u128 {
-    let valves = parse_input(input);
+fn part1(valves: &Vec<ValveData>, cancel: &CancellationToken) -> u128 {
     let valves_by_name = HashMap::from_iter(valves.iter().map(|valve| (valve.name, valve)));
 
-    let mut all_valve_distances: HashMap<&str, HashMap<&str, u128>> = HashMap::new();
-    for valve in &valves {
-        all_valve_distances.insert(valve.name, dijkstra_step_count(&valves_by_name, valve.name));
-    }
+    let all_valve_distances = all_pairs_steps(valves.iter().map(|valve| valve.name), |name| valves_by_name[name].tunnels.clone());
 
     open_next_valve(PressureSearchData {
         all_valve_distances: &all_valve_distances,
         open_valves: HashSet::new(),
         valves: &valves_by_name,
         current_valve_name: "AA",
-        minutes_remaining: 30
+        minutes_remaining: 30,
+        cancel
     })
 }
 
-fn part2(input: &str) -> u128 {
+fn part2(_valves: &Vec<ValveData>) -> u128 {
     0
 }
 
-pub fn run() {
-    println!("Day 16");
-    let input = read_input(16);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+pub struct Day16;
+
+impl Solution for Day16 {
+    type Input<'a> = Vec<ValveData<'a>>;
+
+    fn parse(input: &str) -> AocResult<Vec<ValveData<'_>>> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Vec<ValveData>) -> AocResult<Answer> {
+        Ok(part1(input, &CancellationToken::never()).into())
+    }
+
+    fn part1_with(input: &Vec<ValveData>, context: &PartContext) -> AocResult<Answer> {
+        Ok(part1(input, &context.cancel).into())
+    }
+
+    fn part2(input: &Vec<ValveData>) -> AocResult<Answer> {
+        Ok(part2(input).into())
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use crate::cancel::CancellationToken;
+    use crate::error::AocError;
+
     const INPUT: &str = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
 Valve BB has flow rate=13; tunnels lead to valves CC, AA
 Valve CC has flow rate=2; tunnels lead to valves DD, BB
@@ -156,11 +166,17 @@ Valve JJ has flow rate=21; tunnel leads to valve II";
 
     #[test]
     pub fn part1() {
-        assert_eq!(1651, super::part1(INPUT));
+        assert_eq!(1651, super::part1(&super::parse_input(INPUT).unwrap(), &CancellationToken::never()));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(56000011, super::part2(INPUT));
+        assert_eq!(56000011, super::part2(&super::parse_input(INPUT).unwrap()));
+    }
+
+    #[test]
+    pub fn invalid_valves() {
+        let input = "Valve AA has flow rate=0; tunnels lead to valves BB\nValve BB has flow rate=3; tunnels lead to valves CC";
+        assert_eq!(Some(AocError::parse_at(2, 50, "valve CC does not exist")), super::parse_input(input).err());
     }
 }
@@ -1,10 +1,13 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::VecDeque;
 use std::ops::Range;
+use std::time::Duration;
 use std::cmp::max;
 use itertools::Itertools;
-use regex::Regex;
-use crate::common::movement::{Bounds, Grid, GridDirection, Point};
-use crate::common::read_input;
+use crate::common::cycle::extrapolate;
+use crate::common::movement::{Bounds, DenseGrid, GridDirection, GridStorage, Orientation, Point};
+use crate::common::render::{Animation, Cell, Frame, Rgb};
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Solution};
 
 const CHAMBER_WIDTH: usize = 7;
 const CHAMBER_RANGE_X: Range<usize> = 0..CHAMBER_WIDTH;
@@ -30,7 +33,7 @@ const PATTERNS: &str = "####
 ##";
 
 struct RockChamber {
-    rocks: HashSet<Point>,
+    rocks: DenseGrid<()>,
     floor_y: u128,
     jet_pattern: Vec<GridDirection>,
     jet_pattern_index: usize,
@@ -39,7 +42,7 @@ struct RockChamber {
 impl RockChamber {
     pub fn new(jet_pattern: Vec<GridDirection>) -> RockChamber {
         RockChamber {
-            rocks: HashSet::new(),
+            rocks: DenseGrid::new(),
             floor_y: 0,
             jet_pattern_index: 0,
             jet_pattern,
@@ -47,11 +50,11 @@ impl RockChamber {
     }
 }
 
-fn parse_input(input: &str) -> Vec<GridDirection> {
-    input.chars().map(|c| match c {
-        '>' => GridDirection::Right,
-        '<' => GridDirection::Left,
-        _ => panic!()
+fn parse_input(input: &str) -> AocResult<Vec<GridDirection>> {
+    input.chars().enumerate().map(|(i, c)| match c {
+        '>' => Ok(GridDirection::Right),
+        '<' => Ok(GridDirection::Left),
+        _ => Err(AocError::parse_at(1, i + 1, format!("expected `<` or `>`, got `{}`", c)))
     }).collect()
 }
 
@@ -77,32 +80,37 @@ fn parse_patterns() -> VecDeque<Vec<Point>> {
 
 fn is_legal_position(chamber: &RockChamber, rock_positions: &Vec<Point>) -> bool {
     rock_positions.iter().all(|point| {
-        !chamber.rocks.contains(point) && CHAMBER_RANGE_X.contains(&(point.x as usize)) && point.y >= 0
+        !chamber.rocks.has_visited(point) && CHAMBER_RANGE_X.contains(&(point.x as usize)) && point.y >= 0
     })
 }
 
-fn render_possible_grid(chamber: &RockChamber, positions: &Vec<Point>) {
-    let max_y = max(chamber.floor_y, positions.iter().map(|point| point.y as u128).max().unwrap());
-    let position_set: HashSet<&Point> = HashSet::from_iter(positions.iter());
-    for y in (0..=max_y).rev() {
-        println!("|{}|", (0..CHAMBER_WIDTH).map(|x| {
-            let point = Point { x: x as i128, y: y as i128 };
-            if position_set.contains(&point) {
-                '@'
-            } else if chamber.rocks.contains(&point) {
-                '#'
-            } else {
-                '.'
-            }
-        }).join(""));
-    }
-    println!("+-------+");
-    println!();
+// The chamber with its walls and floor up to `top_y`, and the rock that is still falling if there is one
+fn render_chamber(chamber: &RockChamber, falling_positions: &[Point], top_y: i128) -> Frame {
+    let right_wall_x = CHAMBER_WIDTH as i128;
+    Frame::from_bounds(Bounds::new(-1, right_wall_x), Bounds::new(-1, top_y), Orientation::YUp, |point| {
+        let is_wall = point.x == -1 || point.x == right_wall_x;
+        if point.y == -1 {
+            Cell::new(if is_wall { '+' } else { '-' }, Rgb::GREY)
+        } else if is_wall {
+            Cell::new('|', Rgb::GREY)
+        } else if falling_positions.contains(point) {
+            Cell::new('@', Rgb::RED)
+        } else if chamber.rocks.has_visited(point) {
+            Cell::new('#', Rgb::WHITE)
+        } else {
+            Cell::empty()
+        }
+    })
 }
 
-fn place_rock(chamber: &mut RockChamber, pattern: &Vec<Point>) {
+// Where a rock appears before it starts falling
+fn spawn_positions(chamber: &RockChamber, pattern: &[Point]) -> Vec<Point> {
     let current_rock_offset = Point { x: DEFAULT_ROCK_OFFSET.x, y: DEFAULT_ROCK_OFFSET.y + chamber.floor_y as i128 };
-    let mut current_rock_positions: Vec<Point> = pattern.iter().map(|point| point.add(&current_rock_offset)).collect();
+    pattern.iter().map(|point| *point + current_rock_offset).collect()
+}
+
+fn place_rock(chamber: &mut RockChamber, pattern: &Vec<Point>) {
+    let mut current_rock_positions = spawn_positions(chamber, pattern);
 
     loop {
         let next_jet_push = chamber.jet_pattern[chamber.jet_pattern_index];
@@ -118,7 +126,7 @@ fn place_rock(chamber: &mut RockChamber, pattern: &Vec<Point>) {
         if !is_legal_position(chamber, &moved_down_positions) {
             for point in current_rock_positions {
                 chamber.floor_y = max(chamber.floor_y, point.y as u128 + 1);
-                chamber.rocks.insert(point);
+                chamber.rocks.visit(point, ());
             }
             return;
         }
@@ -127,18 +135,9 @@ fn place_rock(chamber: &mut RockChamber, pattern: &Vec<Point>) {
     }
 }
 
-fn render_grid(chamber: &RockChamber) {
-    println!();
-    for y in (0..=chamber.floor_y).rev() {
-        println!("|{}|", (0..CHAMBER_WIDTH).map(|x| if chamber.rocks.contains(&Point { x: x as i128, y: y as i128 }) { '#' } else { '.' }).join(""));
-    }
-    println!("+-------+");
-}
-
-fn part1(input: &str) -> u128 {
+fn part1(jet_patterns: &Vec<GridDirection>) -> u128 {
     let mut patterns = parse_patterns();
-    let jet_patterns = parse_input(input);
-    let mut chamber = RockChamber::new(jet_patterns);
+    let mut chamber = RockChamber::new(jet_patterns.clone());
     for _ in 0..2022 {
         let pattern = patterns.pop_front().unwrap();
         place_rock(&mut chamber, &pattern);
@@ -147,80 +146,78 @@ fn part1(input: &str) -> u128 {
     chamber.floor_y
 }
 
-#[derive(Debug)]
-struct LoopData {
-    start_iteration_index: u128,
-    height_y: u128,
-    iteration_count: u128,
-    height_offset: u128,
-}
-
-struct ChamberConfiguration {
-    iteration: u128,
-    floor_y: u128
-}
-
 const ROW_CHECK_HEIGHT: u128 = 50;
 
-fn serialize_chamber(chamber: &mut RockChamber, pattern_index: usize) -> String {
+fn serialize_chamber(chamber: &RockChamber, pattern_index: usize) -> String {
     format!("jet@{};patt@{};rows@{}", chamber.jet_pattern_index, pattern_index,
-            (max(0, chamber.floor_y - ROW_CHECK_HEIGHT)..=chamber.floor_y)
-                .map(|y| (0..CHAMBER_WIDTH).map(|x| if chamber.rocks.contains(&Point { x: x as i128, y: y as i128 }) { '#' } else { '.' }).join(""))
+            (chamber.floor_y - ROW_CHECK_HEIGHT..=chamber.floor_y)
+                .map(|y| (0..CHAMBER_WIDTH).map(|x| if chamber.rocks.has_visited(&Point { x: x as i128, y: y as i128 }) { '#' } else { '.' }).join(""))
                 .join("")
     )
 }
 
-fn find_loop(patterns: &VecDeque<Vec<Point>>, chamber: &mut RockChamber) -> LoopData {
-    let mut configurations: HashMap<String, ChamberConfiguration> = HashMap::new();
-    let mut loop_index: u128 = 0;
-    let mut pattern_index = 0;
-    loop {
-        if loop_index >= ROW_CHECK_HEIGHT {
-            let configuration = serialize_chamber(chamber, pattern_index);
-            if let Some(existing_configuration) = configurations.get(&configuration) {
-                return LoopData {
-                    start_iteration_index: existing_configuration.iteration,
-                    height_y: chamber.floor_y - existing_configuration.floor_y,
-                    iteration_count: loop_index - existing_configuration.iteration,
-                    height_offset: existing_configuration.floor_y
-                }
-            }
+const TARGET_LOOP: u128 = 1_000_000_000_000;
 
-            configurations.insert(configuration, ChamberConfiguration {
-                iteration: loop_index,
-                floor_y: chamber.floor_y
-            });
-        }
+const RENDERED_ROCKS: usize = 30;
+
+// The first few rocks, each one shown where it appears and then added to the tower
+fn render(jet_patterns: &[GridDirection]) -> Animation {
+    let patterns = parse_patterns();
+    // Every frame needs the same height, so find where the highest rock appears before drawing any
+    let mut chamber = RockChamber::new(jet_patterns.to_vec());
+    let spawns: Vec<Vec<Point>> = patterns.iter().cycle().take(RENDERED_ROCKS).map(|pattern| {
+        let spawned = spawn_positions(&chamber, pattern);
+        place_rock(&mut chamber, pattern);
+        spawned
+    }).collect();
+    let top_y = spawns.iter().flatten().map(|point| point.y).max().unwrap_or(0);
+
+    let mut animation = Animation::new(Duration::from_millis(200));
+    let mut chamber = RockChamber::new(jet_patterns.to_vec());
+    for (pattern, spawned) in patterns.iter().cycle().zip(&spawns) {
+        animation.push(render_chamber(&chamber, spawned, top_y));
+        place_rock(&mut chamber, pattern);
+    }
+    animation.push(render_chamber(&chamber, &[], top_y));
+    animation
+}
+
+fn part2(jet_patterns: &Vec<GridDirection>) -> i128 {
+    let patterns = parse_patterns();
+    let mut state = (RockChamber::new(jet_patterns.clone()), 0);
+    extrapolate(
+        &mut state,
+        TARGET_LOOP,
+        |(chamber, pattern_index): &mut (RockChamber, usize)| {
+            place_rock(chamber, &patterns[*pattern_index]);
+            *pattern_index = (*pattern_index + 1) % patterns.len();
+        },
+        // The top rows only say something about what falls next once there are enough of them
+        |(chamber, pattern_index)| (chamber.floor_y >= ROW_CHECK_HEIGHT).then(|| serialize_chamber(chamber, *pattern_index)),
+        |(chamber, _)| chamber.floor_y as i128
+    )
+}
 
-        place_rock(chamber, &patterns[pattern_index]);
-        pattern_index = (pattern_index + 1) % patterns.len();
+pub struct Day17;
 
-        loop_index += 1;
+impl Solution for Day17 {
+    type Input<'a> = Vec<GridDirection>;
+
+    fn parse(input: &str) -> AocResult<Vec<GridDirection>> {
+        parse_input(input)
     }
-}
 
-const TARGET_LOOP: u128 = 1_000_000_000_000;
+    fn part1(input: &Vec<GridDirection>) -> AocResult<Answer> {
+        Ok(part1(input).into())
+    }
 
-fn part2(input: &str) -> u128 {
-    let mut patterns = parse_patterns();
-    let jet_patterns = parse_input(input);
-    let mut chamber = RockChamber::new(jet_patterns);
-    let loop_data = find_loop(&patterns, &mut chamber);
-    let y_before_extra = chamber.floor_y;
-    for _ in 0..((TARGET_LOOP - loop_data.start_iteration_index) % loop_data.iteration_count) {
-        let pattern = patterns.pop_front().unwrap();
-        place_rock(&mut chamber, &pattern);
-        patterns.push_back(pattern);
+    fn part2(input: &Vec<GridDirection>) -> AocResult<Answer> {
+        Ok(part2(input).into())
     }
-    let missed_loops = (TARGET_LOOP - loop_data.start_iteration_index) / loop_data.iteration_count;
-    (chamber.floor_y - y_before_extra) + loop_data.height_offset + (loop_data.height_y * missed_loops)
-}
 
-pub fn run() {
-    println!("Day 17");
-    let input = read_input(17);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+    fn render(input: &Vec<GridDirection>) -> AocResult<Option<Animation>> {
+        Ok(Some(render(input)))
+    }
 }
 
 #[cfg(test)]
@@ -229,11 +226,11 @@ pub mod tests {
 
     #[test]
     pub fn part1() {
-        assert_eq!(3068, super::part1(INPUT));
+        assert_eq!(3068, super::part1(&super::parse_input(INPUT).unwrap()));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(1514285714288, super::part2(INPUT));
+        assert_eq!(1514285714288, super::part2(&super::parse_input(INPUT).unwrap()));
     }
 }
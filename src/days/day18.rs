@@ -1,94 +1,34 @@
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::hash::Hash;
 use std::ops::RangeInclusive;
 use itertools::{max, min};
 use regex::Regex;
-use crate::common::movement::Bounds;
-use crate::common::read_input;
-
-#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
-enum Axis3D {
-    X,
-    Y,
-    Z,
-}
-
-impl Axis3D {
-    pub fn all() -> Vec<Axis3D> {
-        vec![Axis3D::X, Axis3D::Y, Axis3D::Z]
-    }
-}
-
-#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
-struct Point3D {
-    x: i128,
-    y: i128,
-    z: i128,
-}
-
-impl Point3D {
-    pub fn zero() -> Point3D {
-        Point3D {
-            x: 0,
-            y: 0,
-            z: 0,
-        }
-    }
-
-    pub fn get_axis_value(&self, axis: Axis3D) -> &i128 {
-        match axis {
-            Axis3D::X => &self.x,
-            Axis3D::Y => &self.y,
-            Axis3D::Z => &self.z
-        }
-    }
-
-    pub fn set_axis_value(&mut self, axis: Axis3D, value: i128) {
-        match axis {
-            Axis3D::X => self.x = value,
-            Axis3D::Y => self.y = value,
-            Axis3D::Z => self.z = value
-        }
-    }
+use crate::common::movement::{Axis3D, Bounds, Point3};
+use crate::common::parse;
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Solution};
 
-    pub fn get_moved_in_axis(&self, axis: Axis3D, amount: i128) -> Point3D {
-        self.get_with_axis_value(axis, self.get_axis_value(axis) + amount)
-    }
-
-    pub fn get_with_axis_value(&self, axis: Axis3D, value: i128) -> Point3D {
-        let mut other = self.clone();
-        other.set_axis_value(axis, value);
-        other
-    }
-
-    pub fn neighbors(&self) -> Vec<Point3D> {
-        let axis_movements = [-1, 1];
-        Axis3D::all()
-            .iter()
-            .flat_map(|&axis| axis_movements.iter().map(move |&movement| self.get_moved_in_axis(axis, movement)))
-            .collect()
-    }
-}
+type Point3D = Point3<i128>;
 
-fn parse_input(input: &str) -> Vec<Point3D> {
+fn parse_input(input: &str) -> AocResult<HashSet<Point3D>> {
     input.split("\n").map(|line| {
-        let coordinates: Vec<i128> = line.split(",").map(|part| part.parse::<i128>().unwrap()).collect();
-        Point3D {
+        let coordinates: Vec<i128> = parse::separated(line, ",", |part| parse::integer(input, part))?;
+        if coordinates.len() != 3 {
+            return Err(AocError::parse_in(input, line, format!("expected 3 coordinates, got {}", coordinates.len())));
+        }
+        Ok(Point3D {
             x: coordinates[0],
             y: coordinates[1],
             z: coordinates[2],
-        }
+        })
     }).collect()
 }
 
-fn part1(input: &str) -> u128 {
-    let points: HashSet<Point3D> = HashSet::from_iter(parse_input(input));
+fn part1(points: &HashSet<Point3D>) -> u128 {
     points
         .iter()
         .map(|point|
-            point.neighbors()
-                .iter()
-                .filter(|&neighbor| !points.contains(neighbor))
+            point.neighbors6()
+                .filter(|neighbor| !points.contains(neighbor))
                 .count() as u128
         )
         .sum()
@@ -97,7 +37,7 @@ fn part1(input: &str) -> u128 {
 fn is_in_bounds(point: &Point3D, ranges_by_axis: &HashMap<&Axis3D, RangeInclusive<i128>>) -> bool {
     Axis3D::all()
         .iter()
-        .all(|axis| ranges_by_axis.get(axis).unwrap().contains(point.get_axis_value(*axis)))
+        .all(|axis| ranges_by_axis.get(axis).unwrap().contains(&point.get_axis_value(*axis)))
 }
 
 fn count_open_faces(points: &HashSet<Point3D>) -> u128 {
@@ -108,7 +48,7 @@ fn count_open_faces(points: &HashSet<Point3D>) -> u128 {
             bounds_by_axis
                 .entry(axis)
                 .or_insert_with(|| Bounds::new(0, 0))
-                .update(*point.get_axis_value(axis));
+                .update(point.get_axis_value(axis));
         }
     }
 
@@ -129,7 +69,7 @@ fn count_open_faces(points: &HashSet<Point3D>) -> u128 {
 
     while !queue.is_empty() {
         let next = queue.pop_front().unwrap();
-        for neighbor in next.neighbors() {
+        for neighbor in next.neighbors6() {
             if points.contains(&neighbor) {
                 open_faces += 1;
             } else if !visited.contains(&neighbor) && is_in_bounds(&neighbor, &ranges_by_axis) {
@@ -142,16 +82,26 @@ fn count_open_faces(points: &HashSet<Point3D>) -> u128 {
     open_faces
 }
 
-fn part2(input: &str) -> u128 {
-    let points: HashSet<Point3D> = HashSet::from_iter(parse_input(input));
-    count_open_faces(&points)
+fn part2(points: &HashSet<Point3D>) -> u128 {
+    count_open_faces(points)
 }
 
-pub fn run() {
-    println!("Day 18");
-    let input = read_input(18);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+pub struct Day18;
+
+impl Solution for Day18 {
+    type Input<'a> = HashSet<Point3D>;
+
+    fn parse(input: &str) -> AocResult<HashSet<Point3D>> {
+        parse_input(input)
+    }
+
+    fn part1(input: &HashSet<Point3D>) -> AocResult<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &HashSet<Point3D>) -> AocResult<Answer> {
+        Ok(part2(input).into())
+    }
 }
 
 #[cfg(test)]
@@ -172,11 +122,11 @@ pub mod tests {
 
     #[test]
     pub fn part1() {
-        assert_eq!(64, super::part1(INPUT));
+        assert_eq!(64, super::part1(&super::parse_input(INPUT).unwrap()));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(58, super::part2(INPUT));
+        assert_eq!(58, super::part2(&super::parse_input(INPUT).unwrap()));
     }
 }
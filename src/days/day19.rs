@@ -1,10 +1,10 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
-use std::ops::RangeInclusive;
-use itertools::{Itertools, max, merge, min};
 use regex::Regex;
-use crate::common::movement::Bounds;
-use crate::common::read_input;
+use crate::common::parse_number_in;
+use crate::error::{AocError, AocResult};
+use crate::cancel::CancellationToken;
+use crate::solution::{Answer, PartContext, Solution};
 
 #[derive(Eq, Hash, PartialEq, Copy, Clone, Debug)]
 enum ResourceType {
@@ -24,13 +24,13 @@ impl ResourceType {
         ]
     }
 
-    pub fn from_str(value: &str) -> ResourceType {
+    pub fn from_str(value: &str) -> AocResult<ResourceType> {
         match value {
-            "ore" => ResourceType::Ore,
-            "clay" => ResourceType::Clay,
-            "obsidian" => ResourceType::Obsidian,
-            "geode" => ResourceType::Geode,
-            _ => panic!("Invalid resource type {}", value)
+            "ore" => Ok(ResourceType::Ore),
+            "clay" => Ok(ResourceType::Clay),
+            "obsidian" => Ok(ResourceType::Obsidian),
+            "geode" => Ok(ResourceType::Geode),
+            _ => Err(AocError::parse(format!("unknown resource type `{}`", value)))
         }
     }
 }
@@ -38,43 +38,54 @@ impl ResourceType {
 type RecipeCost = HashMap<ResourceType, u128>;
 type BlueprintRecipes = HashMap<ResourceType, RecipeCost>;
 
-struct Blueprint {
+pub struct Blueprint {
     id: u128,
     recipes: BlueprintRecipes,
 }
 
-fn parse_input(input: &str) -> Vec<Blueprint> {
+fn parse_input(input: &str) -> AocResult<Vec<Blueprint>> {
     let blueprint_regex = Regex::new(r"^Blueprint (?P<id>\d+): (?P<recipes>.+)$").unwrap();
     let recipe_regex = Regex::new(r"Each (?P<type>\w+?) robot costs (?P<requirements>.+?)[.]").unwrap();
 
-    input.split("\n").map(|blueprint_line| {
-        let blueprint_captures = blueprint_regex.captures(blueprint_line).unwrap();
-        let id: u128 = blueprint_captures.name("id").unwrap().as_str().parse().unwrap();
+    input.split("\n").enumerate().map(|(i, blueprint_line)| {
+        let blueprint_captures = blueprint_regex.captures(blueprint_line)
+            .ok_or_else(|| AocError::parse("expected `Blueprint <id>: <recipes>`").at_line(i + 1))?;
+        let id: u128 = parse_number_in(input, blueprint_captures.name("id").unwrap().as_str())?;
 
         let mut recipes: BlueprintRecipes = BlueprintRecipes::new();
 
         let recipes_str = blueprint_captures.name("recipes").unwrap().as_str();
         for recipe_captures in recipe_regex.captures_iter(recipes_str) {
-            let resource = ResourceType::from_str(recipe_captures.name("type").unwrap().as_str());
+            let type_match = recipe_captures.name("type").unwrap();
+            let resource = ResourceType::from_str(type_match.as_str())
+                .map_err(|error| error.at_line(i + 1))?;
             let requirements_str = recipe_captures.name("requirements").unwrap().as_str();
             let mut requirements = RecipeCost::new();
             for requirement_str in requirements_str.split(" and ") {
-                let (amount_str, type_str) = requirement_str.split_once(" ").unwrap();
-                requirements.insert(ResourceType::from_str(type_str), amount_str.parse().unwrap());
+                let (amount_str, type_str) = requirement_str.split_once(" ")
+                    .ok_or_else(|| AocError::parse_in(input, requirement_str, "expected a cost like `4 ore`"))?;
+                let requirement_type = ResourceType::from_str(type_str).map_err(|error| error.at_line(i + 1))?;
+                requirements.insert(requirement_type, parse_number_in(input, amount_str)?);
             }
 
             recipes.insert(resource, requirements);
         }
 
-        Blueprint {
+        // the search assumes every robot can be built
+        if let Some(missing) = ResourceType::all().iter().find(|resource| !recipes.contains_key(resource)) {
+            return Err(AocError::parse(format!("missing a recipe for the {:?} robot", missing)).at_line(i + 1));
+        }
+
+        Ok(Blueprint {
             id,
             recipes,
-        }
+        })
     }).collect()
 }
 
 struct SearchData<'a> {
     blueprint: &'a Blueprint,
+    cancel: &'a CancellationToken,
     robots_owned: HashMap<ResourceType, u128>,
     resources_owned: HashMap<ResourceType, u128>,
     minutes_left: u128,
@@ -103,12 +114,13 @@ fn empty_resource_inventory() -> HashMap<ResourceType, u128> {
 }
 
 fn purchase_and_find_next(data: &SearchData, resources_after_minute: &RecipeCost, possible_robot_type: ResourceType) -> u128 {
-    let SearchData { blueprint, robots_owned, resources_owned: _, minutes_left } = data;
+    let SearchData { blueprint, cancel, robots_owned, resources_owned: _, minutes_left } = data;
     let mut possible_robots_owned = robots_owned.clone();
     *possible_robots_owned.entry(possible_robot_type).or_insert(0) += 1;
     let possible_new_resources = purchase_resources(resources_after_minute, blueprint.recipes.get(&possible_robot_type).unwrap());
     find_max_geodes(SearchData {
         blueprint,
+        cancel,
         robots_owned: possible_robots_owned,
         resources_owned: possible_new_resources,
         minutes_left: minutes_left - 1
@@ -124,10 +136,9 @@ fn is_robot_needed(blueprint: &Blueprint, robots_owned: &HashMap<ResourceType, u
 }
 
 fn find_max_geodes(data: SearchData) -> u128 {
-    let SearchData { blueprint, robots_owned, resources_owned, minutes_left } = &data;
+    let SearchData { blueprint, cancel, robots_owned, resources_owned, minutes_left } = &data;
 
-    if *minutes_left == 0 {
-        println!("Inventory in this branch: {:?}", resources_owned.iter().map(|(resource_type, amount)| format!("{:?}={}", resource_type, amount)).join(", "));
+    if *minutes_left == 0 || cancel.is_cancelled() {
         return *resources_owned.get(&ResourceType::Geode).unwrap();
     }
 
@@ -161,6 +172,7 @@ fn find_max_geodes(data: SearchData) -> u128 {
     // todo: take action if we would otherwise never be able to afford the geode bot
     max_geodes = std::cmp::max(max_geodes, find_max_geodes(SearchData {
         blueprint,
+        cancel,
         robots_owned: robots_owned.clone(),
         resources_owned: resources_after_minute,
         minutes_left: minutes_left - 1
@@ -171,48 +183,78 @@ fn find_max_geodes(data: SearchData) -> u128 {
 
 const MINUTES_PART_1: u128 = 24;
 
-fn part1(input: &str) -> u128 {
-    let blueprints = parse_input(input);
+fn part1(blueprints: &Vec<Blueprint>, cancel: &CancellationToken) -> u128 {
     let mut max_geodes_by_blueprint: HashMap<u128, u128> = HashMap::new();
     for blueprint in blueprints {
         let mut default_robots_owned: HashMap<ResourceType, u128> = empty_resource_inventory();
         default_robots_owned.insert(ResourceType::Ore, 1);
 
-        println!("Finding max geodes for blueprint id {}", blueprint.id);
         max_geodes_by_blueprint.insert(blueprint.id, find_max_geodes(SearchData {
-            blueprint: &blueprint,
+            blueprint,
+            cancel,
             robots_owned: default_robots_owned,
             resources_owned: empty_resource_inventory(),
             minutes_left: MINUTES_PART_1
         }));
     }
-    println!("Max geodes by blueprint: {:?}", max_geodes_by_blueprint);
     max_geodes_by_blueprint.iter().map(|(id, geodes)| id * geodes).sum()
 }
 
-fn part2(input: &str) -> u128 {
+fn part2(_blueprints: &Vec<Blueprint>) -> u128 {
     0
 }
 
-pub fn run() {
-    println!("Day 19");
-    let input = read_input(19);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+pub struct Day19;
+
+impl Solution for Day19 {
+    type Input<'a> = Vec<Blueprint>;
+
+    fn parse(input: &str) -> AocResult<Vec<Blueprint>> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Vec<Blueprint>) -> AocResult<Answer> {
+        Ok(part1(input, &CancellationToken::never()).into())
+    }
+
+    fn part1_with(input: &Vec<Blueprint>, context: &PartContext) -> AocResult<Answer> {
+        Ok(part1(input, &context.cancel).into())
+    }
+
+    fn part2(input: &Vec<Blueprint>) -> AocResult<Answer> {
+        Ok(part2(input).into())
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use std::time::{Duration, Instant};
+    use crate::cancel::CancellationToken;
+    use crate::error::AocError;
+
     const INPUT: &str = "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
 Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.";
 
     #[test]
     pub fn part1() {
-        assert_eq!(33, super::part1(INPUT));
+        assert_eq!(33, super::part1(&super::parse_input(INPUT).unwrap(), &CancellationToken::never()));
+    }
+
+    #[test]
+    pub fn part1_gives_up_when_cancelled() {
+        let start = Instant::now();
+        super::part1(&super::parse_input(INPUT).unwrap(), &CancellationToken::after(Duration::from_millis(50)));
+        assert!(start.elapsed() < Duration::from_secs(5));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(58, super::part2(INPUT));
+        assert_eq!(58, super::part2(&super::parse_input(INPUT).unwrap()));
+    }
+
+    #[test]
+    pub fn invalid_blueprint() {
+        let input = INPUT.replacen("Each clay robot costs 3 ore", "Each clay robot costs x ore", 1);
+        assert_eq!(Some(AocError::parse_at(2, 64, "expected a number, got `x`")), super::parse_input(&input).err());
     }
 }
@@ -1,6 +1,7 @@
 use std::fmt;
 use std::fmt::Formatter;
-use crate::common::read_input;
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Solution};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 enum Move {
@@ -95,21 +96,47 @@ fn fight_score(opponent_move: Move, your_move: Move) -> i32 {
     outcome_value(fight_outcome) + move_value(your_move.clone())
 }
 
-fn part1(input: &str) -> i32 {
+type Guide = Vec<(char, char)>;
+
+fn parse_line(line: &str) -> AocResult<(char, char)> {
+    let chars: Vec<char> = line.chars().collect();
+    if chars.len() != 3 || chars[1] != ' ' {
+        return Err(AocError::parse(format!("expected `<opponent move> <guide value>`, got `{}`", line)));
+    }
+
+    if !['A', 'B', 'C'].contains(&chars[0]) {
+        return Err(AocError::parse(format!("expected opponent move A, B or C, got `{}`", chars[0])));
+    }
+
+    if !['X', 'Y', 'Z'].contains(&chars[2]) {
+        return Err(AocError::parse(format!("expected guide value X, Y or Z, got `{}`", chars[2])));
+    }
+
+    Ok((chars[0], chars[2]))
+}
+
+fn parse_input(input: &str) -> AocResult<Guide> {
     input.split("\n")
-        .map(|line| {
-            let opponent_move = move_from_guide(line.chars().nth(0).expect("Char missing"));
-            let your_move = move_from_guide(line.chars().nth(2).expect("Char missing"));
+        .enumerate()
+        .map(|(i, line)| parse_line(line).map_err(|err| err.at_line(i + 1)))
+        .collect()
+}
+
+fn part1(guide: &Guide) -> i32 {
+    guide.iter()
+        .map(|&(opponent_guide_value, your_guide_value)| {
+            let opponent_move = move_from_guide(opponent_guide_value);
+            let your_move = move_from_guide(your_guide_value);
             fight_score(opponent_move, your_move)
         })
         .sum()
 }
 
-fn part2(input: &str) -> i32 {
-    input.split("\n")
-        .map(|line| {
-            let opponent_move = move_from_guide(line.chars().nth(0).expect("Char missing"));
-            let desired_outcome = outcome_from_guide(line.chars().nth(2).expect("Char missing"));
+fn part2(guide: &Guide) -> i32 {
+    guide.iter()
+        .map(|&(opponent_guide_value, outcome_guide_value)| {
+            let opponent_move = move_from_guide(opponent_guide_value);
+            let desired_outcome = outcome_from_guide(outcome_guide_value);
             let your_move = match desired_outcome {
                 Outcome::Draw => opponent_move,
                 Outcome::Win => get_beaten_by(opponent_move),
@@ -120,13 +147,22 @@ fn part2(input: &str) -> i32 {
         .sum()
 }
 
-pub fn run() {
-    println!("Day 2");
+pub struct Day2;
+
+impl Solution for Day2 {
+    type Input<'a> = Guide;
+
+    fn parse(input: &str) -> AocResult<Guide> {
+        parse_input(input)
+    }
 
-    let input = read_input(2);
+    fn part1(input: &Guide) -> AocResult<Answer> {
+        Ok(part1(input).into())
+    }
 
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+    fn part2(input: &Guide) -> AocResult<Answer> {
+        Ok(part2(input).into())
+    }
 }
 
 #[cfg(test)]
@@ -139,11 +175,16 @@ C Z";
 
     #[test]
     pub fn part1() {
-        assert_eq!(15, super::part1(INPUT))
+        assert_eq!(15, super::part1(&parse_input(INPUT).unwrap()))
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(12, super::part2(INPUT))
+        assert_eq!(12, super::part2(&parse_input(INPUT).unwrap()))
+    }
+
+    #[test]
+    pub fn invalid_guide() {
+        assert_eq!(Err(AocError::parse("expected guide value X, Y or Z, got `Q`").at_line(2)), parse_input("A Y\nB Q"));
     }
 }
\ No newline at end of file
@@ -1,63 +1,99 @@
 use std::collections::VecDeque;
-use itertools::Itertools;
-use crate::common::{read_input, wrap_value};
+use crate::common::{parse_number_in, wrap_value};
+use crate::common::numeric::{with_number_type, Number};
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, PartContext, Solution};
 
-fn parse_input(input: &str) -> VecDeque<i128> {
-    input.split("\n").map(|line| line.parse::<i128>().unwrap()).collect()
+fn parse_input(input: &str) -> AocResult<VecDeque<i128>> {
+    let values = input.split("\n").map(|line| parse_number_in(input, line)).collect::<AocResult<VecDeque<i128>>>()?;
+    if !values.contains(&0) {
+        return Err(AocError::parse("expected the list to contain 0"));
+    }
+    Ok(values)
 }
 
-struct Node {
-    value: i128,
+struct Node<N> {
+    value: N,
     original_index: usize,
 }
 
-fn mix_list(items: &mut VecDeque<Node>) {
+fn mix_list<N: Number>(items: &mut VecDeque<Node<N>>) -> AocResult<()> {
     let mut current_items_index = 0;
     for current_index_to_mix in 0..items.len() {
         while items[current_items_index].original_index != current_index_to_mix {
             current_items_index = wrap_value((current_items_index + 1) as i128, items.len() as i128) as usize;
         }
         let node = items.remove(current_items_index).unwrap();
-        items.insert(wrap_value((current_items_index as i128 + node.value as i128), items.len() as i128) as usize, node);
+        // Only how far the value moves around the rest of the list matters, which always fits in a usize
+        let shift = match items.len() {
+            0 => 0,
+            len => node.value.try_rem_euclid(&N::from_i128(len as i128))?.to_i128().unwrap() as usize
+        };
+        let new_index = (current_items_index + shift) % items.len().max(1);
+        items.insert(new_index, node);
     }
+    Ok(())
 }
 
-fn find_grove_coordinates(items: &VecDeque<Node>) -> i128 {
-    let zero_index = items.iter().position(|node| node.value == 0).expect("Zero is not in the list");
-    items[(zero_index + 1000) % items.len()].value + items[(zero_index + 2000) % items.len()].value + items[(zero_index + 3000) % items.len()].value
+fn find_grove_coordinates<N: Number>(items: &VecDeque<Node<N>>) -> AocResult<N> {
+    let zero_index = items.iter().position(|node| node.value.is_zero()).expect("Zero is not in the list");
+    [1000, 2000, 3000].iter()
+        .try_fold(N::from_i128(0), |sum, offset| sum.try_add(&items[(zero_index + offset) % items.len()].value))
 }
 
-fn part1(input: &str) -> i128 {
-    let mut list: VecDeque<Node> = parse_input(input).iter().enumerate().map(|(i, &value)| Node {
-        value,
+fn part1<N: Number>(values: &VecDeque<i128>) -> AocResult<N> {
+    let mut list: VecDeque<Node<N>> = values.iter().enumerate().map(|(i, &value)| Node {
+        value: N::from_i128(value),
         original_index: i,
     }).collect();
-    mix_list(&mut list);
+    mix_list(&mut list)?;
     find_grove_coordinates(&list)
 }
 
 const DECRYPTION_KEY: i128 = 811589153;
 
-fn part2(input: &str) -> i128 {
-    let mut list: VecDeque<Node> = parse_input(input).iter().enumerate().map(|(i, &value)| Node {
-        value: value * DECRYPTION_KEY,
+fn part2<N: Number>(values: &VecDeque<i128>) -> AocResult<N> {
+    let mut list: VecDeque<Node<N>> = values.iter().enumerate().map(|(i, &value)| Ok(Node {
+        value: N::from_i128(value).try_mul(&N::from_i128(DECRYPTION_KEY))?,
         original_index: i,
-    }).collect();
+    })).collect::<AocResult<VecDeque<Node<N>>>>()?;
     for _ in 0..10 {
-        mix_list(&mut list);
+        mix_list(&mut list)?;
     }
     find_grove_coordinates(&list)
 }
 
-pub fn run() {
-    println!("Day 20");
-    let input = read_input(20);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+pub struct Day20;
+
+impl Solution for Day20 {
+    type Input<'a> = VecDeque<i128>;
+
+    fn parse(input: &str) -> AocResult<VecDeque<i128>> {
+        parse_input(input)
+    }
+
+    fn part1(input: &VecDeque<i128>) -> AocResult<Answer> {
+        part1::<i128>(input).map(Answer::from)
+    }
+
+    fn part1_with(input: &VecDeque<i128>, context: &PartContext) -> AocResult<Answer> {
+        with_number_type!(context.arithmetic, N => part1::<N>(input).map(|value| value.to_answer()))
+    }
+
+    fn part2(input: &VecDeque<i128>) -> AocResult<Answer> {
+        part2::<i128>(input).map(Answer::from)
+    }
+
+    fn part2_with(input: &VecDeque<i128>, context: &PartContext) -> AocResult<Answer> {
+        with_number_type!(context.arithmetic, N => part2::<N>(input).map(|value| value.to_answer()))
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use num_bigint::BigInt;
+    use crate::common::numeric::Checked;
+
     const INPUT: &str = "1
 2
 -3
@@ -68,11 +104,23 @@ pub mod tests {
 
     #[test]
     pub fn part1() {
-        assert_eq!(3, super::part1(INPUT));
+        assert_eq!(Ok(3), super::part1::<i128>(&super::parse_input(INPUT).unwrap()));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(1623178306, super::part2(INPUT));
+        assert_eq!(Ok(1623178306), super::part2::<i128>(&super::parse_input(INPUT).unwrap()));
+    }
+
+    #[test]
+    pub fn part2_in_every_arithmetic() {
+        let values = super::parse_input(INPUT).unwrap();
+        assert_eq!(Ok(Checked(1623178306)), super::part2::<Checked>(&values));
+        assert_eq!(Ok(BigInt::from(1623178306)), super::part2::<BigInt>(&values));
+
+        // Too big for 128 bits once the decryption key is applied
+        let values = super::parse_input("0\n1\n-300000000000000000000000000000").unwrap();
+        assert!(super::part2::<Checked>(&values).is_err());
+        assert!(super::part2::<BigInt>(&values).is_ok());
     }
 }
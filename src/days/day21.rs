@@ -1,59 +1,73 @@
-use std::cmp::max;
 use std::collections::HashMap;
-use std::ops::{Add, Div, Mul, Sub};
-use crate::common::{BinaryOperation, read_input};
+use crate::common::{parse, BinaryOperation};
+use crate::common::numeric::{with_number_type, Number};
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, PartContext, Solution};
 
 #[derive(Copy, Clone)]
-enum MonkeyJob<'a> {
+pub enum MonkeyJob<'a> {
     Yell(i128),
     Math(&'a str, BinaryOperation, &'a str)
 }
 
-fn do_operation<T>(left: T, operation: BinaryOperation, right: T) -> T
-where T: Add<Output = T> + Sub<Output = T> + Div<Output = T> + Mul<Output = T>
-{
+// Monkeys only ever divide numbers that divide evenly, so anything else means the numbers went wrong
+fn do_operation<N: Number>(left: &N, operation: BinaryOperation, right: &N) -> AocResult<N> {
     match operation {
-        BinaryOperation::Add => left + right,
-        BinaryOperation::Subtract => left - right,
-        BinaryOperation::Multiply => left * right,
-        BinaryOperation::Divide => left / right
+        BinaryOperation::Add => left.try_add(right),
+        BinaryOperation::Subtract => left.try_sub(right),
+        BinaryOperation::Multiply => left.try_mul(right),
+        BinaryOperation::Divide => left.try_div_exact(right)
     }
 }
 
-fn resolve_job(monkeys: &HashMap<&str, MonkeyJob>, job: &MonkeyJob) -> i128 {
+fn resolve_job<N: Number>(monkeys: &HashMap<&str, MonkeyJob>, job: &MonkeyJob) -> AocResult<N> {
     match job {
-        MonkeyJob::Yell(value) => *value,
+        MonkeyJob::Yell(value) => Ok(N::from_i128(*value)),
         MonkeyJob::Math(left_monkey_name, operation, right_monkey_name) => {
             let left_monkey_job = monkeys.get(left_monkey_name).unwrap();
             let right_monkey_job = monkeys.get(right_monkey_name).unwrap();
             do_operation(
-                resolve_job(monkeys, left_monkey_job),
+                &resolve_job(monkeys, left_monkey_job)?,
                 *operation,
-                resolve_job(monkeys, right_monkey_job)
+                &resolve_job(monkeys, right_monkey_job)?
             )
         }
-        _ => panic!()
     }
 }
 
-fn parse_input(input: &str) -> HashMap<&str, MonkeyJob> {
-    input.lines().map(|line| {
-        let (name, job_str) = line.split_once(": ").unwrap();
-        if let Ok(value) = job_str.parse() {
-            (name, MonkeyJob::Yell(value))
-        } else {
-            let mut parts = job_str.split_ascii_whitespace();
-            let left_monkey_name = parts.next().unwrap();
-            let operation = BinaryOperation::from_str(parts.next().unwrap());
-            let right_monkey_name = parts.next().unwrap();
-            (name, MonkeyJob::Math(left_monkey_name, operation, right_monkey_name))
+fn parse_job<'a>(job_str: &'a str) -> AocResult<MonkeyJob<'a>> {
+    if let Ok(value) = job_str.parse() {
+        return Ok(MonkeyJob::Yell(value));
+    }
+
+    let parts: Vec<&str> = job_str.split_ascii_whitespace().collect();
+    if parts.len() != 3 {
+        return Err(AocError::parse(format!("expected a number or `<monkey> <operation> <monkey>`, got `{}`", job_str)));
+    }
+    Ok(MonkeyJob::Math(parts[0], BinaryOperation::from_str(parts[1])?, parts[2]))
+}
+
+fn parse_input(input: &str) -> AocResult<HashMap<&str, MonkeyJob<'_>>> {
+    let monkeys = parse::key_values(input, input, ":")?.into_iter().map(|(name, job_str)| {
+        Ok((name, parse_job(job_str).map_err(|error| AocError::parse_in(input, job_str, error.to_string()))?))
+    }).collect::<AocResult<HashMap<&str, MonkeyJob>>>()?;
+
+    if !monkeys.contains_key("root") {
+        return Err(AocError::parse("expected a monkey named `root`"));
+    }
+    for job in monkeys.values() {
+        if let MonkeyJob::Math(left, _, right) = job {
+            if let Some(unknown) = [left, right].into_iter().find(|name| !monkeys.contains_key(*name)) {
+                return Err(AocError::parse_in(input, unknown, format!("monkey `{}` does not exist", unknown)));
+            }
         }
-    }).collect()
+    }
+
+    Ok(monkeys)
 }
 
-fn part1(input: &str) -> i128 {
-    let monkeys = parse_input(input);
-    resolve_job(&monkeys, monkeys.get("root").unwrap())
+fn part1<N: Number>(monkeys: &HashMap<&str, MonkeyJob>) -> AocResult<N> {
+    resolve_job(monkeys, monkeys.get("root").unwrap())
 }
 
 fn contains_monkey(monkeys: &HashMap<&str, MonkeyJob>, target_monkey: &str, current_monkey: &MonkeyJob) -> bool {
@@ -70,78 +84,87 @@ fn contains_monkey(monkeys: &HashMap<&str, MonkeyJob>, target_monkey: &str, curr
 
 const HUMAN_MONKEY_NAME: &str = "humn";
 
-fn check_does_increasing_x_increase_result(mut monkeys: HashMap<&str, MonkeyJob>, job: &MonkeyJob) -> bool {
-    monkeys.insert(HUMAN_MONKEY_NAME, MonkeyJob::Yell(50));
-    let a = resolve_job(&monkeys, job);
-    monkeys.insert(HUMAN_MONKEY_NAME, MonkeyJob::Yell(100));
-    let b = resolve_job(&monkeys, job);
-    return a < b;
+fn depends_on_human(monkeys: &HashMap<&str, MonkeyJob>, monkey_name: &str) -> bool {
+    monkey_name == HUMAN_MONKEY_NAME || contains_monkey(monkeys, HUMAN_MONKEY_NAME, monkeys.get(monkey_name).unwrap())
 }
 
-fn find_human_value(mut monkeys: HashMap<&str, MonkeyJob>, job_with_human: &MonkeyJob, job_without_human: &MonkeyJob) -> i128 {
-    let without_human_value = resolve_job(&monkeys, job_without_human);
-    let does_increasing_x_increase_result = check_does_increasing_x_increase_result(monkeys.clone(), job_with_human);
-    let mut min = i128::MIN / 10_000;
-    let mut max = i128::MAX / 10_000;
-    loop {
-        let current = ((max - min) / 2) + min;
+// Works back from what `monkey_name` has to yell, undoing one operation at a time until reaching the human
+fn find_human_value<N: Number>(monkeys: &HashMap<&str, MonkeyJob>, monkey_name: &str, target: N) -> AocResult<N> {
+    let (left_name, operation, right_name) = match monkeys.get(monkey_name).unwrap() {
+        _ if monkey_name == HUMAN_MONKEY_NAME => return Ok(target),
+        MonkeyJob::Yell(_) => return Err(AocError::arithmetic(format!("`{}` doesn't depend on the human", monkey_name))),
+        MonkeyJob::Math(left_name, operation, right_name) => (*left_name, *operation, *right_name)
+    };
+
+    if depends_on_human(monkeys, left_name) {
+        let right: N = resolve_job(monkeys, monkeys.get(right_name).unwrap())?;
+        let left_target = match operation {
+            BinaryOperation::Add => target.try_sub(&right)?,
+            BinaryOperation::Subtract => target.try_add(&right)?,
+            BinaryOperation::Multiply => target.try_div_exact(&right)?,
+            BinaryOperation::Divide => target.try_mul(&right)?
+        };
+        find_human_value(monkeys, left_name, left_target)
+    } else {
+        let left: N = resolve_job(monkeys, monkeys.get(left_name).unwrap())?;
+        let right_target = match operation {
+            BinaryOperation::Add => target.try_sub(&left)?,
+            BinaryOperation::Subtract => left.try_sub(&target)?,
+            BinaryOperation::Multiply => target.try_div_exact(&left)?,
+            BinaryOperation::Divide => left.try_div_exact(&target)?
+        };
+        find_human_value(monkeys, right_name, right_target)
+    }
+}
 
-        monkeys.insert(HUMAN_MONKEY_NAME, MonkeyJob::Yell(current));
+fn part2<N: Number>(monkeys: &HashMap<&str, MonkeyJob>) -> AocResult<N> {
+    let (root_left_name, root_right_name) = match monkeys.get("root").unwrap() {
+        MonkeyJob::Math(root_left_name, _, root_right_name) => (*root_left_name, *root_right_name),
+        MonkeyJob::Yell(_) => return Err(AocError::arithmetic("`root` has to compare two monkeys"))
+    };
 
-        let with_human_value = resolve_job(&monkeys, job_with_human);
+    // Whichever side doesn't hear from the human decides what the other side has to come to
+    if depends_on_human(monkeys, root_left_name) {
+        let target = resolve_job(monkeys, monkeys.get(root_right_name).unwrap())?;
+        find_human_value(monkeys, root_left_name, target)
+    } else {
+        let target = resolve_job(monkeys, monkeys.get(root_left_name).unwrap())?;
+        find_human_value(monkeys, root_right_name, target)
+    }
+}
 
-        if with_human_value == without_human_value {
-            return current;
-        }
+pub struct Day21;
 
-        if with_human_value < without_human_value {
-            if does_increasing_x_increase_result {
-                min = current;
-            } else {
-                max = current;
-            }
-        } else {
-            if does_increasing_x_increase_result {
-                max = current;
-            } else {
-                min = current;
-            }
-        }
+impl Solution for Day21 {
+    type Input<'a> = HashMap<&'a str, MonkeyJob<'a>>;
 
-        if min == max {
-            panic!("Could not find value");
-        }
+    fn parse(input: &str) -> AocResult<HashMap<&str, MonkeyJob<'_>>> {
+        parse_input(input)
     }
-}
-
-fn part2(input: &str) -> i128 {
-    let mut monkeys = parse_input(input);
 
-    if let MonkeyJob::Math(root_left_name, _, root_right_name) = monkeys.get("root").unwrap() {
-        let root_left_job = monkeys.get(root_left_name).unwrap();
-        let root_right_job = monkeys.get(root_right_name).unwrap();
+    fn part1(input: &HashMap<&str, MonkeyJob>) -> AocResult<Answer> {
+        part1::<i128>(input).map(Answer::from)
+    }
 
-        let is_monkey_in_left = contains_monkey(&monkeys, HUMAN_MONKEY_NAME, root_left_job);
+    fn part1_with(input: &HashMap<&str, MonkeyJob>, context: &PartContext) -> AocResult<Answer> {
+        with_number_type!(context.arithmetic, N => part1::<N>(input).map(|value| value.to_answer()))
+    }
 
-        if is_monkey_in_left {
-            find_human_value(monkeys.clone(), root_left_job, root_right_job)
-        } else {
-            find_human_value(monkeys.clone(), root_right_job, root_left_job)
-        }
-    } else {
-        panic!();
+    fn part2(input: &HashMap<&str, MonkeyJob>) -> AocResult<Answer> {
+        part2::<i128>(input).map(Answer::from)
     }
-}
 
-pub fn run() {
-    println!("Day 21");
-    let input = read_input(21);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+    fn part2_with(input: &HashMap<&str, MonkeyJob>, context: &PartContext) -> AocResult<Answer> {
+        with_number_type!(context.arithmetic, N => part2::<N>(input).map(|value| value.to_answer()))
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use num_bigint::BigInt;
+    use crate::common::numeric::Checked;
+    use crate::error::AocError;
+
     const INPUT: &str = "root: pppw + sjmn
 dbpl: 5
 cczh: sllz + lgvd
@@ -160,11 +183,22 @@ hmdt: 32";
 
     #[test]
     pub fn part1() {
-        assert_eq!(152, super::part1(INPUT));
+        assert_eq!(Ok(152), super::part1::<i128>(&super::parse_input(INPUT).unwrap()));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(301, super::part2(INPUT));
+        assert_eq!(Ok(301), super::part2::<i128>(&super::parse_input(INPUT).unwrap()));
+    }
+
+    #[test]
+    pub fn reports_overflow() {
+        let input = "root: big * big\nbig: 100000000000000000000";
+        let monkeys = super::parse_input(input).unwrap();
+        assert!(super::part1::<Checked>(&monkeys).is_err());
+        assert_eq!(BigInt::from(10).pow(40), super::part1::<BigInt>(&monkeys).unwrap());
+
+        let monkeys = super::parse_input("root: humn + half\nhalf: nine / two\nnine: 9\ntwo: 2\nhumn: 1").unwrap();
+        assert_eq!(Err(AocError::arithmetic("9 is not divisible by 2")), super::part1::<Checked>(&monkeys));
     }
 }
@@ -1,9 +1,11 @@
 use std::collections::{HashMap, HashSet};
 use std::iter::Map;
-use itertools::Itertools;
 use regex::Regex;
-use crate::common::movement::{Bounds, GridDirection, Point};
-use crate::common::{read_input, read_input_no_trim, wrap_value, wrap_value_around_bounds};
+use crate::common::movement::{Bounds, GridDirection, Orientation, Point};
+use crate::common::{parse_number_in, wrap_value, wrap_value_around_bounds};
+use crate::common::render::{Animation, Cell, Frame, Rgb};
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Solution};
 
 struct WrapGrid {
     // Overall bounds across the whole grid - no guarantee that these are in bounds
@@ -38,11 +40,11 @@ impl TurnDirection {
         GRID_DIRECTIONS_IN_CLOCKWISE_ORDER[new_direction_index as usize]
     }
 
-    pub fn from_str(value: &str) -> TurnDirection {
+    pub fn from_str(value: &str) -> AocResult<TurnDirection> {
         match value {
-            "L" => TurnDirection::Left,
-            "R" => TurnDirection::Right,
-            _ => panic!()
+            "L" => Ok(TurnDirection::Left),
+            "R" => Ok(TurnDirection::Right),
+            _ => Err(AocError::parse(format!("expected `L` or `R`, got `{}`", value)))
         }
     }
 }
@@ -52,7 +54,7 @@ struct MoveInstruction {
     move_amount: u128,
 }
 
-struct ProgramInput {
+pub struct ProgramInput {
     map: WrapGrid,
     instructions: Vec<MoveInstruction>,
 }
@@ -61,7 +63,7 @@ fn add_to_bounds(bounds_by_coordinate: &mut HashMap<i128, Bounds<i128>>, coordin
     bounds_by_coordinate.entry(coordinate).or_insert_with(|| Bounds::new(0, 0)).update(bound_coordinate)
 }
 
-fn parse_map(input: &str) -> WrapGrid {
+fn parse_map(input: &str) -> AocResult<WrapGrid> {
     let mut grid = WrapGrid {
         overall_x_bounds: Bounds::new(0, 0),
         overall_y_bounds: Bounds::new(0, 0),
@@ -71,7 +73,7 @@ fn parse_map(input: &str) -> WrapGrid {
     };
 
     for (y, line) in input.lines().rev().enumerate() {
-        for (x, c) in line.chars().enumerate() {
+        for (x, (offset, c)) in line.char_indices().enumerate() {
             let point = Point { x: x as i128, y: y as i128 };
             match c {
                 '#' | '.' => {
@@ -83,32 +85,40 @@ fn parse_map(input: &str) -> WrapGrid {
                         grid.walls.insert(point);
                     }
                 }
-                _ => ()
+                ' ' => (),
+                _ => return Err(AocError::parse_in(input, &line[offset..], format!("unexpected map tile `{}`", c)))
             }
         }
     }
 
-    grid
+    Ok(grid)
 }
 
-fn parse_input(input: &str) -> ProgramInput {
+fn parse_input(input: &str) -> AocResult<ProgramInput> {
+    let instructions_regex = Regex::new(r"^(?:\d+[LR]?)+$").unwrap();
     let turn_instruction_regex = Regex::new(r"(?P<count>\d+)(?P<turn>[LR])?").unwrap();
 
-    let (map_str, instructions_str) = input.split_once("\n\n").unwrap();
+    let (map_str, instructions_str) = input.split_once("\n\n")
+        .ok_or_else(|| AocError::parse("expected the map and the path separated by a blank line"))?;
+    let instructions_str = instructions_str.trim();
 
-    let map = parse_map(map_str);
+    let map = parse_map(map_str)?;
+
+    if !instructions_regex.is_match(instructions_str) {
+        return Err(AocError::parse_in(input, instructions_str, "expected a path like `10R5L5`"));
+    }
 
     let instructions: Vec<MoveInstruction> = turn_instruction_regex.captures_iter(instructions_str).map(|capture| {
-        MoveInstruction {
-            turn_dir: capture.name("turn").map(|turn_match| TurnDirection::from_str(turn_match.as_str())),
-            move_amount: capture.name("count").unwrap().as_str().parse().unwrap(),
-        }
-    }).collect();
+        Ok(MoveInstruction {
+            turn_dir: capture.name("turn").map(|turn_match| TurnDirection::from_str(turn_match.as_str())).transpose()?,
+            move_amount: parse_number_in(input, capture.name("count").unwrap().as_str())?,
+        })
+    }).collect::<AocResult<Vec<MoveInstruction>>>()?;
 
-    ProgramInput {
+    Ok(ProgramInput {
         map,
         instructions,
-    }
+    })
 }
 
 struct MapState {
@@ -161,38 +171,35 @@ fn direction_value(direction: GridDirection) -> i128 {
     }
 }
 
-fn render_grid(map: &WrapGrid, last_dir_by_point: &HashMap<Point, GridDirection>) {
-    let rendered_grid = map.overall_y_bounds.to_range().rev().map(|y| {
-        map.overall_x_bounds.to_range().map(|x| {
-            if !map.y_bounds_by_x.get(&x).unwrap().to_range().contains(&y) || !map.x_bounds_by_y.get(&y).unwrap().to_range().contains(&x) {
-                ' '
-            } else {
-                let point = Point { x, y };
-
-                let last_dir_for_this_point_opt = last_dir_by_point.get(&point);
-                if let Some(last_dir_for_this_point) = last_dir_for_this_point_opt {
-                    return match last_dir_for_this_point {
-                        GridDirection::Up => '^',
-                        GridDirection::Down => 'v',
-                        GridDirection::Left => '<',
-                        GridDirection::Right => '>'
-                    };
-                }
+// The map with the path walked across it, each tile showing the way it was last crossed
+fn render_grid(map: &WrapGrid, last_dir_by_point: &HashMap<Point, GridDirection>) -> Frame {
+    Frame::from_bounds(map.overall_x_bounds, map.overall_y_bounds, Orientation::YUp, |point| {
+        let Point { x, y } = *point;
+        if !map.y_bounds_by_x.get(&x).unwrap().to_range().contains(&y) || !map.x_bounds_by_y.get(&y).unwrap().to_range().contains(&x) {
+            return Cell::new(' ', Rgb::BLACK);
+        }
 
-                if map.walls.contains(&point) {
-                    '#'
-                } else {
-                    '.'
-                }
-            }
-        }).join("")
-    }).join("\n");
+        if let Some(last_dir_for_this_point) = last_dir_by_point.get(point) {
+            let glyph = match last_dir_for_this_point {
+                GridDirection::Up => '^',
+                GridDirection::Down => 'v',
+                GridDirection::Left => '<',
+                GridDirection::Right => '>'
+            };
+            return Cell::new(glyph, Rgb::RED);
+        }
 
-    println!("{}", rendered_grid);
+        if map.walls.contains(point) {
+            Cell::new('#', Rgb::GREY)
+        } else {
+            Cell::empty()
+        }
+    })
 }
 
-fn part1(input: &str) -> i128 {
-    let ProgramInput { mut map, instructions } = parse_input(input);
+// Follows every instruction from the leftmost tile of the top row, remembering which way each tile was last crossed
+fn walk(program_input: &ProgramInput) -> (MapState, HashMap<Point, GridDirection>) {
+    let ProgramInput { map, instructions } = program_input;
     let max_y = map.overall_y_bounds.max;
     let mut current_state = MapState {
         direction: GridDirection::Right,
@@ -203,22 +210,46 @@ fn part1(input: &str) -> i128 {
         },
     };
     let mut last_dir_by_point = HashMap::new();
-    for instruction in &instructions {
-        current_state = execute_instruction(&mut last_dir_by_point, &map, current_state, instruction);
+    for instruction in instructions {
+        current_state = execute_instruction(&mut last_dir_by_point, map, current_state, instruction);
     }
-    // render_grid(&map, &last_dir_by_point);
+    (current_state, last_dir_by_point)
+}
+
+fn part1(program_input: &ProgramInput) -> i128 {
+    let map = &program_input.map;
+    let (current_state, _) = walk(program_input);
     (1000 * ((map.overall_y_bounds.max - current_state.position.y) + 1)) + (4 * (current_state.position.x + 1)) + direction_value(current_state.direction)
 }
 
-fn part2(input: &str) -> u128 {
+fn part2(_program_input: &ProgramInput) -> u128 {
     0
 }
 
-pub fn run() {
-    println!("Day 22");
-    let input = read_input_no_trim(22);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+pub struct Day22;
+
+impl Solution for Day22 {
+    type Input<'a> = ProgramInput;
+
+    // The first row of the map starts with spaces, so trimming would shift it
+    const TRIM_INPUT: bool = false;
+
+    fn parse(input: &str) -> AocResult<ProgramInput> {
+        parse_input(input)
+    }
+
+    fn part1(input: &ProgramInput) -> AocResult<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &ProgramInput) -> AocResult<Answer> {
+        Ok(part2(input).into())
+    }
+
+    fn render(input: &ProgramInput) -> AocResult<Option<Animation>> {
+        let (_, last_dir_by_point) = walk(input);
+        Ok(Some(Animation::still(render_grid(&input.map, &last_dir_by_point))))
+    }
 }
 
 #[cfg(test)]
@@ -240,11 +271,11 @@ pub mod tests {
 
     #[test]
     pub fn part1() {
-        assert_eq!(6032, super::part1(INPUT));
+        assert_eq!(6032, super::part1(&super::parse_input(INPUT).unwrap()));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(20, super::part2(INPUT));
+        assert_eq!(20, super::part2(&super::parse_input(INPUT).unwrap()));
     }
 }
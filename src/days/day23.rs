@@ -1,34 +1,22 @@
 use std::collections::{HashMap, HashSet};
-use itertools::Itertools;
-use crate::common::movement::{Bounds, GridDirection, Point};
-use crate::common::read_input;
+use std::time::Duration;
+use crate::common::movement::{Bounds, GridDirection, Orientation, Point};
+use crate::common::render::{Animation, Cell, Frame, Rgb};
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Solution};
 
-fn parse_input(input: &str) -> HashSet<Point> {
+fn parse_input(input: &str) -> AocResult<HashSet<Point>> {
     input.lines().rev().enumerate().flat_map(|(y, line)| {
-        line.chars().enumerate().filter_map(move |(x, c)| {
-            if c == '#' {
-                Some(Point { x: x as i128, y: y as i128 })
-            } else {
-                None
+        line.char_indices().enumerate().filter_map(move |(x, (offset, c))| {
+            match c {
+                '#' => Some(Ok(Point { x: x as i128, y: y as i128 })),
+                '.' => None,
+                _ => Some(Err(AocError::parse_in(input, &line[offset..], format!("expected `#` or `.`, got `{}`", c))))
             }
         })
     }).collect()
 }
 
-fn get_neighbors(point: &Point) -> Vec<Point> {
-    let Point { x, y } = *point;
-    vec![
-        Point { x: x - 1, y },
-        Point { x: x - 1, y: y - 1 },
-        Point { x: x - 1, y: y + 1 },
-        Point { x: x + 1, y },
-        Point { x: x + 1, y: y - 1 },
-        Point { x: x + 1, y: y + 1 },
-        Point { x, y: y + 1 },
-        Point { x, y: y - 1 },
-    ]
-}
-
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
 enum DirectionWithDiagonal {
     N,
@@ -196,24 +184,41 @@ fn find_elf_rect(map: &HashSet<Point>) -> Rect {
     }
 }
 
-fn render_map(map: &HashSet<Point>) {
-    let elf_rect = find_elf_rect(map);
-    println!("{}",
-             elf_rect.y_bounds.to_range().rev().map(|y|
-                 elf_rect.x_bounds.to_range().map(|x| {
-                     let point = Point { x, y };
-                     if map.contains(&point) {
-                         '#'
-                     } else {
-                         '.'
-                     }
-                 }).join("")
-             ).join("\n")
-    );
+fn render_map(map: &HashSet<Point>, rect: &Rect) -> Frame {
+    Frame::from_bounds(rect.x_bounds, rect.y_bounds, Orientation::YUp, |point| {
+        if map.contains(point) {
+            Cell::new('#', Rgb::GREEN)
+        } else {
+            Cell::empty()
+        }
+    })
+}
+
+// The elves spreading out over the rounds part 1 counts
+fn render(starting_map: &HashSet<Point>) -> Animation {
+    let mut maps = vec![starting_map.clone()];
+    for i in 0..10 {
+        let mut map = maps.last().unwrap().clone();
+        did_elf_move_during_round(&mut map, i);
+        maps.push(map);
+    }
+
+    // Every frame covers everywhere the elves went, so they all have the same size
+    let mut rect = find_elf_rect(starting_map);
+    for elf_pos in maps.iter().flatten() {
+        rect.x_bounds.update(elf_pos.x);
+        rect.y_bounds.update(elf_pos.y);
+    }
+
+    let mut animation = Animation::new(Duration::from_millis(500));
+    for map in &maps {
+        animation.push(render_map(map, &rect));
+    }
+    animation
 }
 
-fn part1(input: &str) -> u128 {
-    let mut map = parse_input(input);
+fn part1(starting_map: &HashSet<Point>) -> u128 {
+    let mut map = starting_map.clone();
 
     for i in 0..10 {
         did_elf_move_during_round(&mut map, i);
@@ -228,8 +233,8 @@ fn part1(input: &str) -> u128 {
     ).sum()
 }
 
-fn part2(input: &str) -> u128 {
-    let mut map = parse_input(input);
+fn part2(starting_map: &HashSet<Point>) -> u128 {
+    let mut map = starting_map.clone();
 
     let mut i = 0;
     loop {
@@ -240,11 +245,26 @@ fn part2(input: &str) -> u128 {
     }
 }
 
-pub fn run() {
-    println!("Day 23");
-    let input = read_input(23);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+pub struct Day23;
+
+impl Solution for Day23 {
+    type Input<'a> = HashSet<Point>;
+
+    fn parse(input: &str) -> AocResult<HashSet<Point>> {
+        parse_input(input)
+    }
+
+    fn part1(input: &HashSet<Point>) -> AocResult<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &HashSet<Point>) -> AocResult<Answer> {
+        Ok(part2(input).into())
+    }
+
+    fn render(input: &HashSet<Point>) -> AocResult<Option<Animation>> {
+        Ok(Some(render(input)))
+    }
 }
 
 #[cfg(test)]
@@ -266,11 +286,11 @@ pub mod tests {
 
     #[test]
     pub fn part1() {
-        assert_eq!(110, super::part1(INPUT));
+        assert_eq!(110, super::part1(&super::parse_input(INPUT).unwrap()));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(20, super::part2(INPUT));
+        assert_eq!(20, super::part2(&super::parse_input(INPUT).unwrap()));
     }
 }
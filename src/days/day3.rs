@@ -1,5 +1,7 @@
 use std::collections::HashSet;
-use crate::common::{intersect_to_set, read_input, string_to_set};
+use crate::common::{intersect_to_set, string_to_set};
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Solution};
 use itertools::Itertools;
 
 fn priority(value: &char) -> u32 {
@@ -9,42 +11,71 @@ fn priority(value: &char) -> u32 {
     return 1 + (*value as u32 - 'a' as u32);
 }
 
-fn part1(input: &str) -> u32 {
+fn parse_input(input: &str) -> AocResult<Vec<&str>> {
     input.split("\n")
-        .map(|line| {
+        .enumerate()
+        .map(|(i, line)| {
+            if line.len() % 2 != 0 {
+                return Err(AocError::parse("rucksack has an odd number of items").at_line(i + 1));
+            }
+
+            if let Some(column) = line.chars().position(|c| !c.is_ascii_alphabetic()) {
+                return Err(AocError::parse_at(i + 1, column + 1, "items must be letters"));
+            }
+
+            Ok(line)
+        })
+        .collect()
+}
+
+fn part1(rucksacks: &Vec<&str>) -> AocResult<u32> {
+    rucksacks.iter()
+        .enumerate()
+        .map(|(i, line)| {
             let midpoint = line.len() / 2;
             let a = string_to_set(&line[..midpoint]);
             let b = string_to_set(&line[midpoint..]);
-            let invalid_char = a.intersection(&b).nth(0).expect("No char was in both sides");
-            priority(invalid_char)
+            let invalid_char = a.intersection(&b).nth(0)
+                .ok_or_else(|| AocError::parse("no item is in both compartments").at_line(i + 1))?;
+            Ok(priority(invalid_char))
         })
         .sum()
 }
 
-fn part2(input: &str) -> u32 {
-    input.split("\n")
-        .into_iter()
+fn part2(rucksacks: &Vec<&str>) -> AocResult<u32> {
+    rucksacks.iter()
         .chunks(3)
         .into_iter()
-        .map(|chunk| {
-            let sets: Vec<HashSet<char>> = chunk.map(string_to_set).collect();
-            let a = &sets[0];
-            let b = &sets[1];
-            let c = &sets[2];
+        .enumerate()
+        .map(|(i, chunk)| {
+            let sets: Vec<HashSet<char>> = chunk.map(|line| string_to_set(line)).collect();
+            let [a, b, c] = &sets[..] else {
+                return Err(AocError::parse("expected the rucksacks to come in groups of three").at_line(i * 3 + 1));
+            };
             let common_intersection = intersect_to_set(&intersect_to_set(a, b), c);
-            let common_char = common_intersection.iter().nth(0).expect("No common char");
-            priority(common_char)
+            let common_char = common_intersection.iter().nth(0)
+                .ok_or_else(|| AocError::parse("no item is in all three rucksacks of the group").at_line(i * 3 + 1))?;
+            Ok(priority(common_char))
         })
         .sum()
 }
 
-pub fn run() {
-    println!("Day 3");
+pub struct Day3;
+
+impl Solution for Day3 {
+    type Input<'a> = Vec<&'a str>;
+
+    fn parse(input: &str) -> AocResult<Vec<&str>> {
+        parse_input(input)
+    }
 
-    let input = read_input(3);
+    fn part1(input: &Vec<&str>) -> AocResult<Answer> {
+        part1(input).map(Answer::from)
+    }
 
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+    fn part2(input: &Vec<&str>) -> AocResult<Answer> {
+        part2(input).map(Answer::from)
+    }
 }
 
 #[cfg(test)]
@@ -60,11 +91,13 @@ CrZsJsPPZsGzwwsLwLmpwMDw";
 
     #[test]
     pub fn part1() {
-        assert_eq!(157, super::part1(INPUT));
+        assert_eq!(Ok(157), super::part1(&parse_input(INPUT).unwrap()));
+        assert_eq!(Err(AocError::parse("no item is in both compartments").at_line(2)), super::part1(&vec!["aa", "ab"]));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(70, super::part2(INPUT));
+        assert_eq!(Ok(70), super::part2(&parse_input(INPUT).unwrap()));
+        assert_eq!(Err(AocError::parse("expected the rucksacks to come in groups of three").at_line(1)), super::part2(&vec!["aa", "aa"]));
     }
 }
\ No newline at end of file
@@ -1,67 +1,37 @@
-use std::fmt::{Formatter};
-use crate::common;
+use crate::common::interval::Interval;
+use crate::common::parse_number_in;
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Solution};
 
-#[derive(Clone, Debug)]
-struct Range {
-    start: i32,
-    end: i32,
-}
-
-impl Range {
-    fn new(start: i32, end: i32) -> Range {
-        Range {
-            start,
-            end,
-        }
-    }
-
-    pub fn contains(&self, other: &Range) -> bool {
-        self.start <= other.start && self.end >= other.end
-    }
+type Range = Interval<i32>;
 
-    pub fn overlaps(&self, other: &Range) -> bool {
-        (other.start >= self.start && other.start <= self.end)
-            || (other.end >= self.start && other.end <= self.end)
-            || (self.start >= other.start && self.start <= other.end)
-            || (self.end >= other.start && self.end <= other.end)
-    }
-}
-
-impl std::fmt::Display for Range {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}-{}", self.start, self.end)
-    }
+pub struct Day4 {
+    pairs: Vec<(Range, Range)>,
 }
 
-struct Day4 {
-    pairs: Vec<(Range, Range)>,
+fn parse_range(input: &str, range_str: &str) -> AocResult<Range> {
+    let (start, end) = range_str.split_once("-")
+        .ok_or_else(|| AocError::parse_in(input, range_str, format!("expected `<start>-<end>`, got `{}`", range_str)))?;
+    let (start, end) = (parse_number_in(input, start)?, parse_number_in(input, end)?);
+    Range::new(start, end).ok_or_else(|| AocError::parse_in(input, range_str, format!("range `{}` ends before it starts", range_str)))
 }
 
-fn parse_input(input: &str) -> Vec<(Range, Range)> {
+fn parse_input(input: &str) -> AocResult<Vec<(Range, Range)>> {
     input
         .split("\n")
         .map(|line| {
-            let pair_ranges = line
-                .split(",")
-                .map(|range_str| {
-                    let range_values: Vec<i32> = range_str
-                        .split("-")
-                        .map(|value| value.parse::<i32>().expect("Could not parse range value"))
-                        .collect();
-
-                    Range::new(range_values[0], range_values[1])
-                })
-                .collect::<Vec<Range>>();
-            (pair_ranges[0].clone(), pair_ranges[1].clone())
+            let (first, second) = line.split_once(",")
+                .ok_or_else(|| AocError::parse_in(input, line, "expected two ranges separated by `,`"))?;
+            Ok((parse_range(input, first)?, parse_range(input, second)?))
         })
         .collect()
 }
 
 impl Day4 {
-    fn new(input: &str) -> Day4 {
-        Day4 {
-            pairs: parse_input(input)
-        }
+    fn new(input: &str) -> AocResult<Day4> {
+        Ok(Day4 {
+            pairs: parse_input(input)?
+        })
     }
 
     pub fn part1(&self) -> usize {
@@ -73,12 +43,20 @@ impl Day4 {
     }
 }
 
-pub fn run() {
-    println!("Day 4");
-    let input = common::read_input(4);
-    let day = Day4::new(input.as_str());
-    println!("Part 1: {}", day.part1());
-    println!("Part 2: {}", day.part2());
+impl Solution for Day4 {
+    type Input<'a> = Day4;
+
+    fn parse(input: &str) -> AocResult<Day4> {
+        Day4::new(input)
+    }
+
+    fn part1(input: &Day4) -> AocResult<Answer> {
+        Ok(input.part1().into())
+    }
+
+    fn part2(input: &Day4) -> AocResult<Answer> {
+        Ok(input.part2().into())
+    }
 }
 
 #[cfg(test)]
@@ -94,7 +72,7 @@ pub mod test {
 6-6,4-6
 2-6,4-8";
 
-        let day = Day4::new(input);
+        let day = Day4::new(input).unwrap();
 
         assert_eq!(2, day.part1());
     }
@@ -108,7 +86,7 @@ pub mod test {
 6-6,4-6
 2-6,4-8";
 
-        let day = Day4::new(input);
+        let day = Day4::new(input).unwrap();
 
         assert_eq!(4, day.part2());
     }
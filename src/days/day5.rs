@@ -1,6 +1,7 @@
 use itertools::Itertools;
-use crate::common::read_input;
-use regex::Regex;
+use crate::common::parse;
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Solution};
 
 struct MoveInstruction {
     count: u32,
@@ -10,94 +11,117 @@ struct MoveInstruction {
 
 type CrateStack = Vec<char>;
 
-fn parse_input(input: &str) -> (Vec<CrateStack>, Vec<MoveInstruction>) {
-    let move_instruction_regex: Regex = Regex::new(r"move (\d+) from (\d+) to (\d+)").expect("Could not create regex");
-    let column_regex: Regex = Regex::new(r"\b\d+\b").expect("Could not create regex");
-
-    let mut crates: Vec<CrateStack> = vec![];
-    let mut instructions: Vec<MoveInstruction> = vec![];
-
-    let mut parts: Vec<Vec<&str>> = input.split("\n\n").map(|part| part.split("\n").collect::<Vec<&str>>()).collect();
-    let instructions_part: Vec<&str> = parts.pop().expect("Instructions part should be here");
-    let mut crates_part: Vec<&str> = parts.pop().expect("Crates part should be here");
-
-    let column_spots: &str = crates_part.pop().expect("No last spot");
-    let column_count = column_regex.find_iter(column_spots).count();
+pub struct Procedure {
+    crates: Vec<CrateStack>,
+    instructions: Vec<MoveInstruction>,
+}
 
-    for _ in 0..column_count {
-        crates.push(vec![]);
+fn parse_input(input: &str) -> AocResult<Procedure> {
+    let (crates_str, instructions_str) = parse::split_pair(input, input, "\n\n")
+        .map_err(|_| AocError::parse("expected a blank line between the crates and the instructions"))?;
+    let mut crates_part: Vec<&str> = parse::lines(crates_str).collect();
+
+    // Each stack's crates sit in the same character column as its number in the last row
+    let column_spots: &str = crates_part.pop().expect("split always returns at least one item");
+    let column_char_indices: Vec<usize> = column_spots.char_indices()
+        .filter(|&(i, c)| c.is_ascii_digit() && !column_spots[..i].ends_with(|before: char| before.is_ascii_digit()))
+        .map(|(i, _)| i)
+        .collect();
+    let column_count = column_char_indices.len();
+    if column_count == 0 {
+        return Err(AocError::parse_in(input, column_spots, "expected a row of column numbers below the crates"));
     }
 
+    let mut crates: Vec<CrateStack> = vec![vec![]; column_count];
     crates_part.reverse();
     for crate_line in crates_part {
-        for column in 0..column_count {
-            let column_char_index = (column * 4) + 1;
-            let column_char_opt = crate_line.chars().nth(column_char_index);
-            match column_char_opt {
-                None => continue,
-                Some(column_char) => {
-                    if column_char != ' ' {
-                        crates[column].push(column_char);
-                    }
+        for (column, &column_char_index) in column_char_indices.iter().enumerate() {
+            if let Some(column_char) = crate_line.chars().nth(column_char_index) {
+                if column_char != ' ' {
+                    crates[column].push(column_char);
                 }
             }
         }
     }
 
-    for instruction_line in instructions_part {
-        let captures = move_instruction_regex.captures(instruction_line).expect("No capture on instruction");
-        let count: u32 = captures.get(1).expect("No capture").as_str().parse::<u32>().expect("Not a number");
-        let source: u32 = captures.get(2).expect("No capture").as_str().parse::<u32>().expect("Not a number") - 1;
-        let dest: u32 = captures.get(3).expect("No capture").as_str().parse::<u32>().expect("Not a number") - 1;
-
+    let mut instructions: Vec<MoveInstruction> = vec![];
+    for instruction_line in parse::lines(instructions_str) {
+        let [count, source, dest] = parse::fields(input, instruction_line, "move {} from {} to {}")?;
+        let count: u32 = parse::integer(input, count)?;
+        let mut stack_indices: Vec<u32> = vec![];
+        for stack_str in [source, dest] {
+            let stack_number: u32 = parse::integer(input, stack_str)?;
+            if stack_number == 0 || stack_number as usize > column_count {
+                return Err(AocError::parse_in(input, stack_str, format!("stack {} does not exist", stack_number)));
+            }
+            stack_indices.push(stack_number - 1);
+        }
         instructions.push(MoveInstruction {
             count,
-            source,
-            dest,
+            source: stack_indices[0],
+            dest: stack_indices[1],
         });
     }
 
-    (crates, instructions)
+    Ok(Procedure {
+        crates,
+        instructions,
+    })
+}
+
+// The top crate of the instruction's source stack, taken off it
+fn take_crate(crates: &mut [CrateStack], instruction_index: usize, instruction: &MoveInstruction) -> AocResult<char> {
+    crates[instruction.source as usize].pop().ok_or_else(|| AocError::parse(format!(
+        "instruction {} moves a crate from stack {}, which is empty by then", instruction_index + 1, instruction.source + 1)))
 }
 
-fn part1(input: &str) -> String {
-    let (mut crates, mut instructions) = parse_input(input);
+fn part1(procedure: &Procedure) -> AocResult<String> {
+    let mut crates = procedure.crates.clone();
 
-    for instruction in instructions {
+    for (i, instruction) in procedure.instructions.iter().enumerate() {
         for _ in 0..instruction.count {
-            let source_item = crates[instruction.source as usize].pop().expect("No source crate");
+            let source_item = take_crate(&mut crates, i, instruction)?;
             crates[instruction.dest as usize].push(source_item);
         }
     }
 
-    crates.iter().filter_map(|stack| stack.last()).join("")
+    Ok(crates.iter().filter_map(|stack| stack.last()).join(""))
 }
 
-fn part2(input: &str) -> String {
-    let (mut crates, mut instructions) = parse_input(input);
+fn part2(procedure: &Procedure) -> AocResult<String> {
+    let mut crates = procedure.crates.clone();
 
-    for instruction in instructions {
+    for (i, instruction) in procedure.instructions.iter().enumerate() {
         let mut source_items: CrateStack = vec![];
 
         for _ in 0..instruction.count {
-            let source_item = crates[instruction.source as usize].pop().expect("No source crate");
-            source_items.push(source_item);
+            source_items.push(take_crate(&mut crates, i, instruction)?);
         }
 
-        for _ in 0..instruction.count {
-            let source_item = source_items.pop().expect("No source item");
-            crates[instruction.dest as usize].push(source_item);
-        }
+        // Taken off top first, so they go back on in the opposite order
+        source_items.reverse();
+        crates[instruction.dest as usize].extend(source_items);
     }
 
-    crates.iter().filter_map(|stack| stack.last()).join("")
+    Ok(crates.iter().filter_map(|stack| stack.last()).join(""))
 }
 
-pub fn run() {
-    println!("Day 5");
-    let input = read_input(5);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+pub struct Day5;
+
+impl Solution for Day5 {
+    type Input<'a> = Procedure;
+
+    fn parse(input: &str) -> AocResult<Procedure> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Procedure) -> AocResult<Answer> {
+        part1(input).map(Answer::from)
+    }
+
+    fn part2(input: &Procedure) -> AocResult<Answer> {
+        part2(input).map(Answer::from)
+    }
 }
 
 #[cfg(test)]
@@ -116,11 +140,23 @@ move 1 from 1 to 2";
 
     #[test]
     pub fn part1() {
-        assert_eq!("CMZ", super::part1(INPUT));
+        assert_eq!(Ok("CMZ".to_string()), super::part1(&parse_input(INPUT).unwrap()));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!("MCD", super::part2(INPUT));
+        assert_eq!(Ok("MCD".to_string()), super::part2(&parse_input(INPUT).unwrap()));
+    }
+
+    #[test]
+    pub fn invalid_procedure() {
+        assert_eq!(
+            Some(AocError::parse_at(5, 13, "stack 4 does not exist")),
+            parse_input("    [D]\n[N] [C]\n 1   2\n\nmove 1 from 4 to 1").err()
+        );
+        let procedure = parse_input("    [D]\n[N] [C]\n 1   2\n\nmove 1 from 2 to 1\nmove 2 from 2 to 1").unwrap();
+        let error = Err(AocError::parse("instruction 2 moves a crate from stack 2, which is empty by then"));
+        assert_eq!(error, super::part1(&procedure));
+        assert_eq!(error, super::part2(&procedure));
     }
-}
\ No newline at end of file
+}
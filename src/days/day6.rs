@@ -1,5 +1,6 @@
 use itertools::Itertools;
-use crate::common::read_input;
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Solution};
 
 const START_OF_PACKET_SIZE: usize = 4;
 const START_OF_MESSAGE_SIZE: usize = 14;
@@ -13,19 +14,37 @@ fn find_start_packet(input: &str, packet_size: usize) -> Option<usize> {
     return None;
 }
 
-fn part1(input: &str) -> i128 {
-    find_start_packet(input, START_OF_PACKET_SIZE).expect("Start packet not found") as i128
+// Where the first `packet_size` different characters in a row end
+fn find_marker(input: &str, packet_size: usize) -> AocResult<usize> {
+    find_start_packet(input, packet_size)
+        .ok_or_else(|| AocError::parse(format!("expected {} different characters in a row", packet_size)))
 }
 
-fn part2(input: &str) -> i128 {
-    find_start_packet(input, START_OF_MESSAGE_SIZE).expect("Start packet not found") as i128
+fn part1(input: &str) -> AocResult<usize> {
+    find_marker(input, START_OF_PACKET_SIZE)
 }
 
-pub fn run() {
-    println!("Day 6");
-    let input = read_input(6);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+fn part2(input: &str) -> AocResult<usize> {
+    find_marker(input, START_OF_MESSAGE_SIZE)
+}
+
+pub struct Day6;
+
+impl Solution for Day6 {
+    // The datastream is already in the shape we want
+    type Input<'a> = &'a str;
+
+    fn parse(input: &str) -> AocResult<&str> {
+        Ok(input)
+    }
+
+    fn part1(input: &&str) -> AocResult<Answer> {
+        part1(input).map(Answer::from)
+    }
+
+    fn part2(input: &&str) -> AocResult<Answer> {
+        part2(input).map(Answer::from)
+    }
 }
 
 #[cfg(test)]
@@ -34,19 +53,20 @@ pub mod tests {
 
     #[test]
     pub fn part1() {
-        assert_eq!(7, super::part1("mjqjpqmgbljsphdztnvjfqwrcgsmlb"));
-        assert_eq!(5, super::part1("bvwbjplbgvbhsrlpgdmjqwftvncz"));
-        assert_eq!(6, super::part1("nppdvjthqldpwncqszvftbrmjlhg"));
-        assert_eq!(10, super::part1("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"));
-        assert_eq!(11, super::part1("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"));
+        assert_eq!(Ok(7), super::part1("mjqjpqmgbljsphdztnvjfqwrcgsmlb"));
+        assert_eq!(Ok(5), super::part1("bvwbjplbgvbhsrlpgdmjqwftvncz"));
+        assert_eq!(Ok(6), super::part1("nppdvjthqldpwncqszvftbrmjlhg"));
+        assert_eq!(Ok(10), super::part1("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"));
+        assert_eq!(Ok(11), super::part1("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"));
+        assert_eq!(Err(AocError::parse("expected 4 different characters in a row")), super::part1("abcabcc"));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(19, super::part2("mjqjpqmgbljsphdztnvjfqwrcgsmlb"));
-        assert_eq!(23, super::part2("bvwbjplbgvbhsrlpgdmjqwftvncz"));
-        assert_eq!(23, super::part2("nppdvjthqldpwncqszvftbrmjlhg"));
-        assert_eq!(29, super::part2("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"));
-        assert_eq!(26, super::part2("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"));
+        assert_eq!(Ok(19), super::part2("mjqjpqmgbljsphdztnvjfqwrcgsmlb"));
+        assert_eq!(Ok(23), super::part2("bvwbjplbgvbhsrlpgdmjqwftvncz"));
+        assert_eq!(Ok(23), super::part2("nppdvjthqldpwncqszvftbrmjlhg"));
+        assert_eq!(Ok(29), super::part2("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"));
+        assert_eq!(Ok(26), super::part2("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"));
     }
 }
@@ -1,11 +1,21 @@
-use crate::common::read_input;
-
-fn parse_forest(input: &str) -> Vec<Vec<u32>> {
-    input.split("\n")
-        .map(|line| line.chars()
-            .map(|c| c as u32 - '0' as u32)
-            .collect::<Vec<u32>>()
-        ).collect()
+use crate::common::movement::Point2;
+use crate::error::{AocError, AocResult};
+use crate::solution::{Answer, Solution};
+
+fn parse_forest(input: &str) -> AocResult<Vec<Vec<u32>>> {
+    let forest = input.split("\n")
+        .enumerate()
+        .map(|(y, line)| line.chars()
+            .enumerate()
+            .map(|(x, c)| c.to_digit(10).ok_or_else(|| AocError::parse_at(y + 1, x + 1, format!("expected a tree height, got `{}`", c))))
+            .collect::<AocResult<Vec<u32>>>()
+        ).collect::<AocResult<Vec<Vec<u32>>>>()?;
+
+    if let Some(y) = forest.iter().position(|row| row.len() != forest[0].len()) {
+        return Err(AocError::parse("every row of the forest must be the same length").at_line(y + 1));
+    }
+
+    Ok(forest)
 }
 
 fn is_visible(forest: &Vec<Vec<u32>>, tree_x: usize, tree_y: usize) -> bool {
@@ -20,17 +30,13 @@ fn is_visible(forest: &Vec<Vec<u32>>, tree_x: usize, tree_y: usize) -> bool {
         || (tree_y + 1..forest.len()).all(|y| forest[y][tree_x] < tree_height)
 }
 
-fn part1(input: &str) -> usize {
-    let forest = parse_forest(input);
+fn part1(forest: &Vec<Vec<u32>>) -> usize {
     (0..forest.len())
-        .map(|y| (0..forest[y].len()).filter(|x| is_visible(&forest, x.clone(), y)).count())
+        .map(|y| (0..forest[y].len()).filter(|x| is_visible(forest, x.clone(), y)).count())
         .sum()
 }
 
-struct Point {
-    x: usize,
-    y: usize,
-}
+type Point = Point2<usize>;
 
 fn count_visible_trees<I>(forest: &Vec<Vec<u32>>, tree_position: &Point, trees_to_check: I) -> usize
     where I: Iterator<Item=Point>
@@ -62,20 +68,29 @@ fn scenic_score(forest: &Vec<Vec<u32>>, tree_position: Point) -> usize {
     return left * right * top * bottom;
 }
 
-fn part2(input: &str) -> usize {
-    let forest = parse_forest(input);
-
+fn part2(forest: &Vec<Vec<u32>>) -> AocResult<usize> {
     (0..forest.len())
-        .map(|y| (0..forest[y].len()).map(|x| scenic_score(&forest, Point { x, y })).max().expect("No max found"))
+        .flat_map(|y| (0..forest[y].len()).map(move |x| scenic_score(forest, Point { x, y })))
         .max()
-        .expect("No max found")
+        .ok_or_else(|| AocError::parse("expected at least one tree"))
 }
 
-pub fn run() {
-    println!("Day 8");
-    let input = read_input(8);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+pub struct Day8;
+
+impl Solution for Day8 {
+    type Input<'a> = Vec<Vec<u32>>;
+
+    fn parse(input: &str) -> AocResult<Vec<Vec<u32>>> {
+        parse_forest(input)
+    }
+
+    fn part1(input: &Vec<Vec<u32>>) -> AocResult<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Vec<Vec<u32>>) -> AocResult<Answer> {
+        part2(input).map(Answer::from)
+    }
 }
 
 #[cfg(test)]
@@ -90,11 +105,12 @@ pub mod tests {
 
     #[test]
     pub fn part1() {
-        assert_eq!(21, super::part1(INPUT));
+        assert_eq!(21, super::part1(&parse_forest(INPUT).unwrap()));
     }
 
     #[test]
     pub fn part2() {
-        assert_eq!(8, super::part2(INPUT));
+        assert_eq!(Ok(8), super::part2(&parse_forest(INPUT).unwrap()));
+        assert_eq!(Err(AocError::parse("expected at least one tree")), super::part2(&parse_forest("").unwrap()));
     }
 }
@@ -1,60 +1,58 @@
 use std::cmp::{max, min};
 use std::collections::HashSet;
-use itertools::Itertools;
-use crate::common::read_input;
-use crate::common::movement::{GridDirection, Point};
+use crate::solution::{Answer, Solution};
+use crate::common::movement::{Bounds, GridDirection, Orientation, Point};
+use crate::common::render::{Animation, Cell, Frame, Rgb};
+use crate::common::parse_number_in;
+use crate::error::{AocError, AocResult};
 
-struct MoveInstruction {
+pub struct MoveInstruction {
     direction: GridDirection,
     count: usize,
 }
 
-fn parse_instructions(input: &str) -> Vec<MoveInstruction> {
+fn parse_instructions(input: &str) -> AocResult<Vec<MoveInstruction>> {
     input.split("\n")
         .map(|line| {
-            let (direction, count) = line.split_once(" ").expect("Could not split");
-            MoveInstruction {
+            let (direction, count) = line.split_once(" ")
+                .ok_or_else(|| AocError::parse_in(input, line, "expected `<direction> <count>`"))?;
+            Ok(MoveInstruction {
                 direction: match direction {
                     "R" => GridDirection::Right,
                     "L" => GridDirection::Left,
                     "U" => GridDirection::Up,
                     "D" => GridDirection::Down,
-                    _ => panic!("Illegal direction {}", direction)
+                    _ => return Err(AocError::parse_in(input, direction, format!("illegal direction `{}`", direction)))
                 },
-                count: count.parse().expect("Could not parse count"),
-            }
+                count: parse_number_in(input, count)?,
+            })
         })
         .collect()
 }
 
-fn render_grid(visited_positions: &HashSet<Point>, rope: &Vec<Point>) -> String {
-    let mut min_x = 0;
-    let mut max_x = 0;
-    let mut min_y = 0;
-    let mut max_y = 0;
+fn render_grid(visited_positions: &HashSet<Point>, rope: &Vec<Point>) -> Frame {
+    let mut x_bounds = Bounds::new(0, 0);
+    let mut y_bounds = Bounds::new(0, 0);
     for position in visited_positions {
-        min_x = min(min_x, position.x);
-        max_x = max(max_x, position.x);
-        min_y = min(min_y, position.y);
-        max_y = max(max_y, position.y);
+        x_bounds.update(position.x);
+        y_bounds.update(position.y);
     }
-    (min_y - 1..=max_y + 1)
-        .map(|y| (min_x - 1..=max_x + 1)
-            .map(|x| -> String {
-                let current = Point { x, y};
-                if rope.iter().all(|point| current == *point) {
-                    "+".to_string()
-                } else if let Some(position) = rope.iter().position(|point| current == *point) {
-                    (rope.len() - position).to_string()
-                } else if current == Point::zero() {
-                    "s".to_string()
-                } else if visited_positions.contains(&Point { x, y }) {
-                    "#".to_string()
-                } else {
-                    ".".to_string()
-                }
-            }).join("")
-        ).join("\n")
+    let x_bounds = Bounds::new(min(x_bounds.min, 0) - 1, max(x_bounds.max, 0) + 1);
+    let y_bounds = Bounds::new(min(y_bounds.min, 0) - 1, max(y_bounds.max, 0) + 1);
+
+    Frame::from_bounds(x_bounds, y_bounds, Orientation::YDown, |&current| {
+        if rope.iter().all(|point| current == *point) {
+            Cell::new('+', Rgb::RED)
+        } else if let Some(position) = rope.iter().position(|point| current == *point) {
+            Cell::new(char::from_digit((rope.len() - position) as u32, 36).unwrap_or('?'), Rgb::YELLOW)
+        } else if current == Point::zero() {
+            Cell::new('s', Rgb::BLUE)
+        } else if visited_positions.contains(&current) {
+            Cell::new('#', Rgb::GREEN)
+        } else {
+            Cell::empty()
+        }
+    })
 }
 
 fn move_knots(remaining_rope: &mut [Point]) {
@@ -65,24 +63,18 @@ fn move_knots(remaining_rope: &mut [Point]) {
     let head = remaining_rope[0];
     let knot = &mut remaining_rope[1];
 
-    // println!("Moving knot at {:?} relative to head {:?}", knot, head);
-
-    let distance = head.subtract(knot).magnitude();
+    let distance = (head - *knot).magnitude();
     if distance < 2.into() {
-        // println!("Distance is not far enough ({})", distance);
         return
     }
 
-    // println!("Moving knot because it is too far, distance: {}", distance);
     if head.y == knot.y {
-        // println!("x axis is shared, moving along x-axis");
         if head.x > knot.x {
             knot.x += 1;
         } else {
             knot.x -= 1;
         }
     } else if head.x == knot.x {
-        // println!("y axis is shared, moving along y-axis");
         if head.y > knot.y {
             knot.y += 1;
         } else {
@@ -106,28 +98,16 @@ fn move_knots(remaining_rope: &mut [Point]) {
 }
 
 fn move_head(rope: &mut Vec<Point>, direction: GridDirection, count: usize, out_positions: &mut HashSet<Point>) {
-    // println!("===");
-    // println!("{:?} {}", direction, count);
-    // println!("Starting positions: head={:?}, tail={:?}", out_rope.head, out_rope.tail);
-    // println!("Starting grid:");
-    // println!("\n{}\n", render_grid(out_positions, &rope));
-
     for _ in 0..count {
-        // println!("Moving");
-        // println!("Current positions before move: head={:?}, tail={:?}", out_rope.head, out_rope.tail);
         rope[0].move_in_dir(direction.clone());
-        // println!("Current positions after move: head={:?}, tail={:?}", out_rope.head, out_rope.tail);
 
         move_knots(rope);
         out_positions.insert(rope.last().expect("No last point").clone());
-
-        // println!("\n{}\n", render_grid(out_positions, &rope));
     }
 }
 
-fn simulate_rope(input: &str, rope_size: usize) -> usize {
-    let instructions = parse_instructions(input);
-
+// Where the tail has been, and where the rope ends up
+fn simulate_rope(instructions: &Vec<MoveInstruction>, rope_size: usize) -> (HashSet<Point>, Vec<Point>) {
     let mut rope = vec![Point::zero(); rope_size];
 
     let mut visited_positions: HashSet<Point> = HashSet::new();
@@ -137,22 +117,39 @@ fn simulate_rope(input: &str, rope_size: usize) -> usize {
         move_head(&mut rope, instruction.direction, instruction.count, &mut visited_positions);
     }
 
-    visited_positions.len()
+    (visited_positions, rope)
 }
 
-fn part1(input: &str) -> usize {
-    simulate_rope(input, 2)
+fn part1(instructions: &Vec<MoveInstruction>) -> usize {
+    simulate_rope(instructions, 2).0.len()
 }
 
-fn part2(input: &str) -> usize {
-    simulate_rope(input, 10)
+fn part2(instructions: &Vec<MoveInstruction>) -> usize {
+    simulate_rope(instructions, 10).0.len()
 }
 
-pub fn run() {
-    println!("Day 9");
-    let input = read_input(9);
-    println!("Part 1: {}", part1(input.as_str()));
-    println!("Part 2: {}", part2(input.as_str()));
+pub struct Day9;
+
+impl Solution for Day9 {
+    type Input<'a> = Vec<MoveInstruction>;
+
+    fn parse(input: &str) -> AocResult<Vec<MoveInstruction>> {
+        parse_instructions(input)
+    }
+
+    fn part1(input: &Vec<MoveInstruction>) -> AocResult<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Vec<MoveInstruction>) -> AocResult<Answer> {
+        Ok(part2(input).into())
+    }
+
+    // Everywhere the tail of the long rope went
+    fn render(input: &Vec<MoveInstruction>) -> AocResult<Option<Animation>> {
+        let (visited_positions, rope) = simulate_rope(input, 10);
+        Ok(Some(Animation::still(render_grid(&visited_positions, &rope))))
+    }
 }
 
 #[cfg(test)]
@@ -169,7 +166,7 @@ R 4
 D 1
 L 5
 R 2";
-        assert_eq!(13, super::part1(INPUT));
+        assert_eq!(13, super::part1(&parse_instructions(INPUT).unwrap()));
     }
 
     #[test]
@@ -182,6 +179,6 @@ R 17
 D 10
 L 25
 U 20";
-        assert_eq!(36, super::part2(INPUT));
+        assert_eq!(36, super::part2(&parse_instructions(INPUT).unwrap()));
     }
 }
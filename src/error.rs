@@ -0,0 +1,132 @@
+use std::fmt;
+use std::fmt::Formatter;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AocError {
+    // Lines and columns are 1-based so they match what an editor shows
+    Parse { line: Option<usize>, column: Option<usize>, message: String },
+    Io { path: String, message: String },
+    Fetch { url: String, message: String },
+    // A solver's numbers went out of range or couldn't be divided
+    Arithmetic { message: String },
+    // A solver panicked, and the panic was caught so the other days could carry on
+    Panic { message: String },
+}
+
+pub type AocResult<T> = Result<T, AocError>;
+
+impl AocError {
+    pub fn parse<S: Into<String>>(message: S) -> AocError {
+        AocError::Parse {
+            line: None,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn parse_at<S: Into<String>>(line: usize, column: usize, message: S) -> AocError {
+        AocError::Parse {
+            line: Some(line),
+            column: Some(column),
+            message: message.into(),
+        }
+    }
+
+    // Locates `fragment`, which must be a slice of `source`, so block-based parsers don't need to count lines
+    pub fn parse_in<S: Into<String>>(source: &str, fragment: &str, message: S) -> AocError {
+        let source_start = source.as_ptr() as usize;
+        let fragment_start = fragment.as_ptr() as usize;
+        if fragment_start < source_start || fragment_start > source_start + source.len() {
+            return AocError::parse(message);
+        }
+
+        let before_fragment = &source[..fragment_start - source_start];
+        let line = before_fragment.matches('\n').count() + 1;
+        let column = before_fragment.len() - before_fragment.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+        AocError::parse_at(line, column, message)
+    }
+
+    pub fn io<S: Into<String>>(path: S, message: S) -> AocError {
+        AocError::Io {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn fetch<S: Into<String>>(url: S, message: S) -> AocError {
+        AocError::Fetch {
+            url: url.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn arithmetic<S: Into<String>>(message: S) -> AocError {
+        AocError::Arithmetic {
+            message: message.into(),
+        }
+    }
+
+    pub fn panic<S: Into<String>>(message: S) -> AocError {
+        AocError::Panic {
+            message: message.into(),
+        }
+    }
+
+    // Parsers for a single line don't know where that line is, so the caller fills it in
+    pub fn at_line(self, line_number: usize) -> AocError {
+        match self {
+            AocError::Parse { line: None, column, message } => AocError::Parse {
+                line: Some(line_number),
+                column,
+                message,
+            },
+            other => other
+        }
+    }
+
+    // Like `at_line`, for parsers that know the column too
+    pub fn at(self, line_number: usize, column_number: usize) -> AocError {
+        match self {
+            AocError::Parse { line: None, column: None, message } => AocError::parse_at(line_number, column_number, message),
+            other => other.at_line(line_number)
+        }
+    }
+
+    pub fn has_position(&self) -> bool {
+        matches!(self, AocError::Parse { line: Some(_), .. })
+    }
+}
+
+impl fmt::Display for AocError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AocError::Parse { line: Some(line), column: Some(column), message } => write!(f, "line {}, column {}: {}", line, column, message),
+            AocError::Parse { line: Some(line), column: None, message } => write!(f, "line {}: {}", line, message),
+            AocError::Parse { line: None, message, .. } => write!(f, "{}", message),
+            AocError::Io { path, message } => write!(f, "could not read {}: {}", path, message),
+            AocError::Fetch { url, message } => write!(f, "could not download {}: {}", url, message),
+            AocError::Arithmetic { message } => write!(f, "arithmetic error: {}", message),
+            AocError::Panic { message } => write!(f, "panicked: {}", message)
+        }
+    }
+}
+
+impl std::error::Error for AocError {}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn parse_in_finds_position() {
+        let source = "first line\nsecond line";
+        let fragment = &source[18..];
+        assert_eq!(AocError::parse_at(2, 8, "bad"), AocError::parse_in(source, fragment, "bad"));
+    }
+
+    #[test]
+    pub fn display() {
+        assert_eq!("line 12: expected `tunnels lead to valves`", AocError::parse("expected `tunnels lead to valves`").at_line(12).to_string());
+        assert_eq!("line 3, column 5: oops", AocError::parse_at(3, 5, "oops").to_string());
+    }
+}
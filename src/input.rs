@@ -0,0 +1,210 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::common::read_input_file;
+use crate::error::{AocError, AocResult};
+
+const YEAR: u32 = 2022;
+const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+const DEFAULT_CACHE_DIR: &str = "input";
+// Be gentle with the puzzle server, even when several days are missing at once
+const DEFAULT_REQUEST_INTERVAL: Duration = Duration::from_secs(3);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const USER_AGENT: &str = concat!("advent-2022/", env!("CARGO_PKG_VERSION"));
+
+pub const BASE_URL_VAR: &str = "AOC_BASE_URL";
+pub const SESSION_VAR: &str = "AOC_SESSION";
+
+// Finds the puzzle input for a day, reading it from the cache directory if it has been seen before and otherwise
+// downloading it and saving it there. Empty files (like the ones `new-day` creates) count as missing.
+pub struct InputProvider {
+    cache_dir: PathBuf,
+    base_url: String,
+    session: Option<String>,
+    request_interval: Duration,
+    // Shared by every thread that asks for an input, so the interval holds across the whole process
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl InputProvider {
+    pub fn new(cache_dir: PathBuf, base_url: &str, session: Option<String>, request_interval: Duration) -> InputProvider {
+        InputProvider {
+            cache_dir,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            session,
+            request_interval,
+            last_request: Mutex::new(None),
+        }
+    }
+
+    pub fn from_env() -> InputProvider {
+        let base_url = env::var(BASE_URL_VAR).unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
+        let session = env::var(SESSION_VAR).ok().map(|session| session.trim().to_string()).filter(|session| !session.is_empty());
+        InputProvider::new(PathBuf::from(DEFAULT_CACHE_DIR), base_url.as_str(), session, DEFAULT_REQUEST_INTERVAL)
+    }
+
+    pub fn cache_path(&self, day: u32) -> PathBuf {
+        self.cache_dir.join(format!("day{}.txt", day))
+    }
+
+    fn input_url(&self, day: u32) -> String {
+        format!("{}/{}/day/{}/input", self.base_url, YEAR, day)
+    }
+
+    fn wait_for_turn(&self) -> std::sync::MutexGuard<'_, Option<Instant>> {
+        let last_request = self.last_request.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(last_request_time) = *last_request {
+            let next_allowed = last_request_time + self.request_interval;
+            let now = Instant::now();
+            if next_allowed > now {
+                thread::sleep(next_allowed - now);
+            }
+        }
+        last_request
+    }
+
+    fn download(&self, day: u32) -> AocResult<String> {
+        let url = self.input_url(day);
+        let session = self.session.as_ref().ok_or_else(|| AocError::fetch(
+            url.clone(),
+            format!("{} is missing or empty and {} is not set, so it can't be downloaded", self.cache_path(day).display(), SESSION_VAR)
+        ))?;
+
+        // Holding the lock for the whole request keeps concurrent downloads in line too
+        let mut last_request = self.wait_for_turn();
+        let response = ureq::AgentBuilder::new()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(USER_AGENT)
+            .build()
+            .get(url.as_str())
+            .set("Cookie", format!("session={}", session).as_str())
+            .call();
+        *last_request = Some(Instant::now());
+
+        match response {
+            Ok(response) => response.into_string().map_err(|err| AocError::fetch(url, err.to_string())),
+            Err(ureq::Error::Status(404, _)) => Err(AocError::fetch(url, format!("day {} is not available yet", day))),
+            Err(ureq::Error::Status(status @ (400 | 401 | 500), _)) => Err(AocError::fetch(url, format!("server answered {}, is {} still valid?", status, SESSION_VAR))),
+            Err(ureq::Error::Status(status, _)) => Err(AocError::fetch(url, format!("server answered {}", status))),
+            Err(err) => Err(AocError::fetch(url, err.to_string()))
+        }
+    }
+
+    pub fn load(&self, day: u32) -> AocResult<String> {
+        let cache_path = self.cache_path(day);
+        if cache_path.exists() {
+            let input = read_input_file(&cache_path)?;
+            if !input.is_empty() {
+                return Ok(input);
+            }
+        }
+
+        let input = self.download(day)?;
+        // Written next to the cache and renamed into place, so an interrupted write never looks like a cached input
+        let temp_path = cache_path.with_extension(format!("txt.{}.tmp", process::id()));
+        fs::create_dir_all(&self.cache_dir)
+            .and_then(|_| fs::write(&temp_path, input.as_str()))
+            .and_then(|_| fs::rename(&temp_path, &cache_path))
+            .map_err(|err| {
+                let _ = fs::remove_file(&temp_path);
+                AocError::io(cache_path.display().to_string(), err.to_string())
+            })?;
+        Ok(input)
+    }
+}
+
+pub fn default_provider() -> &'static InputProvider {
+    static PROVIDER: OnceLock<InputProvider> = OnceLock::new();
+    PROVIDER.get_or_init(InputProvider::from_env)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+    use super::*;
+
+    fn temp_cache_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("advent-2022-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    // Answers `responses` requests in order and hands back the request line and cookie of each
+    fn mock_server(responses: Vec<(u32, &'static str)>) -> (String, JoinHandle<Vec<(String, String)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || responses.into_iter().map(|(status, body)| {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut cookie = String::new();
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some(value) = header.strip_prefix("Cookie: ") {
+                    cookie = value.trim().to_string();
+                }
+            }
+            write!(stream, "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body).unwrap();
+            (request_line.trim().to_string(), cookie)
+        }).collect());
+        (base_url, handle)
+    }
+
+    #[test]
+    pub fn downloads_then_uses_cache() {
+        let cache_dir = temp_cache_dir("download");
+        let (base_url, server) = mock_server(vec![(200, "1\n2\n3\n")]);
+        let provider = InputProvider::new(cache_dir.clone(), base_url.as_str(), Some("secret".to_string()), Duration::ZERO);
+
+        assert_eq!(Ok("1\n2\n3\n".to_string()), provider.load(7));
+        // The server only answers once, so this has to come from the cache
+        assert_eq!(Ok("1\n2\n3\n".to_string()), provider.load(7));
+        assert_eq!(vec![("GET /2022/day/7/input HTTP/1.1".to_string(), "session=secret".to_string())], server.join().unwrap());
+        assert_eq!("1\n2\n3\n", fs::read_to_string(cache_dir.join("day7.txt")).unwrap());
+        // Nothing left behind from writing it
+        assert_eq!(1, fs::read_dir(&cache_dir).unwrap().count());
+        fs::remove_dir_all(cache_dir).unwrap();
+    }
+
+    #[test]
+    pub fn reports_missing_input() {
+        let cache_dir = temp_cache_dir("missing");
+        let without_session = InputProvider::new(cache_dir.clone(), "http://127.0.0.1:1", None, Duration::ZERO);
+        assert!(without_session.load(19).unwrap_err().to_string().contains(SESSION_VAR));
+        fs::create_dir_all(&cache_dir).unwrap();
+        fs::write(cache_dir.join("day19.txt"), "").unwrap();
+        assert!(without_session.load(19).unwrap_err().to_string().contains(SESSION_VAR));
+
+        let (base_url, server) = mock_server(vec![(404, "Not found")]);
+        let provider = InputProvider::new(cache_dir.clone(), base_url.as_str(), Some("secret".to_string()), Duration::ZERO);
+        assert_eq!(Err(AocError::fetch(format!("{}/2022/day/25/input", base_url), "day 25 is not available yet".to_string())), provider.load(25));
+        server.join().unwrap();
+        assert!(!cache_dir.join("day25.txt").exists());
+        fs::remove_dir_all(cache_dir).unwrap();
+    }
+
+    #[test]
+    pub fn spaces_out_requests() {
+        let cache_dir = temp_cache_dir("rate-limit");
+        let (base_url, server) = mock_server(vec![(200, "a"), (200, "b")]);
+        let provider = InputProvider::new(cache_dir.clone(), base_url.as_str(), Some("secret".to_string()), Duration::from_millis(300));
+
+        let start = Instant::now();
+        provider.load(1).unwrap();
+        provider.load(2).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(300));
+        server.join().unwrap();
+        fs::remove_dir_all(cache_dir).unwrap();
+    }
+}
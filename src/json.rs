@@ -0,0 +1,70 @@
+use std::fmt::Write;
+use crate::solution::Answer;
+
+// Just enough JSON to write flat result objects without pulling in a serializer
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Number(i128),
+    String(String),
+}
+
+impl From<&Answer> for JsonValue {
+    fn from(answer: &Answer) -> JsonValue {
+        match answer {
+            Answer::Number(value) => JsonValue::Number(*value),
+            Answer::Text(value) => JsonValue::String(value.clone())
+        }
+    }
+}
+
+fn quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c)
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl JsonValue {
+    pub fn to_json(&self) -> String {
+        match self {
+            JsonValue::Null => "null".to_string(),
+            JsonValue::Number(value) => value.to_string(),
+            JsonValue::String(value) => quote(value)
+        }
+    }
+}
+
+// Fields are written in the given order so the output is stable line to line
+pub fn object(fields: &[(&str, JsonValue)]) -> String {
+    let fields: Vec<String> = fields.iter().map(|(key, value)| format!("{}:{}", quote(key), value.to_json())).collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn writes_objects() {
+        assert_eq!(
+            r##"{"day":10,"answer":"#..\n\"x\"\\","error":null}"##,
+            object(&[
+                ("day", JsonValue::Number(10)),
+                ("answer", JsonValue::String("#..\n\"x\"\\".to_string())),
+                ("error", JsonValue::Null),
+            ])
+        );
+        assert_eq!(r#""\u0001""#, JsonValue::String("\u{1}".to_string()).to_json());
+    }
+}
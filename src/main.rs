@@ -1,31 +1,46 @@
 extern crate core;
 
+use std::env;
+use std::process::exit;
+use crate::cli::Command;
+
+mod alloc_stats;
+mod bench;
+mod cancel;
+mod cli;
 mod days;
 mod common;
+mod error;
+mod input;
+mod json;
+mod runner;
+mod scaffold;
+mod solution;
+mod verify;
 mod vm;
 
 fn main() {
-    days::day1::run();
-    days::day2::run();
-    days::day3::run();
-    days::day4::run();
-    days::day5::run();
-    days::day6::run();
-    days::day8::run();
-    days::day9::run();
-    days::day10::run();
-    // todo: optimize these so I can run every day at once (:
-    // days::day11::run();
-    // days::day12::run();
-    // days::day13::run();
-    // days::day14::run();
-    // days::day15::run();
-    // days::day16::run();
-    // days::day17::run();
-    // days::day18::run();
-    // days::day19::run();
-    // days::day20::run();
-    days::day21::run();
-    days::day22::run();
-    days::day23::run();
+    let command = match cli::parse_args(env::args().skip(1)) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, cli::USAGE);
+            exit(2);
+        }
+    };
+
+    let result = match command {
+        Command::Run(options) => runner::run(&options),
+        Command::Bench(options) => bench::bench(&options),
+        Command::Verify(options) => verify::verify(&options),
+        Command::NewDay(day) => scaffold::new_day(day),
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            Ok(())
+        }
+    };
+
+    if let Err(message) = result {
+        eprintln!("{}", message);
+        exit(1);
+    }
 }
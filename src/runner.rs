@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use crate::alloc_stats::format_bytes;
+use crate::cli::{DaySelection, OutputFormat, RenderTarget, RunOptions};
+use crate::common::{read_input, read_input_file};
+use crate::common::render;
+use crate::common::render::{Animation, Frame};
+use crate::days;
+use crate::days::{Day, DayResult, PartOutcome, PartResult};
+use crate::error::{AocError, AocResult};
+use crate::json;
+use crate::json::JsonValue;
+use crate::solution::{Answer, Part};
+
+// todo: optimize these so I can run every day at once (:
+const SLOW_DAYS: [u32; 10] = [11, 12, 13, 14, 15, 16, 17, 18, 19, 20];
+
+// Pixels per cell in saved pictures
+const RENDER_SCALE: usize = 4;
+
+// What `--render` made of one day, reported after the day's answers
+enum Rendered {
+    Saved(PathBuf, usize),
+    Picture(Frame),
+    Failed(String),
+}
+
+// What `--trace` made of the day: where the table went, how many cycles it has, and where the program stopped
+enum Traced {
+    Saved(PathBuf, usize, Vec<String>),
+    Failed(String),
+}
+
+pub fn select_days(selection: &DaySelection) -> Result<Vec<Day>, String> {
+    match selection {
+        DaySelection::Default => Ok(days::all().into_iter().filter(|day| !SLOW_DAYS.contains(&day.number)).collect()),
+        DaySelection::All => Ok(days::all()),
+        DaySelection::Only(numbers) => numbers.iter()
+            .map(|&number| days::find(number).ok_or_else(|| format!("Day {} is not implemented", number)))
+            .collect()
+    }
+}
+
+pub fn load_input(day: &Day, options: &RunOptions) -> AocResult<String> {
+    match &options.input_path {
+        Some(path) => read_input_file(path),
+        None => read_input(day.number)
+    }
+}
+
+pub fn describe_error(day: &Day, error: &AocError) -> String {
+    if error.has_position() {
+        format!("Day {}, {}", day.number, error)
+    } else {
+        format!("Day {}: {}", day.number, error)
+    }
+}
+
+// Runs `task` for each item on up to `jobs` threads. Results are handed to `on_result` on the calling thread
+// in the same order as `items`, each one as soon as it and everything before it has finished.
+pub fn run_ordered<T, R, F, G>(items: &[T], jobs: usize, task: F, mut on_result: G)
+    where T: Sync, R: Send, F: Fn(&T) -> R + Sync, G: FnMut(&T, R) {
+    if jobs <= 1 {
+        for item in items {
+            on_result(item, task(item));
+        }
+        return;
+    }
+
+    let next_index = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(items.len()) {
+            let sender = sender.clone();
+            let (next_index, task) = (&next_index, &task);
+            scope.spawn(move || loop {
+                let index = next_index.fetch_add(1, Ordering::Relaxed);
+                if index >= items.len() || sender.send((index, task(&items[index]))).is_err() {
+                    break;
+                }
+            });
+        }
+        // Only the workers hold senders now, so the loop below ends once they're all done
+        drop(sender);
+
+        let mut finished: HashMap<usize, R> = HashMap::new();
+        let mut next_to_report = 0;
+        for (index, result) in receiver {
+            finished.insert(index, result);
+            while let Some(result) = finished.remove(&next_to_report) {
+                on_result(&items[next_to_report], result);
+                next_to_report += 1;
+            }
+        }
+    });
+}
+
+fn print_answer(part: Part, answer: &Answer) {
+    let answer_str = answer.to_string();
+    if answer_str.contains('\n') {
+        println!("Part {}:\n{}", part.number(), answer_str);
+    } else {
+        println!("Part {}: {}", part.number(), answer_str);
+    }
+}
+
+fn is_timed_out(part_result: &PartResult) -> bool {
+    part_result.outcome == PartOutcome::TimedOut
+}
+
+fn is_failed(part_result: &PartResult) -> bool {
+    matches!(part_result.outcome, PartOutcome::Failed(_))
+}
+
+fn print_text(day: &Day, result: &AocResult<DayResult>) {
+    println!("Day {}", day.number);
+    match result {
+        Ok(day_result) => {
+            for part_result in &day_result.parts {
+                match &part_result.outcome {
+                    PartOutcome::Solved(answer) => print_answer(part_result.part, answer),
+                    PartOutcome::TimedOut => println!("Part {}: timed out after {:?}", part_result.part.number(), part_result.elapsed),
+                    PartOutcome::Failed(error) => println!("Part {}: {}", part_result.part.number(), error)
+                }
+                if let Some(memory) = part_result.memory {
+                    println!("        peak heap {} over {} allocations", format_bytes(memory.peak_bytes), memory.allocations);
+                }
+            }
+        }
+        Err(error) => eprintln!("{}", describe_error(day, error))
+    }
+}
+
+// One line per part, so scripts can read results as they arrive
+fn print_json(day: &Day, parts: &[Part], result: &AocResult<DayResult>) {
+    let day_number = JsonValue::Number(day.number as i128);
+    match result {
+        Ok(day_result) => {
+            for part_result in &day_result.parts {
+                let (status, answer, error) = match &part_result.outcome {
+                    PartOutcome::Solved(answer) => ("ok", JsonValue::from(answer), JsonValue::Null),
+                    PartOutcome::TimedOut => ("timeout", JsonValue::Null, JsonValue::Null),
+                    PartOutcome::Failed(error) => ("error", JsonValue::Null, JsonValue::String(error.to_string()))
+                };
+                println!("{}", json::object(&[
+                    ("day", day_number.clone()),
+                    ("part", JsonValue::Number(part_result.part.number() as i128)),
+                    ("status", JsonValue::String(status.to_string())),
+                    ("answer", answer),
+                    ("duration_ns", JsonValue::Number(part_result.elapsed.as_nanos() as i128)),
+                    ("error", error),
+                    ("peak_heap_bytes", part_result.memory.map(|memory| JsonValue::Number(memory.peak_bytes as i128)).unwrap_or(JsonValue::Null)),
+                    ("allocations", part_result.memory.map(|memory| JsonValue::Number(memory.allocations as i128)).unwrap_or(JsonValue::Null)),
+                ]));
+            }
+        }
+        // Nothing ran, so every requested part gets the same error
+        Err(error) => {
+            for part in parts {
+                println!("{}", json::object(&[
+                    ("day", day_number.clone()),
+                    ("part", JsonValue::Number(part.number() as i128)),
+                    ("status", JsonValue::String("error".to_string())),
+                    ("answer", JsonValue::Null),
+                    ("duration_ns", JsonValue::Null),
+                    ("error", JsonValue::String(error.to_string())),
+                    ("peak_heap_bytes", JsonValue::Null),
+                    ("allocations", JsonValue::Null),
+                ]));
+            }
+        }
+    }
+}
+
+fn save_animation(day: &Day, animation: &Animation, dir: &Path) -> Result<PathBuf, String> {
+    let (extension, bytes) = animation.encode(RENDER_SCALE)?;
+    fs::create_dir_all(dir).map_err(|err| format!("Could not create {}: {}", dir.display(), err))?;
+    let path = dir.join(format!("day{}.{}", day.number, extension));
+    render::save(&path, &bytes)?;
+    Ok(path)
+}
+
+// None when the day has nothing to draw
+fn render_day(day: &Day, input: &str, target: &RenderTarget) -> Option<Rendered> {
+    let animation = match day.render(input) {
+        Ok(animation) => animation.filter(|animation| !animation.is_empty())?,
+        Err(error) => return Some(Rendered::Failed(describe_error(day, &error)))
+    };
+    Some(match target {
+        RenderTarget::Terminal => Rendered::Picture(animation.last()?.clone()),
+        RenderTarget::Directory(dir) => match save_animation(day, &animation, dir) {
+            Ok(path) => Rendered::Saved(path, animation.len()),
+            Err(message) => Rendered::Failed(format!("Day {}: {}", day.number, message))
+        }
+    })
+}
+
+fn print_rendered(rendered: &Rendered, format: OutputFormat) {
+    match rendered {
+        // Only text output has room for anything but results
+        Rendered::Saved(path, frames) => if format == OutputFormat::Text {
+            println!("Rendered {} frame(s) to {}", frames, path.display());
+        },
+        // Colours only make sense to a terminal
+        Rendered::Picture(frame) => if io::stdout().is_terminal() {
+            println!("{}", frame.to_ansi());
+        } else {
+            println!("{}", frame.to_ascii());
+        },
+        Rendered::Failed(message) => eprintln!("{}", message)
+    }
+}
+
+fn trace_day(day: &Day, input: &str, path: &Path, breakpoints: &[String]) -> Traced {
+    let trace = match day.trace(input, breakpoints) {
+        Ok(Some(trace)) => trace,
+        Ok(None) => return Traced::Failed(format!("Day {}: There's no program to trace, only day 10 runs one", day.number)),
+        Err(error) => return Traced::Failed(describe_error(day, &error))
+    };
+    match fs::write(path, trace.csv) {
+        Ok(()) => Traced::Saved(path.to_path_buf(), trace.cycles, trace.stops),
+        Err(err) => Traced::Failed(format!("Day {}: Could not write {}: {}", day.number, path.display(), err))
+    }
+}
+
+fn print_traced(traced: &Traced, format: OutputFormat) {
+    match traced {
+        Traced::Saved(path, cycles, stops) => if format == OutputFormat::Text {
+            for stop in stops {
+                println!("Stopped at {}", stop);
+            }
+            println!("Traced {} cycle(s) to {}", cycles, path.display());
+        },
+        Traced::Failed(message) => eprintln!("{}", message)
+    }
+}
+
+pub fn run(options: &RunOptions) -> Result<(), String> {
+    let mut failed_days = 0;
+    let mut timed_out_parts = 0;
+    let days = select_days(&options.days)?;
+    let parts = options.parts();
+    let settings = options.settings();
+
+    let run_day = |day: &Day| {
+        let input = load_input(day, options);
+        let rendered = match (&options.render, &input) {
+            (Some(target), Ok(input)) => render_day(day, input, target),
+            _ => None
+        };
+        let traced = match (&options.trace_path, &input) {
+            (Some(path), Ok(input)) => Some(trace_day(day, input, path, &options.breakpoints)),
+            _ => None
+        };
+        (input.and_then(|input| day.run_within_budget(input, &parts, &settings)), rendered, traced)
+    };
+    run_ordered(&days, options.jobs, run_day, |day, (result, rendered, traced)| {
+        match options.format {
+            OutputFormat::Text => print_text(day, &result),
+            OutputFormat::Json => print_json(day, &parts, &result)
+        }
+        if let Some(rendered) = &rendered {
+            print_rendered(rendered, options.format);
+        }
+        if let Some(traced) = &traced {
+            print_traced(traced, options.format);
+        }
+        let is_day_failed = match &result {
+            Ok(day_result) => {
+                timed_out_parts += day_result.parts.iter().filter(|part_result| is_timed_out(part_result)).count();
+                day_result.parts.iter().any(is_failed)
+            }
+            Err(_) => true
+        };
+        if is_day_failed || matches!(rendered, Some(Rendered::Failed(_))) || matches!(traced, Some(Traced::Failed(_))) {
+            failed_days += 1;
+        }
+    });
+
+    if failed_days > 0 || timed_out_parts > 0 {
+        return Err(format!("{} day(s) failed, {} part(s) timed out", failed_days, timed_out_parts));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Duration;
+    use super::*;
+
+    #[test]
+    pub fn run_ordered_keeps_order() {
+        let items: Vec<u64> = (0..20).collect();
+        let mut results = vec![];
+        // Earlier items take longer, so they finish out of order
+        run_ordered(&items, 4, |&item| {
+            thread::sleep(Duration::from_millis(20 - item));
+            item * 2
+        }, |&item, result| results.push((item, result)));
+        assert_eq!(items.iter().map(|&item| (item, item * 2)).collect::<Vec<(u64, u64)>>(), results);
+    }
+}
@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::Path;
+
+const DAYS_DIR: &str = "src/days";
+const DAYS_MODULE_PATH: &str = "src/days.rs";
+const INPUT_DIR: &str = "input";
+
+// `{day}` is replaced with the day number
+const DAY_TEMPLATE: &str = r#"use crate::error::AocResult;
+use crate::solution::{Answer, Solution};
+
+fn parse_input(input: &str) -> AocResult<Vec<&str>> {
+    Ok(input.split("\n").collect())
+}
+
+fn part1(lines: &[&str]) -> usize {
+    lines.len()
+}
+
+fn part2(lines: &[&str]) -> usize {
+    lines.len()
+}
+
+pub struct Day{day};
+
+impl Solution for Day{day} {
+    type Input<'a> = Vec<&'a str>;
+
+    fn parse(input: &str) -> AocResult<Vec<&str>> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Vec<&str>) -> AocResult<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Vec<&str>) -> AocResult<Answer> {
+        Ok(part2(input).into())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    const INPUT: &str = "example";
+
+    #[test]
+    pub fn part1() {
+        assert_eq!(1, super::part1(&super::parse_input(INPUT).unwrap()));
+    }
+
+    #[test]
+    pub fn part2() {
+        assert_eq!(1, super::part2(&super::parse_input(INPUT).unwrap()));
+    }
+}
+"#;
+
+fn render_day(day: u32) -> String {
+    DAY_TEMPLATE.replace("{day}", day.to_string().as_str())
+}
+
+// Finds the number in lines like `pub mod day12;` or `Day::new::<day12::Day12>(12),`
+fn registered_day(line: &str, prefix: &str) -> Option<u32> {
+    let rest = line.trim().strip_prefix(prefix)?;
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+// Inserts `new_line` after the last line registering an earlier day, keeping the list sorted
+fn insert_sorted(lines: &mut Vec<String>, prefix: &str, day: u32, new_line: String) -> Result<(), String> {
+    let registered: Vec<(usize, u32)> = lines.iter()
+        .enumerate()
+        .filter_map(|(i, line)| registered_day(line, prefix).map(|number| (i, number)))
+        .collect();
+
+    if registered.iter().any(|&(_, number)| number == day) {
+        return Err(format!("Day {} is already registered in {}", day, DAYS_MODULE_PATH));
+    }
+
+    let insert_at = match registered.iter().rev().find(|&&(_, number)| number < day) {
+        Some(&(i, _)) => i + 1,
+        None => registered.first().map(|&(i, _)| i).ok_or_else(|| format!("Could not find where to register day {} in {}", day, DAYS_MODULE_PATH))?
+    };
+    lines.insert(insert_at, new_line);
+    Ok(())
+}
+
+pub fn register_day(days_module: &str, day: u32) -> Result<String, String> {
+    let mut lines: Vec<String> = days_module.lines().map(|line| line.to_string()).collect();
+    insert_sorted(&mut lines, "pub mod day", day, format!("pub mod day{};", day))?;
+    insert_sorted(&mut lines, "Day::new::<day", day, format!("        Day::new::<day{}::Day{}>({}),", day, day, day))?;
+    Ok(lines.join("\n") + "\n")
+}
+
+pub fn new_day(day: u32) -> Result<(), String> {
+    let module_path = Path::new(DAYS_DIR).join(format!("day{}.rs", day));
+    let days_module_path = Path::new(DAYS_MODULE_PATH);
+    let input_path = Path::new(INPUT_DIR).join(format!("day{}.txt", day));
+
+    if !days_module_path.exists() {
+        return Err(format!("Could not find {}, run this from the root of the repository", DAYS_MODULE_PATH));
+    }
+    if module_path.exists() {
+        return Err(format!("{} already exists", module_path.display()));
+    }
+
+    // Work out the registration first so a failure doesn't leave a half-added day behind
+    let days_module = fs::read_to_string(days_module_path).map_err(|err| format!("Could not read {}: {}", DAYS_MODULE_PATH, err))?;
+    let registered_days_module = register_day(days_module.as_str(), day)?;
+
+    fs::write(&module_path, render_day(day)).map_err(|err| format!("Could not write {}: {}", module_path.display(), err))?;
+    fs::write(days_module_path, registered_days_module).map_err(|err| format!("Could not write {}: {}", DAYS_MODULE_PATH, err))?;
+    println!("Created {} and registered it in {}", module_path.display(), DAYS_MODULE_PATH);
+
+    if !input_path.exists() {
+        fs::create_dir_all(INPUT_DIR)
+            .and_then(|_| fs::write(&input_path, ""))
+            .map_err(|err| format!("Could not write {}: {}", input_path.display(), err))?;
+        println!("Created empty {}", input_path.display());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const DAYS_MODULE: &str = "pub mod day1;
+pub mod day3;
+
+pub fn all() -> Vec<Day> {
+    vec![
+        Day::new::<day1::Day1>(1),
+        Day::new::<day3::Day3>(3),
+    ]
+}";
+
+    #[test]
+    pub fn registers_in_order() {
+        assert_eq!(Ok("pub mod day1;
+pub mod day2;
+pub mod day3;
+
+pub fn all() -> Vec<Day> {
+    vec![
+        Day::new::<day1::Day1>(1),
+        Day::new::<day2::Day2>(2),
+        Day::new::<day3::Day3>(3),
+    ]
+}
+".to_string()), register_day(DAYS_MODULE, 2));
+        assert!(register_day(DAYS_MODULE, 25).unwrap().contains("pub mod day3;\npub mod day25;\n"));
+        assert!(register_day(DAYS_MODULE, 3).is_err());
+    }
+
+    #[test]
+    pub fn renders_template() {
+        let module = render_day(24);
+        assert!(module.contains("pub struct Day24;"));
+        assert!(module.contains("impl Solution for Day24 {"));
+        assert!(!module.contains("{day}"));
+    }
+}
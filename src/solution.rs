@@ -0,0 +1,130 @@
+use std::fmt;
+use std::fmt::Formatter;
+use crate::cancel::CancellationToken;
+use crate::common::numeric::Arithmetic;
+use crate::common::render::Animation;
+use crate::error::AocResult;
+use crate::vm::debugger::Trace;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Answer {
+    Number(i128),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(value) => write!(f, "{}", value),
+            Answer::Text(value) => write!(f, "{}", value)
+        }
+    }
+}
+
+// A number too big for an i128 is kept as its digits rather than wrapped around
+macro_rules! answer_from_number {
+    ($($number_type:ty),*) => {
+        $(
+            impl From<$number_type> for Answer {
+                fn from(value: $number_type) -> Answer {
+                    i128::try_from(value).map(Answer::Number).unwrap_or_else(|_| Answer::Text(value.to_string()))
+                }
+            }
+        )*
+    };
+}
+
+answer_from_number!(i32, u32, usize, i128, u128);
+
+impl From<String> for Answer {
+    fn from(value: String) -> Answer {
+        Answer::Text(value)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    pub fn all() -> Vec<Part> {
+        vec![Part::One, Part::Two]
+    }
+
+    pub fn from_number(value: u32) -> Option<Part> {
+        match value {
+            1 => Some(Part::One),
+            2 => Some(Part::Two),
+            _ => None
+        }
+    }
+
+    pub fn number(&self) -> u32 {
+        match self {
+            Part::One => 1,
+            Part::Two => 2
+        }
+    }
+}
+
+// What a part runs with besides its input
+#[derive(Copy, Clone, Debug)]
+pub struct PartContext {
+    pub cancel: CancellationToken,
+    pub arithmetic: Arithmetic,
+}
+
+impl Default for PartContext {
+    fn default() -> PartContext {
+        PartContext {
+            cancel: CancellationToken::never(),
+            arithmetic: Arithmetic::Native,
+        }
+    }
+}
+
+pub trait Solution {
+    // Sync so both parts can read the same parsed input from different threads
+    type Input<'a>: Sync;
+
+    // Most days want surrounding whitespace gone, but some (e.g. day 22) care about leading spaces
+    const TRIM_INPUT: bool = true;
+
+    fn parse(input: &str) -> AocResult<Self::Input<'_>>;
+    fn part1(input: &Self::Input<'_>) -> AocResult<Answer>;
+    fn part2(input: &Self::Input<'_>) -> AocResult<Answer>;
+
+    // Days with long searches override these to check `context.cancel` and give up once the time budget is spent,
+    // and days with numeric solvers to run with the number type `context.arithmetic` asks for
+    fn part1_with(input: &Self::Input<'_>, _context: &PartContext) -> AocResult<Answer> {
+        Self::part1(input)
+    }
+
+    fn part2_with(input: &Self::Input<'_>, _context: &PartContext) -> AocResult<Answer> {
+        Self::part2(input)
+    }
+
+    // Pictures of the simulation for `run --render`, from the days that have something worth looking at
+    fn render(_input: &Self::Input<'_>) -> AocResult<Option<Animation>> {
+        Ok(None)
+    }
+
+    // The program's run cycle by cycle for `run --trace`, from the days that run one on the VM
+    fn trace(_input: &Self::Input<'_>, _breakpoints: &[String]) -> AocResult<Option<Trace>> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn answers_from_numbers() {
+        assert_eq!(Answer::Number(-3), Answer::from(-3));
+        assert_eq!(Answer::Number(i128::MAX), Answer::from(i128::MAX as u128));
+        assert_eq!(Answer::Text(u128::MAX.to_string()), Answer::from(u128::MAX));
+    }
+}
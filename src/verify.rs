@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::cli::{DaySelection, VerifyOptions};
+use crate::common::{read_input, read_input_file};
+use crate::days::{Day, PartOutcome, RunSettings};
+use crate::error::{AocError, AocResult};
+use crate::runner::{describe_error, run_ordered, select_days};
+use crate::solution::{Answer, Part};
+
+// The answers file is a small subset of TOML: one `[[answer]]` table per day and input file, e.g.
+//
+//   [[answer]]
+//   day = 1
+//   input = "input/day1.txt"   # optional, defaults to input/dayN.txt, downloaded if missing
+//   part1 = 70369
+//   part2 = "text answers are quoted"
+//
+// Multi-line answers (like the day 10 screen) can use a '''literal''' string spanning several lines.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecordedAnswers {
+    pub day: u32,
+    pub input_path: Option<PathBuf>,
+    pub answers: HashMap<Part, Answer>,
+}
+
+impl RecordedAnswers {
+    fn input_path(&self) -> PathBuf {
+        match &self.input_path {
+            Some(path) => path.clone(),
+            None => PathBuf::from(format!("input/day{}.txt", self.day))
+        }
+    }
+}
+
+fn parse_string(value: &str) -> AocResult<String> {
+    let mut result = String::new();
+    let mut chars = value.strip_prefix('"').unwrap_or(value).chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let rest: String = chars.collect();
+                if !rest.trim().is_empty() && !rest.trim().starts_with('#') {
+                    return Err(AocError::parse(format!("unexpected `{}` after the string", rest.trim())));
+                }
+                return Ok(result);
+            }
+            '\\' => match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some('"') => result.push('"'),
+                Some('\\') => result.push('\\'),
+                other => return Err(AocError::parse(format!("unknown escape `\\{}`", other.map(String::from).unwrap_or_default())))
+            },
+            _ => result.push(c)
+        }
+    }
+    Err(AocError::parse("string is not closed"))
+}
+
+fn strip_comment(value: &str) -> &str {
+    match value.split_once('#') {
+        Some((before_comment, _)) => before_comment.trim(),
+        None => value
+    }
+}
+
+fn parse_answer(value: &str) -> AocResult<Answer> {
+    if value.starts_with('"') {
+        return Ok(Answer::Text(parse_string(value)?));
+    }
+    let value = strip_comment(value);
+    value.parse().map(Answer::Number).map_err(|_| AocError::parse(format!("expected a number or a quoted string, got `{}`", value)))
+}
+
+pub fn parse_answers(contents: &str) -> AocResult<Vec<RecordedAnswers>> {
+    let mut entries: Vec<RecordedAnswers> = vec![];
+    let mut current: Option<RecordedAnswers> = None;
+    let mut current_start_line = 0;
+    let mut lines = contents.lines().enumerate();
+
+    let finish_entry = |entry: Option<RecordedAnswers>, entries: &mut Vec<RecordedAnswers>, start_line: usize| {
+        if let Some(entry) = entry {
+            if entry.day == 0 {
+                return Err(AocError::parse("answer is missing `day`").at_line(start_line));
+            }
+            entries.push(entry);
+        }
+        Ok(())
+    };
+
+    while let Some((i, raw_line)) = lines.next() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line == "[[answer]]" {
+            finish_entry(current.take(), &mut entries, current_start_line)?;
+            current = Some(RecordedAnswers {
+                day: 0,
+                input_path: None,
+                answers: HashMap::new(),
+            });
+            current_start_line = i + 1;
+            continue;
+        }
+
+        let entry = current.as_mut().ok_or_else(|| AocError::parse("expected `[[answer]]` before any values").at_line(i + 1))?;
+        let (key, value) = line.split_once('=').ok_or_else(|| AocError::parse("expected `key = value`").at_line(i + 1))?;
+        let (key, value) = (key.trim(), value.trim());
+
+        let value = if let Some(first_line) = value.strip_prefix("'''") {
+            // TOML drops the newline that directly follows the opening quotes
+            let mut text_lines: Vec<&str> = vec![];
+            if !first_line.is_empty() {
+                text_lines.push(first_line);
+            }
+            loop {
+                let text_line = text_lines.last().copied().unwrap_or("");
+                if let Some(text) = text_line.strip_suffix("'''") {
+                    let last = text_lines.len() - 1;
+                    text_lines[last] = text;
+                    break;
+                }
+                match lines.next() {
+                    Some((_, next_line)) => text_lines.push(next_line),
+                    None => return Err(AocError::parse("multi-line string is not closed").at_line(i + 1))
+                }
+            }
+            Answer::Text(text_lines.join("\n"))
+        } else {
+            parse_answer(value).map_err(|error| error.at_line(i + 1))?
+        };
+
+        match (key, value) {
+            ("day", Answer::Number(day)) if day > 0 => entry.day = day as u32,
+            ("input", Answer::Text(path)) => entry.input_path = Some(PathBuf::from(path)),
+            ("part1", answer) => { entry.answers.insert(Part::One, answer); }
+            ("part2", answer) => { entry.answers.insert(Part::Two, answer); }
+            ("day", _) | ("input", _) => return Err(AocError::parse(format!("invalid value for `{}`", key)).at_line(i + 1)),
+            _ => return Err(AocError::parse(format!("unknown key `{}`", key)).at_line(i + 1))
+        }
+    }
+
+    finish_entry(current, &mut entries, current_start_line)?;
+    Ok(entries)
+}
+
+pub fn read_answers(path: &Path) -> Result<Vec<RecordedAnswers>, String> {
+    let contents = fs::read_to_string(path).map_err(|err| format!("Could not read answers {}: {}", path.display(), err))?;
+    parse_answers(contents.as_str()).map_err(|error| format!("{}, {}", path.display(), error))
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum Status {
+    Pass,
+    Fail { expected: Answer, actual: Answer },
+    Unrecorded,
+    Error(String),
+}
+
+fn check(expected: Option<&Answer>, actual: &Answer) -> Status {
+    match expected {
+        // Compare what would be printed, so `1` and `"1"` in the answers file mean the same thing
+        Some(expected) if expected.to_string() == actual.to_string() => Status::Pass,
+        Some(expected) => Status::Fail {
+            expected: expected.clone(),
+            actual: actual.clone(),
+        },
+        None => Status::Unrecorded
+    }
+}
+
+fn print_answer_detail(label: &str, answer: &Answer) {
+    let answer_str = answer.to_string();
+    if answer_str.contains('\n') {
+        println!("        {}:", label);
+        for line in answer_str.lines() {
+            println!("          {}", line);
+        }
+    } else {
+        println!("        {:<9} {}", format!("{}:", label), answer_str);
+    }
+}
+
+fn print_row(day: u32, part: Option<Part>, input_path: &Path, status: &Status) {
+    let part_str = part.map(|part| part.number().to_string()).unwrap_or_else(|| "-".to_string());
+    let status_str = match status {
+        Status::Pass => "pass",
+        Status::Fail { .. } => "FAIL",
+        Status::Unrecorded => "no answer",
+        Status::Error(_) => "ERROR"
+    };
+    println!("{:>3}  {:>4}  {:<9}  {}", day, part_str, status_str, input_path.display());
+
+    match status {
+        Status::Fail { expected, actual } => {
+            print_answer_detail("expected", expected);
+            print_answer_detail("got", actual);
+        }
+        Status::Error(message) => println!("        {}", message),
+        _ => ()
+    }
+}
+
+// A status per part, or a single one without a part when the whole day failed
+type EntryStatuses = Vec<(Option<Part>, Status)>;
+
+fn verify_entry(day: &Day, entry: &RecordedAnswers, parts: &[Part], settings: &RunSettings) -> EntryStatuses {
+    // Recorded inputs are read as they are, and the default one goes through the same download and cache as `run`
+    let input = match &entry.input_path {
+        Some(path) => read_input_file(path),
+        None => read_input(entry.day)
+    };
+    let result = input.and_then(|input| day.run_within_budget(input, parts, settings));
+    match result {
+        Ok(day_result) => day_result.parts.iter()
+            .map(|part_result| (Some(part_result.part), match &part_result.outcome {
+                PartOutcome::Solved(answer) => check(entry.answers.get(&part_result.part), answer),
+                PartOutcome::TimedOut => Status::Error(format!("timed out after {:?}", part_result.elapsed)),
+                PartOutcome::Failed(error) => Status::Error(error.to_string())
+            }))
+            .collect(),
+        Err(error) => vec![(None, Status::Error(describe_error(day, &error)))]
+    }
+}
+
+pub fn verify(options: &VerifyOptions) -> Result<(), String> {
+    let recorded = read_answers(&options.answers_path)?;
+    // Verifying is about catching regressions anywhere, so it covers every day unless told otherwise
+    let selection = match &options.run.days {
+        DaySelection::Default => DaySelection::All,
+        other => other.clone()
+    };
+    let parts = options.run.parts();
+    let settings = options.run.settings();
+
+    let mut passed = 0;
+    let mut failed = 0;
+    let mut unrecorded = 0;
+
+    let days = select_days(&selection)?;
+    let verify_day = |day: &Day| -> Vec<(RecordedAnswers, EntryStatuses)> {
+        let entries: Vec<&RecordedAnswers> = recorded.iter().filter(|entry| entry.day == day.number).collect();
+        if entries.is_empty() {
+            let default_entry = RecordedAnswers {
+                day: day.number,
+                input_path: None,
+                answers: HashMap::new(),
+            };
+            return vec![(default_entry, vec![(None, Status::Unrecorded)])];
+        }
+
+        entries.into_iter()
+            .map(|entry| (entry.clone(), verify_entry(day, entry, &parts, &settings)))
+            .collect()
+    };
+
+    println!("Day  Part  Result     Input");
+    run_ordered(&days, options.run.jobs, verify_day, |day, entry_results| {
+        for (entry, statuses) in entry_results {
+            for (part, status) in statuses {
+                print_row(day.number, part, &entry.input_path(), &status);
+                match status {
+                    Status::Pass => passed += 1,
+                    Status::Unrecorded => unrecorded += 1,
+                    Status::Fail { .. } | Status::Error(_) => failed += 1
+                }
+            }
+        }
+    });
+
+    println!("\n{} passed, {} failed, {} without a recorded answer", passed, failed, unrecorded);
+
+    if failed > 0 {
+        return Err(format!("{} check(s) did not match the recorded answers", failed));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn parses_answers_file() {
+        let contents = "# known good answers
+[[answer]]
+day = 1
+part1 = 70369 # from the real input
+part2 = \"two\\nlines\"
+
+[[answer]]
+day = 10
+input = \"input/example10.txt\"
+part2 = '''
+#..#
+####'''
+";
+        let answers = parse_answers(contents).unwrap();
+        assert_eq!(2, answers.len());
+        assert_eq!(Some(&Answer::Number(70369)), answers[0].answers.get(&Part::One));
+        assert_eq!(Some(&Answer::Text("two\nlines".to_string())), answers[0].answers.get(&Part::Two));
+        assert_eq!(PathBuf::from("input/day1.txt"), answers[0].input_path());
+        assert_eq!(PathBuf::from("input/example10.txt"), answers[1].input_path());
+        assert_eq!(Some(&Answer::Text("#..#\n####".to_string())), answers[1].answers.get(&Part::Two));
+    }
+
+    #[test]
+    pub fn rejects_malformed_answers() {
+        assert_eq!(Err(AocError::parse("expected `[[answer]]` before any values").at_line(1)), parse_answers("day = 1"));
+        assert_eq!(Err(AocError::parse("unknown key `part3`").at_line(3)), parse_answers("[[answer]]\nday = 1\npart3 = 5"));
+        assert_eq!(Err(AocError::parse("answer is missing `day`").at_line(1)), parse_answers("[[answer]]\npart1 = 5"));
+    }
+
+    #[test]
+    pub fn compares_printed_answers() {
+        assert_eq!(Status::Pass, check(Some(&Answer::Text("42".to_string())), &Answer::Number(42)));
+        assert_eq!(Status::Unrecorded, check(None, &Answer::Number(42)));
+        assert_eq!(Status::Fail {
+            expected: Answer::Number(41),
+            actual: Answer::Number(42),
+        }, check(Some(&Answer::Number(41)), &Answer::Number(42)));
+    }
+}
@@ -1,71 +1,611 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use crate::common::cycle::Cycle;
+use crate::error::{AocError, AocResult};
+use crate::solution::Answer;
+use crate::vm::device::Device;
+
+pub mod assembler;
+pub mod debugger;
+pub mod device;
+
+// Registers are named in programs as they're parsed, and become `Register`s once a machine loads them
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Operand<R = String> {
+    Register(R),
+    Value(i128),
+}
+
+// What the last `cmp` has to have found for a jump to be taken
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Condition {
+    Always,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Condition {
+    pub fn holds(&self, comparison: Option<Ordering>) -> bool {
+        match (self, comparison) {
+            (Condition::Always, _) => true,
+            (_, None) => false,
+            (Condition::Equal, Some(ordering)) => ordering.is_eq(),
+            (Condition::NotEqual, Some(ordering)) => ordering.is_ne(),
+            (Condition::Less, Some(ordering)) => ordering.is_lt(),
+            (Condition::LessOrEqual, Some(ordering)) => ordering.is_le(),
+            (Condition::Greater, Some(ordering)) => ordering.is_gt(),
+            (Condition::GreaterOrEqual, Some(ordering)) => ordering.is_ge()
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum JumpTarget {
+    // An index into the program
+    Absolute(usize),
+    // An offset from the jump itself
+    Relative(i128),
+}
 
 #[derive(Clone, Eq, PartialEq, Debug)]
-pub enum Instruction<'a> {
+pub enum Instruction<R = String> {
     Noop,
-    Add(&'a str, i128),
+    // The register is changed by the operand
+    Add(R, Operand<R>),
+    Sub(R, Operand<R>),
+    Mul(R, Operand<R>),
+    Mov(R, Operand<R>),
+    // Compares the two operands, for the jumps that follow
+    Cmp(Operand<R>, Operand<R>),
+    Jump(Condition, JumpTarget),
+    Halt,
+}
+
+impl<R> Operand<R> {
+    fn map_register<S, F>(&self, f: &F) -> AocResult<Operand<S>>
+        where F: Fn(&R) -> AocResult<S> {
+        match self {
+            Operand::Register(register) => Ok(Operand::Register(f(register)?)),
+            Operand::Value(value) => Ok(Operand::Value(*value))
+        }
+    }
+}
+
+impl<R> Instruction<R> {
+    // How many cycles the instruction takes to finish
+    pub fn cycles(&self) -> usize {
+        match self {
+            Instruction::Noop => 1,
+            Instruction::Add(_, _) => 2,
+            Instruction::Sub(_, _) => 2,
+            Instruction::Mul(_, _) => 4,
+            Instruction::Mov(_, _) => 1,
+            Instruction::Cmp(_, _) => 1,
+            Instruction::Jump(_, _) => 1,
+            Instruction::Halt => 1
+        }
+    }
+
+    // The same instruction with every register swapped for what `f` gives
+    pub fn map_registers<S, F>(&self, f: F) -> AocResult<Instruction<S>>
+        where F: Fn(&R) -> AocResult<S> {
+        Ok(match self {
+            Instruction::Noop => Instruction::Noop,
+            Instruction::Add(register, operand) => Instruction::Add(f(register)?, operand.map_register(&f)?),
+            Instruction::Sub(register, operand) => Instruction::Sub(f(register)?, operand.map_register(&f)?),
+            Instruction::Mul(register, operand) => Instruction::Mul(f(register)?, operand.map_register(&f)?),
+            Instruction::Mov(register, operand) => Instruction::Mov(f(register)?, operand.map_register(&f)?),
+            Instruction::Cmp(left, right) => Instruction::Cmp(left.map_register(&f)?, right.map_register(&f)?),
+            Instruction::Jump(condition, target) => Instruction::Jump(*condition, *target),
+            Instruction::Halt => Instruction::Halt
+        })
+    }
+}
+
+// Where execution goes once an instruction has finished
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Flow {
+    Next,
+    Jump(JumpTarget),
+    Halt,
+}
+
+impl Flow {
+    // The index of the next instruction after the one at `pc`, or None if execution stops
+    pub fn next_pc(&self, pc: usize) -> Option<usize> {
+        match self {
+            Flow::Next => Some(pc + 1),
+            Flow::Jump(JumpTarget::Absolute(index)) => Some(*index),
+            Flow::Jump(JumpTarget::Relative(offset)) => (pc as i128).checked_add(*offset).and_then(|target| usize::try_from(target).ok()),
+            Flow::Halt => None
+        }
+    }
+}
+
+// Written the way the assembler reads it
+impl<R: Display> Display for Operand<R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Register(name) => write!(f, "{}", name),
+            Operand::Value(value) => write!(f, "{}", value)
+        }
+    }
+}
+
+impl<R: Display> Display for Instruction<R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Noop => write!(f, "noop"),
+            Instruction::Add(register, operand) => write!(f, "add {} {}", register, operand),
+            Instruction::Sub(register, operand) => write!(f, "sub {} {}", register, operand),
+            Instruction::Mul(register, operand) => write!(f, "mul {} {}", register, operand),
+            Instruction::Mov(register, operand) => write!(f, "mov {} {}", register, operand),
+            Instruction::Cmp(left, right) => write!(f, "cmp {} {}", left, right),
+            Instruction::Jump(condition, target) => {
+                let mnemonic = match condition {
+                    Condition::Always => "jmp",
+                    Condition::Equal => "jeq",
+                    Condition::NotEqual => "jne",
+                    Condition::Less => "jlt",
+                    Condition::LessOrEqual => "jle",
+                    Condition::Greater => "jgt",
+                    Condition::GreaterOrEqual => "jge"
+                };
+                match target {
+                    JumpTarget::Absolute(index) => write!(f, "{} {}", mnemonic, index),
+                    JumpTarget::Relative(offset) => write!(f, "{} {:+}", mnemonic, offset)
+                }
+            }
+            Instruction::Halt => write!(f, "halt")
+        }
+    }
+}
+
+// A register declared in a `RegisterSet`, by its position there
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Register(usize);
+
+// The registers a machine has, each with the value it starts at
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RegisterSet {
+    names: Vec<String>,
+    initial_values: Vec<i128>,
+}
+
+impl RegisterSet {
+    pub fn new() -> RegisterSet {
+        RegisterSet::default()
+    }
+
+    // Declaring a register again only changes its initial value
+    pub fn with(mut self, name: &str, initial_value: i128) -> RegisterSet {
+        match self.find(name) {
+            Some(register) => self.initial_values[register.0] = initial_value,
+            None => {
+                self.names.push(name.to_string());
+                self.initial_values.push(initial_value);
+            }
+        }
+        self
+    }
+
+    pub fn find(&self, name: &str) -> Option<Register> {
+        self.names.iter().position(|declared| declared == name).map(Register)
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn check(&self, name: &str) -> AocResult<Register> {
+        self.find(name).ok_or_else(|| AocError::parse(format!("undeclared register `{}`", name)))
+    }
+
+    // The instruction as a machine with these registers runs it
+    pub fn resolve(&self, instruction: &Instruction) -> AocResult<Instruction<Register>> {
+        instruction.map_registers(|name| self.check(name))
+    }
+}
+
+// The program counter, register values and last comparison, which decide everything the machine does next
+type MachineState = (usize, Vec<i128>, Option<Ordering>);
+
+// What `run` and the debugger's trace give up with when the program doesn't stop in time
+fn still_running(max_cycles: usize) -> AocError {
+    AocError::parse(format!("the program is still running after {} cycle(s)", max_cycles))
+}
+
+// Whether the loaded program can keep running
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Status {
+    Running,
+    // A `halt` instruction ran
+    Halted,
+    // The program counter moved outside the program
+    Finished,
+    // An instruction's result didn't fit in its register, or a device's didn't fit in 128 bits
+    Faulted,
 }
 
-pub struct VirtualMachine<'a> {
-    _registers: HashMap<&'a str, i128>,
+pub struct VirtualMachine {
+    _register_set: RegisterSet,
+    _registers: Vec<i128>,
     _cycle: usize,
+    _comparison: Option<Ordering>,
+    _program: Vec<Instruction>,
+    // The loaded program with its registers resolved
+    _resolved: Vec<Instruction<Register>>,
+    _pc: usize,
+    _status: Status,
+    // Cycles the instruction at `_pc` has taken so far
+    _elapsed: usize,
+    // Why the machine stopped, once it's faulted
+    _fault: Option<AocError>,
+    _devices: Vec<Box<dyn Device>>,
 }
 
-impl<'a> VirtualMachine<'a> {
-    pub fn new() -> VirtualMachine<'a> {
+impl VirtualMachine {
+    pub fn new(register_set: RegisterSet) -> VirtualMachine {
         VirtualMachine {
-            _registers: HashMap::new(),
+            _registers: register_set.initial_values.clone(),
+            _register_set: register_set,
             _cycle: 0,
+            _comparison: None,
+            _program: vec![],
+            _resolved: vec![],
+            _pc: 0,
+            _status: Status::Finished,
+            _elapsed: 0,
+            _fault: None,
+            _devices: vec![],
         }
     }
 
-    fn parse_unary(command_name: &'a str, raw_command: &'a str, args: &'a str) -> (&'a str, i128) {
-        let (_, register) = raw_command.split_once(command_name).expect("Could not get register from command");
-        (register, args.parse().expect("Could not parse arg"))
+    // Replaces the program and starts it again from a fresh machine. Fails, leaving the machine as it was, if the
+    // program uses a register that isn't declared.
+    pub fn load(&mut self, program: Vec<Instruction>) -> AocResult<()> {
+        self._resolved = program.iter()
+            .map(|instruction| self._register_set.resolve(instruction))
+            .collect::<AocResult<Vec<Instruction<Register>>>>()?;
+        self._program = program;
+        self.reset();
+        Ok(())
     }
 
-    pub fn parse_instruction(line: &'a str) -> Instruction<'a> {
-        if line == "noop" {
-            return Instruction::Noop;
+    // Back to before the first cycle, with the same program loaded
+    pub fn reset(&mut self) {
+        self._registers.clone_from(&self._register_set.initial_values);
+        self._cycle = 0;
+        self._comparison = None;
+        self._pc = 0;
+        self._elapsed = 0;
+        self._fault = None;
+        self._status = if self._program.is_empty() { Status::Finished } else { Status::Running };
+        for device in self._devices.iter_mut() {
+            device.reset();
         }
+    }
 
-        let (command, args) = line.split_once(" ").expect("Could not split line, are there args?");
-
-        if command.starts_with("add") {
-            let (register, amount) = VirtualMachine::parse_unary("add", command, args);
-            return Instruction::Add(register, amount);
+    // Returns the index to read the device's output by, with `device_output`. Devices keep working across `load` and
+    // `reset`.
+    pub fn attach(&mut self, device: Box<dyn Device>) -> AocResult<usize> {
+        for name in device.registers() {
+            self._register_set.check(name)?;
         }
+        self._devices.push(device);
+        Ok(self._devices.len() - 1)
+    }
+
+    pub fn device_output(&self, index: usize) -> Answer {
+        self._devices[index].output()
+    }
 
-        panic!("Could not parse instruction {}", line);
+    pub fn program(&self) -> &[Instruction] {
+        &self._program
     }
 
+    // The instruction running or about to run
+    pub fn get_pc(&self) -> usize {
+        self._pc
+    }
+
+    pub fn status(&self) -> Status {
+        self._status
+    }
+
+    pub fn fault(&self) -> Option<&AocError> {
+        self._fault.as_ref()
+    }
+
+    pub fn register_set(&self) -> &RegisterSet {
+        &self._register_set
+    }
+
+    pub fn register(&self, name: &str) -> Option<Register> {
+        self._register_set.find(name)
+    }
+
+    pub fn value(&self, register: Register) -> i128 {
+        self._registers[register.0]
+    }
+
+    // None if there's no such register
     pub fn get_register_value(&self, name: &str) -> Option<i128> {
-        self._registers.get(name).map(|v| *v)
+        self.register(name).map(|register| self.value(register))
     }
 
-    fn get_or_insert_register_ref(&mut self, name: &'a str) -> &mut i128 {
-        self._registers.entry(name).or_insert(1)
+    // Every register's value, in the order they were declared
+    pub fn registers(&self) -> &[i128] {
+        &self._registers
+    }
+
+    fn operand_value(&self, operand: &Operand<Register>) -> i128 {
+        match operand {
+            Operand::Register(register) => self.value(*register),
+            Operand::Value(value) => *value
+        }
     }
 
     pub fn get_cycle(&self) -> usize {
-        return self._cycle;
+        self._cycle
     }
 
-    pub fn execute_instruction<F>(&mut self, instruction: Instruction<'a>, mut on_cycle: F)
-        where F: FnMut(&Self) -> () {
-        let cycle_count = match instruction {
-            Instruction::Noop => 1,
-            Instruction::Add(_, _) => 2
+    // Writes the result of the current instruction if its last cycle has run, and moves the program counter on
+    fn finish_instruction(&mut self) {
+        if self._status != Status::Running || self._elapsed < self._resolved[self._pc].cycles() {
+            return;
+        }
+        self._elapsed = 0;
+        let flow = match self.apply(self._pc) {
+            Ok(flow) => flow,
+            Err(error) => {
+                self._fault = Some(error);
+                self._status = Status::Faulted;
+                return;
+            }
         };
+        match flow.next_pc(self._pc) {
+            Some(pc) if pc < self._resolved.len() => self._pc = pc,
+            None if flow == Flow::Halt => self._status = Status::Halted,
+            _ => self._status = Status::Finished
+        }
+    }
+
+    // Runs one cycle, leaving the machine as it is during that cycle: an instruction's result is only written once the
+    // next cycle starts. False if the program had already stopped. A device that fails faults the machine, which stops
+    // it once this cycle is over.
+    pub fn tick(&mut self) -> bool {
+        self.finish_instruction();
+        if self._status != Status::Running {
+            return false;
+        }
+        self._cycle += 1;
+        self._elapsed += 1;
+        // Taken out while they tick, so they can look at the rest of the machine
+        let mut devices = std::mem::take(&mut self._devices);
+        let ticked = devices.iter_mut().try_for_each(|device| device.tick(self));
+        self._devices = devices;
+        if let Err(error) = ticked {
+            self._fault = Some(error);
+            self._status = Status::Faulted;
+        }
+        true
+    }
+
+    // Runs the rest of the current instruction, with `on_cycle` seeing each cycle it takes. False if the program had
+    // already stopped.
+    pub fn step<F>(&mut self, mut on_cycle: F) -> bool
+        where F: FnMut(&Self) {
+        self.finish_instruction();
+        if self._status != Status::Running {
+            return false;
+        }
+        while self._status == Status::Running && self._elapsed < self._resolved[self._pc].cycles() {
+            self.tick();
+            on_cycle(self);
+        }
+        self.finish_instruction();
+        true
+    }
+
+    // Ticks until `predicate` holds during a cycle, or returns false if the program stops first
+    pub fn run_until<P>(&mut self, mut predicate: P) -> bool
+        where P: FnMut(&Self) -> bool {
+        while self.tick() {
+            if predicate(self) {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn check_fault(&self) -> AocResult<()> {
+        match &self._fault {
+            Some(error) => Err(error.clone()),
+            None => Ok(())
+        }
+    }
+
+    // Returns how many cycles ran, which is fewer than asked for if the program stopped
+    pub fn run_for(&mut self, cycles: usize) -> AocResult<usize> {
+        let start = self._cycle;
+        let end = start + cycles;
+        if cycles > 0 {
+            self.run_until(|vm| vm.get_cycle() >= end);
+        }
+        self.check_fault()?;
+        Ok(self._cycle - start)
+    }
 
-        for _ in 0..cycle_count {
-            self._cycle += 1;
-            on_cycle(&self);
+    // Runs until the program stops, failing if that's because an instruction faulted, or if it's still running after
+    // `max_cycles` more cycles, which is all a program that never stops would get
+    pub fn run(&mut self, max_cycles: usize) -> AocResult<Status> {
+        let end = self._cycle.saturating_add(max_cycles);
+        self.run_until(|vm| vm.get_cycle() > end);
+        self.check_fault()?;
+        match self._status {
+            Status::Running => Err(still_running(max_cycles)),
+            status => Ok(status)
+        }
+    }
+
+    // Runs instructions until one is about to run with the registers and comparison exactly as they were the last
+    // time it ran, which means the program will go round the same loop forever. Measured in instructions, with the
+    // gain in cycles. None if the program stops, or nothing repeats within `limit` instructions.
+    pub fn find_loop(&mut self, limit: u128) -> Option<Cycle> {
+        let mut seen: HashMap<MachineState, (u128, usize)> = HashMap::new();
+        // Starts from the next instruction boundary
+        if self._elapsed > 0 {
+            self.step(|_| ());
+        }
+        for steps in 0..limit {
+            if self._status != Status::Running {
+                return None;
+            }
+            let key = (self._pc, self._registers.clone(), self._comparison);
+            if let Some(&(start, start_cycle)) = seen.get(&key) {
+                return Some(Cycle {
+                    start,
+                    length: steps - start,
+                    gain: (self._cycle - start_cycle) as i128,
+                });
+            }
+            seen.insert(key, (steps, self._cycle));
+            self.step(|_| ());
         }
+        None
+    }
+
+    // Sets the register to `register <symbol> operand`, or fails without changing it if that overflows
+    fn write_arithmetic(&mut self, register: Register, operand: &Operand<Register>, symbol: &str, operation: fn(i128, i128) -> Option<i128>) -> AocResult<()> {
+        let (left, right) = (self.value(register), self.operand_value(operand));
+        self._registers[register.0] = operation(left, right)
+            .ok_or_else(|| AocError::arithmetic(format!("{} {} {} overflows a 128-bit integer", left, symbol, right)))?;
+        Ok(())
+    }
 
-        match instruction {
+    fn apply(&mut self, pc: usize) -> AocResult<Flow> {
+        match self._resolved[pc].clone() {
             Instruction::Noop => (),
-            Instruction::Add(register, amount) => *self.get_or_insert_register_ref(register) += amount
+            Instruction::Add(register, operand) => self.write_arithmetic(register, &operand, "+", i128::checked_add)?,
+            Instruction::Sub(register, operand) => self.write_arithmetic(register, &operand, "-", i128::checked_sub)?,
+            Instruction::Mul(register, operand) => self.write_arithmetic(register, &operand, "*", i128::checked_mul)?,
+            Instruction::Mov(register, operand) => {
+                self._registers[register.0] = self.operand_value(&operand);
+            }
+            Instruction::Cmp(left, right) => {
+                self._comparison = Some(self.operand_value(&left).cmp(&self.operand_value(&right)));
+            }
+            Instruction::Jump(condition, target) => {
+                if condition.holds(self._comparison) {
+                    return Ok(Flow::Jump(target));
+                }
+            }
+            Instruction::Halt => return Ok(Flow::Halt)
         }
+        Ok(Flow::Next)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::vm::assembler::assemble;
+    use super::*;
+
+    #[test]
+    pub fn runs_by_cycle() {
+        let program = assemble("addx 3\nmov y x\nhalt\nnoop").unwrap();
+        let mut vm = VirtualMachine::new(RegisterSet::new().with("x", 1).with("y", 0));
+        vm.load(program.instructions).unwrap();
+        assert_eq!(Ok(2), vm.run_for(2));
+        // During the `add`'s second cycle, before it has written x
+        assert_eq!((0, Some(1)), (vm.get_pc(), vm.get_register_value("x")));
+        assert!(vm.run_until(|vm| vm.get_pc() == 2));
+        assert_eq!(Some(4), vm.get_register_value("y"));
+        assert_eq!(Ok(0), vm.run_for(5));
+        assert_eq!(Status::Halted, vm.status());
+        assert_eq!(4, vm.get_cycle());
+
+        vm.reset();
+        assert_eq!((Status::Running, 0, &[1, 0][..]), (vm.status(), vm.get_cycle(), vm.registers()));
+        assert_eq!(Ok(Status::Halted), vm.run(10));
+        assert!(!vm.step(|_| ()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn checks_registers() {
+        let registers = RegisterSet::new().with("a", 5).with("b", 0).with("a", 7);
+        assert_eq!(&["a", "b"], registers.names());
+        let mut vm = VirtualMachine::new(registers);
+        assert_eq!(Some(7), vm.get_register_value("a"));
+        assert_eq!(None, vm.get_register_value("c"));
+
+        let b = vm.register("b").unwrap();
+        vm.load(assemble("mov b a\nmul b 3").unwrap().instructions).unwrap();
+        vm.run(10).unwrap();
+        assert_eq!(21, vm.value(b));
+        assert_eq!(
+            Err(AocError::parse("undeclared register `c`")),
+            vm.load(assemble("noop\ncmp a c").unwrap().instructions)
+        );
+        assert_eq!("mov b a", vm.program()[0].to_string());
+    }
+
+    #[test]
+    pub fn finds_loops() {
+        let program = assemble("mov a 0\nloop: add a 1\ncmp a 3\njlt loop\nmov a 0\njmp loop").unwrap();
+        let mut vm = VirtualMachine::new(RegisterSet::new().with("a", 0));
+        vm.load(program.instructions).unwrap();
+        // The first `add` ran before any `cmp`, so the state first repeats at the `jlt` after it
+        assert_eq!(Some(Cycle { start: 3, length: 11, gain: 14 }), vm.find_loop(100));
+
+        vm.load(assemble("mov a 0\nloop: add a 1\njmp loop").unwrap().instructions).unwrap();
+        assert_eq!(None, vm.find_loop(100));
+        vm.load(assemble("noop\njmp -5").unwrap().instructions).unwrap();
+        assert_eq!(None, vm.find_loop(100));
+        assert_eq!(Status::Finished, vm.status());
+    }
+
+    #[test]
+    pub fn faults_on_overflow() {
+        let mut vm = VirtualMachine::new(RegisterSet::new().with("a", i128::MAX / 2 + 1));
+        vm.load(assemble("sub a 1\nmul a 2\nmul a 2\nnoop").unwrap().instructions).unwrap();
+        assert_eq!(Err(AocError::arithmetic(format!("{} * 2 overflows a 128-bit integer", i128::MAX - 1))), vm.run(10));
+        // The register keeps the last value that fit, and the machine stays stopped
+        assert_eq!((Status::Faulted, Some(i128::MAX - 1)), (vm.status(), vm.get_register_value("a")));
+        assert!(!vm.tick());
+
+        vm.reset();
+        assert_eq!((Status::Running, None), (vm.status(), vm.fault()));
+    }
+
+    #[test]
+    pub fn jumps_out_of_the_program() {
+        assert_eq!(None, Flow::Jump(JumpTarget::Relative(i128::MAX)).next_pc(1));
+        assert_eq!(None, Flow::Jump(JumpTarget::Relative(-2)).next_pc(1));
+        assert_eq!(Some(3), Flow::Jump(JumpTarget::Relative(2)).next_pc(1));
+
+        let mut vm = VirtualMachine::new(RegisterSet::new());
+        vm.load(assemble(&format!("noop\njmp +{}", i128::MAX)).unwrap().instructions).unwrap();
+        assert_eq!(Ok(Status::Finished), vm.run(10));
+    }
+
+    #[test]
+    pub fn gives_up_on_long_programs() {
+        let program = assemble("mov a 0\nloop: add a 1\njmp loop").unwrap();
+        let mut vm = VirtualMachine::new(RegisterSet::new().with("a", 0));
+        vm.load(program.instructions).unwrap();
+        assert_eq!(Err(AocError::parse("the program is still running after 100 cycle(s)")), vm.run(100));
+        assert_eq!(101, vm.get_cycle());
+
+        // A program that needs exactly the cycles it's given still stops, and `add` takes two
+        vm.load(assemble("mov a 0\nadd a 1").unwrap().instructions).unwrap();
+        assert_eq!(Ok(Status::Finished), vm.run(3));
+    }
+}
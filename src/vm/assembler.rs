@@ -0,0 +1,215 @@
+// Text programs for the VM, one instruction per line. `;` starts a comment, `name:` labels the instruction that
+// follows it, and operands can be separated by spaces or commas:
+//
+//     mov a, 3
+//     loop:
+//         sub a, 1    ; count down
+//         cmp a, 0
+//         jgt loop
+//         halt
+//
+// Jumps go to a label, an instruction index like `4`, or an offset from the jump like `-2` or `+3`.
+use std::collections::HashMap;
+use crate::common::parse_number;
+use crate::error::{AocError, AocResult};
+use crate::vm::{Condition, Instruction, JumpTarget, Operand};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+    // The source line of each instruction, for errors found after assembling
+    pub lines: Vec<usize>,
+}
+
+fn is_name(value: &str) -> bool {
+    value.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_register(value: &str) -> AocResult<String> {
+    if !is_name(value) {
+        return Err(AocError::parse(format!("expected a register, got `{}`", value)));
+    }
+    Ok(value.to_string())
+}
+
+fn parse_operand(value: &str) -> AocResult<Operand> {
+    if is_name(value) {
+        Ok(Operand::Register(value.to_string()))
+    } else {
+        Ok(Operand::Value(parse_number(value)?))
+    }
+}
+
+fn parse_target<F>(value: &str, resolve_label: F) -> AocResult<JumpTarget>
+    where F: Fn(&str) -> AocResult<usize> {
+    if value.starts_with(['+', '-']) {
+        Ok(JumpTarget::Relative(parse_number(value)?))
+    } else if is_name(value) {
+        Ok(JumpTarget::Absolute(resolve_label(value)?))
+    } else {
+        Ok(JumpTarget::Absolute(parse_number(value)?))
+    }
+}
+
+fn jump_condition(mnemonic: &str) -> Option<Condition> {
+    match mnemonic {
+        "jmp" => Some(Condition::Always),
+        "jeq" => Some(Condition::Equal),
+        "jne" => Some(Condition::NotEqual),
+        "jlt" => Some(Condition::Less),
+        "jle" => Some(Condition::LessOrEqual),
+        "jgt" => Some(Condition::Greater),
+        "jge" => Some(Condition::GreaterOrEqual),
+        _ => None
+    }
+}
+
+// A single instruction without its label or comment. `resolve_label` gives the index a jump's label refers to.
+pub fn parse_instruction<F>(line: &str, resolve_label: F) -> AocResult<Instruction>
+    where F: Fn(&str) -> AocResult<usize> {
+    let tokens: Vec<&str> = line.split(|c: char| c.is_whitespace() || c == ',').filter(|token| !token.is_empty()).collect();
+    let (&mnemonic, args) = tokens.split_first()
+        .ok_or_else(|| AocError::parse("expected an instruction"))?;
+    let expect_args = |count: usize| {
+        if args.len() != count {
+            return Err(AocError::parse(format!("`{}` takes {} operand(s), got {}", mnemonic, count, args.len())));
+        }
+        Ok(())
+    };
+
+    match mnemonic {
+        "noop" | "halt" => {
+            expect_args(0)?;
+            Ok(if mnemonic == "noop" { Instruction::Noop } else { Instruction::Halt })
+        }
+        "add" | "sub" | "mul" | "mov" => {
+            expect_args(2)?;
+            let (register, operand) = (parse_register(args[0])?, parse_operand(args[1])?);
+            Ok(match mnemonic {
+                "add" => Instruction::Add(register, operand),
+                "sub" => Instruction::Sub(register, operand),
+                "mul" => Instruction::Mul(register, operand),
+                _ => Instruction::Mov(register, operand)
+            })
+        }
+        "cmp" => {
+            expect_args(2)?;
+            Ok(Instruction::Cmp(parse_operand(args[0])?, parse_operand(args[1])?))
+        }
+        _ if jump_condition(mnemonic).is_some() => {
+            expect_args(1)?;
+            Ok(Instruction::Jump(jump_condition(mnemonic).unwrap(), parse_target(args[0], resolve_label)?))
+        }
+        // The register can be part of the name, like `addx 15`
+        _ if mnemonic.starts_with("add") => {
+            expect_args(1)?;
+            Ok(Instruction::Add(parse_register(&mnemonic[3..])?, parse_operand(args[0])?))
+        }
+        _ => Err(AocError::parse(format!("unknown instruction `{}`", line.trim())))
+    }
+}
+
+// Splits `name:` off the front of a line that's already had its comment removed
+fn split_label(line: &str) -> AocResult<(Option<&str>, &str)> {
+    match line.split_once(':') {
+        Some((label, rest)) => {
+            let label = label.trim();
+            if !is_name(label) {
+                return Err(AocError::parse(format!("invalid label `{}`", label)));
+            }
+            Ok((Some(label), rest.trim()))
+        }
+        None => Ok((None, line.trim()))
+    }
+}
+
+pub fn assemble(source: &str) -> AocResult<Program> {
+    // Labels can be jumped to before they're defined, so they're all found first
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    let mut instruction_lines: Vec<(usize, &str)> = vec![];
+    for (i, line) in source.lines().enumerate() {
+        let without_comment = line.split_once(';').map(|(code, _)| code).unwrap_or(line);
+        let (label, code) = split_label(without_comment).map_err(|error| error.at_line(i + 1))?;
+        if let Some(label) = label {
+            if labels.insert(label, instruction_lines.len()).is_some() {
+                return Err(AocError::parse(format!("label `{}` is defined more than once", label)).at_line(i + 1));
+            }
+        }
+        if !code.is_empty() {
+            instruction_lines.push((i + 1, code));
+        }
+    }
+
+    let resolve_label = |label: &str| labels.get(label).copied()
+        .ok_or_else(|| AocError::parse(format!("unknown label `{}`", label)));
+    let instructions = instruction_lines.iter()
+        .map(|&(line_number, code)| parse_instruction(code, resolve_label).map_err(|error| error.at_line(line_number)))
+        .collect::<AocResult<Vec<Instruction>>>()?;
+
+    Ok(Program {
+        instructions,
+        lines: instruction_lines.iter().map(|&(line_number, _)| line_number).collect(),
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::vm::{RegisterSet, Status, VirtualMachine};
+    use super::*;
+
+    fn parse_line(line: &str) -> AocResult<Instruction> {
+        parse_instruction(line, |label| Err(AocError::parse(format!("unknown label `{}`", label))))
+    }
+
+    const COUNTDOWN: &str = "mov a, 3      ; a counts down to 0
+mov total 0
+loop:
+    add total, a
+    sub a 1
+    cmp a, 0
+    jgt loop
+done: halt";
+
+    #[test]
+    pub fn assembles_programs() {
+        let program = assemble(COUNTDOWN).unwrap();
+        assert_eq!(vec![1, 2, 4, 5, 6, 7, 8], program.lines);
+        assert_eq!(vec![
+            Instruction::Mov("a".to_string(), Operand::Value(3)),
+            Instruction::Mov("total".to_string(), Operand::Value(0)),
+            Instruction::Add("total".to_string(), Operand::Register("a".to_string())),
+            Instruction::Sub("a".to_string(), Operand::Value(1)),
+            Instruction::Cmp(Operand::Register("a".to_string()), Operand::Value(0)),
+            Instruction::Jump(Condition::Greater, JumpTarget::Absolute(2)),
+            Instruction::Halt,
+        ], program.instructions);
+
+        assert_eq!(Ok(Instruction::Add("x".to_string(), Operand::Value(-11))), parse_line("addx -11"));
+        assert_eq!(Ok(Instruction::Jump(Condition::NotEqual, JumpTarget::Relative(-2))), parse_line("jne -2"));
+        for instruction in &program.instructions {
+            assert_eq!(Ok(instruction.clone()), parse_line(&instruction.to_string()));
+        }
+    }
+
+    #[test]
+    pub fn reports_errors() {
+        assert_eq!(Err(AocError::parse("unknown label `nowhere`").at_line(2)), assemble("noop\njmp nowhere"));
+        assert_eq!(Err(AocError::parse("`mov` takes 2 operand(s), got 1").at_line(1)), assemble("mov a"));
+        assert_eq!(Err(AocError::parse("label `a` is defined more than once").at_line(3)), assemble("a: noop\nnoop\na:"));
+        assert_eq!(Err(AocError::parse("unknown instruction `jump 3`")), parse_line("jump 3"));
+        assert_eq!(Err(AocError::parse("expected a register, got `5`")), parse_line("mov 5 a"));
+    }
+
+    #[test]
+    pub fn runs_jumps() {
+        let program = assemble(COUNTDOWN).unwrap();
+        let mut vm = VirtualMachine::new(RegisterSet::new().with("a", 0).with("total", 0));
+        vm.load(program.instructions).unwrap();
+        assert_eq!(Ok(Status::Halted), vm.run(100));
+        assert_eq!(Some(6), vm.get_register_value("total"));
+        assert_eq!(Some(0), vm.get_register_value("a"));
+        // Two movs, three passes through the loop, and the final halt
+        assert_eq!(2 + 3 * (2 + 2 + 1 + 1) + 1, vm.get_cycle());
+    }
+}
@@ -0,0 +1,369 @@
+// Runs a program on the VM an instruction at a time, stopping at breakpoints and keeping a history of what ran.
+// With tracing on it also records the machine's state during every cycle, which can be written out as CSV.
+use crate::error::{AocError, AocResult};
+use crate::vm::{still_running, Condition, Instruction, Register, RegisterSet, Status, VirtualMachine};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Breakpoint {
+    // Stops before the instruction that would run during this cycle
+    Cycle(usize),
+    // Stops before the instruction at this index runs
+    Instruction(usize),
+    // Stops before the next instruction once the register compares to the value like this
+    Register(Register, Condition, i128),
+}
+
+impl Breakpoint {
+    // `cycle=N`, `pc=N`, or a register compared to a value with one of `=`, `!=`, `<`, `<=`, `>` and `>=`, like `x>=5`
+    pub fn parse(text: &str, register_set: &RegisterSet) -> AocResult<Breakpoint> {
+        let operator_start = text.find(['=', '!', '<', '>'])
+            .ok_or_else(|| AocError::parse(format!("expected a comparison in breakpoint `{}`", text)))?;
+        let (name, rest) = text.split_at(operator_start);
+        let (condition, value) = [
+            ("!=", Condition::NotEqual),
+            ("<=", Condition::LessOrEqual),
+            (">=", Condition::GreaterOrEqual),
+            ("=", Condition::Equal),
+            ("<", Condition::Less),
+            (">", Condition::Greater),
+        ].into_iter()
+            .find_map(|(operator, condition)| rest.strip_prefix(operator).map(|value| (condition, value)))
+            .ok_or_else(|| AocError::parse(format!("unknown comparison in breakpoint `{}`", text)))?;
+        let position = |value: &str| match (condition, value.parse()) {
+            (Condition::Equal, Ok(position)) => Ok(position),
+            _ => Err(AocError::parse(format!("expected `{}=N` with a positive number, got `{}`", name, text)))
+        };
+        match name {
+            "cycle" => Ok(Breakpoint::Cycle(position(value)?)),
+            "pc" => Ok(Breakpoint::Instruction(position(value)?)),
+            _ => {
+                let value = value.parse().map_err(|_| AocError::parse(format!("expected a number, got `{}`", value)))?;
+                Ok(Breakpoint::Register(register_set.check(name)?, condition, value))
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StopReason {
+    Stepped,
+    // The index of the breakpoint that was hit
+    Breakpoint(usize),
+    Halted,
+    // The program counter moved outside the program
+    Finished,
+    // An instruction's result didn't fit, as the machine's `fault` says
+    Faulted,
+}
+
+// An instruction that ran, with the machine as it was just before
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Step {
+    pub pc: usize,
+    pub instruction: Instruction,
+    pub start_cycle: usize,
+    // In the order the registers were declared
+    pub registers: Vec<i128>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TraceRow {
+    pub cycle: usize,
+    pub pc: usize,
+    pub instruction: Instruction,
+    pub registers: Vec<i128>,
+}
+
+pub struct Debugger {
+    vm: VirtualMachine,
+    breakpoints: Vec<Option<Breakpoint>>,
+    history: Vec<Step>,
+    trace: Option<Vec<TraceRow>>,
+}
+
+impl Debugger {
+    // Takes over a machine with its program already loaded
+    pub fn new(vm: VirtualMachine) -> Debugger {
+        Debugger {
+            vm,
+            breakpoints: vec![],
+            history: vec![],
+            trace: None,
+        }
+    }
+
+    // Records a row for every cycle from now on
+    pub fn with_trace(mut self) -> Debugger {
+        self.trace = Some(vec![]);
+        self
+    }
+
+    // Returns the breakpoint's index, for `remove_breakpoint` and `StopReason::Breakpoint`
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(Some(breakpoint));
+        self.breakpoints.len() - 1
+    }
+
+    pub fn remove_breakpoint(&mut self, index: usize) {
+        if let Some(breakpoint) = self.breakpoints.get_mut(index) {
+            *breakpoint = None;
+        }
+    }
+
+    pub fn vm(&self) -> &VirtualMachine {
+        &self.vm
+    }
+
+    // None once the program has halted or run off either end
+    pub fn pc(&self) -> Option<usize> {
+        Some(self.vm.get_pc()).filter(|_| self.vm.status() == Status::Running)
+    }
+
+    pub fn history(&self) -> &[Step] {
+        &self.history
+    }
+
+    pub fn trace(&self) -> Option<&[TraceRow]> {
+        self.trace.as_deref()
+    }
+
+    fn hit_breakpoint(&self) -> Option<usize> {
+        let pc = self.pc()?;
+        let next_cycles = self.vm.get_cycle() + 1..=self.vm.get_cycle() + self.vm.program()[pc].cycles();
+        self.breakpoints.iter().position(|breakpoint| match breakpoint {
+            Some(Breakpoint::Cycle(cycle)) => next_cycles.contains(cycle),
+            Some(Breakpoint::Instruction(index)) => *index == pc,
+            Some(Breakpoint::Register(register, condition, value)) => {
+                condition.holds(Some(self.vm.value(*register).cmp(value)))
+            }
+            None => false
+        })
+    }
+
+    // Runs the next instruction, whatever breakpoints there are
+    pub fn step(&mut self) -> StopReason {
+        let pc = match self.pc() {
+            Some(pc) => pc,
+            None => return Debugger::stop_reason(self.vm.status())
+        };
+        let instruction = self.vm.program()[pc].clone();
+        self.history.push(Step {
+            pc,
+            instruction: instruction.clone(),
+            start_cycle: self.vm.get_cycle() + 1,
+            registers: self.vm.registers().to_vec(),
+        });
+
+        let trace = &mut self.trace;
+        self.vm.step(|vm| {
+            if let Some(rows) = trace.as_mut() {
+                rows.push(TraceRow {
+                    cycle: vm.get_cycle(),
+                    pc,
+                    instruction: instruction.clone(),
+                    registers: vm.registers().to_vec(),
+                });
+            }
+        });
+        Debugger::stop_reason(self.vm.status())
+    }
+
+    fn stop_reason(status: Status) -> StopReason {
+        match status {
+            Status::Running => StopReason::Stepped,
+            Status::Halted => StopReason::Halted,
+            Status::Finished => StopReason::Finished,
+            Status::Faulted => StopReason::Faulted
+        }
+    }
+
+    // Runs until a breakpoint is hit or the program stops. A breakpoint at the current position doesn't stop it again.
+    pub fn resume(&mut self) -> StopReason {
+        loop {
+            match self.step() {
+                StopReason::Stepped => (),
+                stopped => return stopped
+            }
+            if let Some(index) = self.hit_breakpoint() {
+                return StopReason::Breakpoint(index);
+            }
+        }
+    }
+
+    // Like `resume`, but stops before the first instruction too if a breakpoint is already hit there
+    pub fn run(&mut self) -> StopReason {
+        match self.hit_breakpoint() {
+            Some(index) => StopReason::Breakpoint(index),
+            None => self.resume()
+        }
+    }
+}
+
+// What `trace_program` saw: the table of cycles, and a line for every breakpoint that stopped the program
+pub struct Trace {
+    pub csv: String,
+    pub cycles: usize,
+    pub stops: Vec<String>,
+}
+
+fn format_registers(register_set: &RegisterSet, values: &[i128]) -> String {
+    register_set.names().iter().zip(values)
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Runs the loaded program to the end with tracing on, failing if it takes more than `max_cycles`. Each breakpoint, given
+// as `Breakpoint::parse` reads it, stops the program once, to note the machine and the instruction that ran last.
+pub fn trace_program(vm: VirtualMachine, breakpoints: &[String], max_cycles: usize) -> AocResult<Trace> {
+    let mut debugger = Debugger::new(vm).with_trace();
+    for text in breakpoints {
+        let breakpoint = Breakpoint::parse(text, debugger.vm().register_set())?;
+        debugger.add_breakpoint(breakpoint);
+    }
+    // Stops the program before it would start a cycle past the limit
+    let limit = debugger.add_breakpoint(Breakpoint::Cycle(debugger.vm().get_cycle().saturating_add(max_cycles) + 1));
+
+    let mut stops = vec![];
+    loop {
+        match debugger.run() {
+            StopReason::Breakpoint(index) if index == limit => return Err(still_running(max_cycles)),
+            StopReason::Breakpoint(index) => {
+                let register_set = debugger.vm().register_set();
+                let mut stop = format!("`{}` after cycle {}: {}", breakpoints[index], debugger.vm().get_cycle(),
+                                       format_registers(register_set, debugger.vm().registers()));
+                if let Some(last) = debugger.history().last() {
+                    stop += &format!(", after `{}` at pc {} from cycle {} with {}", last.instruction, last.pc,
+                                     last.start_cycle, format_registers(register_set, &last.registers));
+                }
+                stops.push(stop);
+                debugger.remove_breakpoint(index);
+            }
+            StopReason::Faulted => return Err(debugger.vm().fault().cloned().expect("a faulted machine keeps its fault")),
+            _ => break
+        }
+    }
+
+    let rows = debugger.trace().expect("tracing was turned on");
+    Ok(Trace {
+        csv: trace_to_csv(debugger.vm().register_set(), rows),
+        cycles: rows.len(),
+        stops,
+    })
+}
+
+// One row per cycle, with a column for every register
+pub fn trace_to_csv(register_set: &RegisterSet, rows: &[TraceRow]) -> String {
+    let header = ["cycle", "pc", "instruction"].into_iter().chain(register_set.names().iter().map(String::as_str));
+    let mut lines = vec![header.collect::<Vec<&str>>().join(",")];
+    for row in rows {
+        let mut columns = vec![row.cycle.to_string(), row.pc.to_string(), row.instruction.to_string()];
+        columns.extend(row.registers.iter().map(|value| value.to_string()));
+        lines.push(columns.join(","));
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::vm::assembler::assemble;
+    use super::*;
+
+    const PROGRAM: &str = "mov a 3
+loop: sub a 1
+cmp a 0
+jgt loop
+halt";
+
+    fn load(source: &str, register_set: RegisterSet) -> VirtualMachine {
+        let mut vm = VirtualMachine::new(register_set);
+        vm.load(assemble(source).unwrap().instructions).unwrap();
+        vm
+    }
+
+    #[test]
+    pub fn stops_at_breakpoints() {
+        let vm = load(PROGRAM, RegisterSet::new().with("a", 0));
+        let a = vm.register("a").unwrap();
+        let mut debugger = Debugger::new(vm);
+        let at_loop = debugger.add_breakpoint(Breakpoint::Instruction(1));
+        assert_eq!(StopReason::Breakpoint(at_loop), debugger.run());
+        assert_eq!(Some(1), debugger.pc());
+        assert_eq!(StopReason::Breakpoint(at_loop), debugger.resume());
+        assert_eq!(Some(2), debugger.vm().get_register_value("a"));
+
+        debugger.remove_breakpoint(at_loop);
+        let low_a = debugger.add_breakpoint(Breakpoint::Register(a, Condition::LessOrEqual, 1));
+        assert_eq!(StopReason::Breakpoint(low_a), debugger.resume());
+        assert_eq!(Some(2), debugger.pc());
+
+        debugger.remove_breakpoint(low_a);
+        assert_eq!(StopReason::Stepped, debugger.step());
+        assert_eq!(StopReason::Halted, debugger.resume());
+        assert_eq!(StopReason::Halted, debugger.step());
+        assert_eq!(None, debugger.pc());
+
+        let pcs: Vec<usize> = debugger.history().iter().map(|step| step.pc).collect();
+        assert_eq!(vec![0, 1, 2, 3, 1, 2, 3, 1, 2, 3, 4], pcs);
+        // The third `sub`, just before it took `a` down to 0
+        assert_eq!(vec![1], debugger.history()[7].registers);
+    }
+
+    #[test]
+    pub fn stops_at_cycles() {
+        let mut debugger = Debugger::new(load(PROGRAM, RegisterSet::new().with("a", 0)));
+        let breakpoint = debugger.add_breakpoint(Breakpoint::Cycle(3));
+        assert_eq!(StopReason::Breakpoint(breakpoint), debugger.run());
+        // `mov` took cycle 1, and the `sub` about to run takes cycles 2 and 3
+        assert_eq!(Some(1), debugger.pc());
+        assert_eq!(1, debugger.vm().get_cycle());
+        assert_eq!(StopReason::Finished, Debugger::new(load("mov a 3", RegisterSet::new().with("a", 0))).resume());
+    }
+
+    #[test]
+    pub fn records_trace() {
+        let vm = load("addx 3\nnoop\nmov y x", RegisterSet::new().with("x", 1).with("y", 0));
+        let mut debugger = Debugger::new(vm).with_trace();
+        assert_eq!(StopReason::Finished, debugger.resume());
+        // Rows show registers during each cycle, so `y` is only set after the last one
+        assert_eq!("cycle,pc,instruction,x,y
+1,0,add x 3,1,0
+2,0,add x 3,1,0
+3,1,noop,4,0
+4,2,mov y x,4,0
+", trace_to_csv(debugger.vm().register_set(), debugger.trace().unwrap()));
+        assert_eq!(Some(4), debugger.vm().get_register_value("y"));
+    }
+
+    #[test]
+    pub fn parses_breakpoints() {
+        let register_set = RegisterSet::new().with("x", 1);
+        let x = register_set.find("x").unwrap();
+        assert_eq!(Ok(Breakpoint::Cycle(20)), Breakpoint::parse("cycle=20", &register_set));
+        assert_eq!(Ok(Breakpoint::Instruction(3)), Breakpoint::parse("pc=3", &register_set));
+        assert_eq!(Ok(Breakpoint::Register(x, Condition::GreaterOrEqual, -5)), Breakpoint::parse("x>=-5", &register_set));
+        assert_eq!(Ok(Breakpoint::Register(x, Condition::NotEqual, 1)), Breakpoint::parse("x!=1", &register_set));
+        assert_eq!(Err(AocError::parse("undeclared register `y`")), Breakpoint::parse("y<2", &register_set));
+        assert!(Breakpoint::parse("cycle>20", &register_set).is_err());
+        assert!(Breakpoint::parse("x", &register_set).is_err());
+        assert!(Breakpoint::parse("x=>1", &register_set).is_err());
+    }
+
+    #[test]
+    pub fn traces_programs() {
+        let vm = load(PROGRAM, RegisterSet::new().with("a", 0));
+        let breakpoints = ["a=1".to_string(), "cycle=3".to_string()];
+        let trace = trace_program(vm, &breakpoints, 100).unwrap();
+        // Each breakpoint stops the program once, and `sub` takes two cycles
+        assert_eq!(vec![
+            "`cycle=3` after cycle 1: a=3, after `mov a 3` at pc 0 from cycle 1 with a=0",
+            "`a=1` after cycle 7: a=1, after `sub a 1` at pc 1 from cycle 6 with a=2",
+        ], trace.stops);
+        assert_eq!(trace.cycles + 1, trace.csv.lines().count());
+
+        let vm = load("mul a 2", RegisterSet::new().with("a", i128::MAX));
+        assert!(trace_program(vm, &[], 100).is_err());
+        let vm = load(PROGRAM, RegisterSet::new().with("a", 0));
+        assert_eq!(Some(AocError::parse("the program is still running after 5 cycle(s)")), trace_program(vm, &[], 5).err());
+    }
+}
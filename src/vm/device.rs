@@ -0,0 +1,200 @@
+// Hardware that watches the VM while it runs. Attached devices tick during every cycle, after the cycle has started and
+// before the instruction running then writes its result, and read whatever machine state they need.
+use crate::error::{AocError, AocResult};
+use crate::solution::Answer;
+use crate::vm::VirtualMachine;
+
+pub trait Device {
+    // Failing faults the machine
+    fn tick(&mut self, vm: &VirtualMachine) -> AocResult<()>;
+
+    // The registers the device reads, which the machine checks it has when the device is attached
+    fn registers(&self) -> Vec<&str> {
+        vec![]
+    }
+
+    // Called when the machine is reset, to forget everything seen so far
+    fn reset(&mut self);
+
+    // What the device has produced so far
+    fn output(&self) -> Answer;
+}
+
+// A screen drawn one pixel per cycle, left to right and top to bottom. The pixel is lit when it's within one column of
+// the sprite, whose middle is at the value of the register.
+pub struct Crt {
+    register: String,
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+}
+
+impl Crt {
+    pub const WIDTH: usize = 40;
+    pub const HEIGHT: usize = 6;
+
+    pub fn new(register: &str) -> Crt {
+        Crt::with_size(register, Crt::WIDTH, Crt::HEIGHT)
+    }
+
+    pub fn with_size(register: &str, width: usize, height: usize) -> Crt {
+        Crt {
+            register: register.to_string(),
+            width,
+            height,
+            pixels: Vec::with_capacity(width * height),
+        }
+    }
+
+    // One line per row, with `#` for lit pixels. Rows the beam hasn't reached yet are left out.
+    pub fn picture(&self) -> String {
+        self.pixels.chunks(self.width)
+            .map(|row| row.iter().map(|&lit| if lit { '#' } else { '.' }).collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl Device for Crt {
+    fn tick(&mut self, vm: &VirtualMachine) -> AocResult<()> {
+        // Once the screen is full, later cycles have nowhere to draw
+        if self.pixels.len() == self.width * self.height {
+            return Ok(());
+        }
+        let column = (self.pixels.len() % self.width) as i128;
+        let sprite = vm.get_register_value(&self.register).expect("registers are checked when devices are attached");
+        // Only a sprite far off the left of the screen is too far away to subtract
+        self.pixels.push(column.checked_sub(sprite).is_some_and(|distance| distance.unsigned_abs() <= 1));
+        Ok(())
+    }
+
+    fn registers(&self) -> Vec<&str> {
+        vec![&self.register]
+    }
+
+    fn reset(&mut self) {
+        self.pixels.clear();
+    }
+
+    fn output(&self) -> Answer {
+        Answer::Text(self.picture())
+    }
+}
+
+// Adds up the cycle number times the value of the register, during the first cycle given and every `interval` after it
+pub struct SignalSampler {
+    register: String,
+    first_cycle: usize,
+    interval: usize,
+    total: i128,
+}
+
+impl SignalSampler {
+    pub fn new(register: &str, first_cycle: usize, interval: usize) -> SignalSampler {
+        SignalSampler {
+            register: register.to_string(),
+            first_cycle,
+            interval,
+            total: 0,
+        }
+    }
+
+    pub fn total(&self) -> i128 {
+        self.total
+    }
+}
+
+impl Device for SignalSampler {
+    fn tick(&mut self, vm: &VirtualMachine) -> AocResult<()> {
+        let cycle = vm.get_cycle();
+        if cycle >= self.first_cycle && (cycle - self.first_cycle).is_multiple_of(self.interval) {
+            let value = vm.get_register_value(&self.register).expect("registers are checked when devices are attached");
+            let strength = (cycle as i128).checked_mul(value)
+                .ok_or_else(|| AocError::arithmetic(format!("signal strength {} * {} overflows a 128-bit integer", cycle, value)))?;
+            self.total = self.total.checked_add(strength)
+                .ok_or_else(|| AocError::arithmetic(format!("{} + {} overflows a 128-bit integer", self.total, strength)))?;
+        }
+        Ok(())
+    }
+
+    fn registers(&self) -> Vec<&str> {
+        vec![&self.register]
+    }
+
+    fn reset(&mut self) {
+        self.total = 0;
+    }
+
+    fn output(&self) -> Answer {
+        Answer::Number(self.total())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::vm::assembler::assemble;
+    use crate::vm::{RegisterSet, Status};
+    use super::*;
+
+    // Remembers the cycles where a register was odd
+    struct OddCycles(Vec<usize>);
+
+    impl Device for OddCycles {
+        fn tick(&mut self, vm: &VirtualMachine) -> AocResult<()> {
+            if vm.get_register_value("a").unwrap() % 2 != 0 {
+                self.0.push(vm.get_cycle());
+            }
+            Ok(())
+        }
+
+        fn registers(&self) -> Vec<&str> {
+            vec!["a"]
+        }
+
+        fn reset(&mut self) {
+            self.0.clear();
+        }
+
+        fn output(&self) -> Answer {
+            Answer::Text(format!("{:?}", self.0))
+        }
+    }
+
+    #[test]
+    pub fn ticks_devices() {
+        let mut vm = VirtualMachine::new(RegisterSet::new().with("a", 1).with("x", 1));
+        vm.load(assemble("mov a 0\nadd a 1\nadd a 2\nmov x 3").unwrap().instructions).unwrap();
+        let crt = vm.attach(Box::new(Crt::with_size("x", 4, 2))).unwrap();
+        let sampler = vm.attach(Box::new(SignalSampler::new("a", 2, 2))).unwrap();
+        let odd = vm.attach(Box::new(OddCycles(vec![]))).unwrap();
+        assert_eq!(Err(AocError::parse("undeclared register `y`")), vm.attach(Box::new(Crt::new("y"))));
+        vm.run(10).unwrap();
+
+        // x is 1 until the last cycle has finished, and the program ends before the screen is full
+        assert_eq!(Answer::Text("###.\n##".to_string()), vm.device_output(crt));
+        // Samples during cycles 2, 4 and 6, while a is 0, 1 and 3
+        assert_eq!(Answer::Number(22), vm.device_output(sampler));
+        assert_eq!(Answer::Text("[1, 4, 5, 6]".to_string()), vm.device_output(odd));
+
+        vm.reset();
+        vm.run_for(1).unwrap();
+        assert_eq!(Answer::Text("#".to_string()), vm.device_output(crt));
+        assert_eq!(Answer::Number(0), vm.device_output(sampler));
+    }
+
+    #[test]
+    pub fn faults_on_overflow() {
+        // Sprites at either end of the range are just far from every pixel
+        let mut vm = VirtualMachine::new(RegisterSet::new().with("x", i128::MIN));
+        vm.load(assemble("noop\nmov x 170141183460469231731687303715884105727\nnoop").unwrap().instructions).unwrap();
+        let crt = vm.attach(Box::new(Crt::with_size("x", 4, 1))).unwrap();
+        let sampler = vm.attach(Box::new(SignalSampler::new("x", 3, 1))).unwrap();
+        assert_eq!(
+            Err(AocError::arithmetic(format!("signal strength 3 * {} overflows a 128-bit integer", i128::MAX))),
+            vm.run(10)
+        );
+        assert_eq!((Status::Faulted, 3), (vm.status(), vm.get_cycle()));
+        assert_eq!(Answer::Text("...".to_string()), vm.device_output(crt));
+        assert_eq!(Answer::Number(0), vm.device_output(sampler));
+    }
+}